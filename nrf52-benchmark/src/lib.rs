@@ -0,0 +1,315 @@
+//! Benchmark result lines
+//!
+//! Benchmark results are reported as lines of comma separated values,
+//!
+//! ```text
+//! bench,<name>,<bytes>,<iterations>,<cycles>
+//! ```
+//!
+//! where `cycles` is the total number of CPU cycles spent on all iterations.
+//! The lines are written both to the defmt log and to the UART, the parser
+//! skips anything in front of the `bench` tag, such as log timestamps.
+//!
+//! The firmware formats the lines with [`LineBuffer`] through `core::fmt`.
+//! Host tools push the received bytes into a [`LineBuffer`] and parse each
+//! complete line with [`Measurement::parse`].
+
+#![no_std]
+
+use core::fmt;
+
+/// Tag starting each result line
+pub const TAG: &str = "bench";
+
+/// Result of a benchmark
+#[derive(Clone, Debug, PartialEq)]
+pub struct Measurement<'a> {
+    /// Name of the operation
+    pub name: &'a str,
+    /// Number of bytes processed in each iteration
+    pub bytes: usize,
+    /// Number of iterations
+    pub iterations: u32,
+    /// Total number of cycles for all iterations
+    pub cycles: u32,
+}
+
+impl<'a> Measurement<'a> {
+    /// Average number of cycles per iteration
+    pub fn cycles_per_iteration(&self) -> u32 {
+        self.cycles.checked_div(self.iterations).unwrap_or(0)
+    }
+
+    /// Average number of cycles per byte
+    pub fn cycles_per_byte(&self) -> u32 {
+        self.cycles_per_iteration()
+            .checked_div(self.bytes as u32)
+            .unwrap_or(0)
+    }
+
+    /// Parse a result line
+    ///
+    /// Returns `None` if the line doesn't hold a benchmark result.
+    pub fn parse(line: &'a str) -> Option<Self> {
+        let start = line.find(TAG)?;
+        let mut fields = line[start..].trim_end().split(',');
+        if fields.next()? != TAG {
+            return None;
+        }
+        let name = fields.next()?;
+        if name.is_empty() {
+            return None;
+        }
+        let bytes = fields.next()?.parse().ok()?;
+        let iterations = fields.next()?.parse().ok()?;
+        let cycles = fields.next()?.parse().ok()?;
+        if fields.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            name,
+            bytes,
+            iterations,
+            cycles,
+        })
+    }
+}
+
+impl<'a> fmt::Display for Measurement<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{}",
+            TAG, self.name, self.bytes, self.iterations, self.cycles
+        )
+    }
+}
+
+/// Line oriented fixed size buffer
+///
+/// Collects formatted output or received data until a complete line is
+/// available.
+pub struct LineBuffer<'a> {
+    buffer: &'a mut [u8],
+    length: usize,
+}
+
+impl<'a> LineBuffer<'a> {
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Self { buffer, length: 0 }
+    }
+
+    /// The buffered data
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer[..self.length]
+    }
+
+    /// Append received data
+    ///
+    /// Returns the number of bytes stored, which is less than the length of
+    /// `data` when the buffer is full.
+    pub fn push(&mut self, data: &[u8]) -> usize {
+        let count = data.len().min(self.buffer.len() - self.length);
+        self.buffer[self.length..self.length + count].copy_from_slice(&data[..count]);
+        self.length += count;
+        count
+    }
+
+    /// The first complete line, without the line ending
+    ///
+    /// Returns `None` if no complete line has been buffered or if the line
+    /// isn't valid UTF-8.
+    pub fn line(&self) -> Option<&str> {
+        let end = self.line_end()?;
+        let line = core::str::from_utf8(&self.buffer[..end]).ok()?;
+        Some(line.trim_end_matches('\r'))
+    }
+
+    /// Remove the first complete line from the buffer
+    ///
+    /// Returns false if no complete line has been buffered.
+    pub fn consume_line(&mut self) -> bool {
+        match self.line_end() {
+            Some(end) => {
+                self.buffer.copy_within(end + 1..self.length, 0);
+                self.length -= end + 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove all data from the buffer
+    pub fn clear(&mut self) {
+        self.length = 0;
+    }
+
+    fn line_end(&self) -> Option<usize> {
+        self.as_bytes().iter().position(|&b| b == b'\n')
+    }
+}
+
+impl<'a> fmt::Write for LineBuffer<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.length + s.len();
+        if end > self.buffer.len() {
+            return Err(fmt::Error);
+        }
+        self.buffer[self.length..end].copy_from_slice(s.as_bytes());
+        self.length = end;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::fmt::Write;
+    use std::string::ToString;
+
+    #[test]
+    fn parse_valid_line() {
+        let measurement = Measurement::parse("bench,ccm-star-encrypt,16,100,123456").unwrap();
+        assert_eq!(
+            measurement,
+            Measurement {
+                name: "ccm-star-encrypt",
+                bytes: 16,
+                iterations: 100,
+                cycles: 123456,
+            }
+        );
+        assert_eq!(measurement.cycles_per_iteration(), 1234);
+        assert_eq!(measurement.cycles_per_byte(), 77);
+    }
+
+    #[test]
+    fn parse_skips_prefix() {
+        let expected = Measurement {
+            name: "aes-ecb",
+            bytes: 32,
+            iterations: 100,
+            cycles: 5000,
+        };
+        assert_eq!(
+            Measurement::parse("0.001234 INFO bench,aes-ecb,32,100,5000"),
+            Some(expected.clone())
+        );
+        assert_eq!(
+            Measurement::parse("17 bench,aes-ecb,32,100,5000\r\n"),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn parse_rejects_field_count() {
+        assert_eq!(Measurement::parse("bench,aes-ecb,32,100"), None);
+        assert_eq!(Measurement::parse("bench,aes-ecb,32,100,5000,1"), None);
+        assert_eq!(Measurement::parse("bench"), None);
+        assert_eq!(Measurement::parse("benchmark,aes-ecb,32,100,5000"), None);
+        assert_eq!(Measurement::parse("~ benchmark done ~"), None);
+    }
+
+    #[test]
+    fn parse_rejects_empty_name() {
+        assert_eq!(Measurement::parse("bench,,32,100,5000"), None);
+    }
+
+    #[test]
+    fn parse_rejects_non_numeric() {
+        assert_eq!(Measurement::parse("bench,aes-ecb,x,100,5000"), None);
+        assert_eq!(Measurement::parse("bench,aes-ecb,32,-1,5000"), None);
+        assert_eq!(Measurement::parse("bench,aes-ecb,32,100,5e3"), None);
+        assert_eq!(Measurement::parse("bench,aes-ecb,32,100,"), None);
+    }
+
+    #[test]
+    fn zero_iterations() {
+        let measurement = Measurement::parse("bench,aes-ecb,0,0,5000").unwrap();
+        assert_eq!(measurement.cycles_per_iteration(), 0);
+        assert_eq!(measurement.cycles_per_byte(), 0);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let measurement = Measurement {
+            name: "ccm-star-decrypt",
+            bytes: 96,
+            iterations: 100,
+            cycles: 4_294_967_295,
+        };
+        let line = measurement.to_string();
+        assert_eq!(line, "bench,ccm-star-decrypt,96,100,4294967295");
+        assert_eq!(Measurement::parse(&line), Some(measurement));
+    }
+
+    #[test]
+    fn format_into_buffer() {
+        let measurement = Measurement {
+            name: "aes-ctr",
+            bytes: 64,
+            iterations: 100,
+            cycles: 9000,
+        };
+        let mut buffer = [0u8; 64];
+        let mut line = LineBuffer::new(&mut buffer);
+        writeln!(line, "{}", measurement).unwrap();
+        assert_eq!(line.as_bytes(), b"bench,aes-ctr,64,100,9000\n");
+        assert_eq!(
+            line.line().and_then(Measurement::parse),
+            Some(measurement.clone())
+        );
+
+        let mut buffer = [0u8; 16];
+        let mut line = LineBuffer::new(&mut buffer);
+        assert!(writeln!(line, "{}", measurement).is_err());
+    }
+
+    #[test]
+    fn line_split_across_pushes() {
+        let mut buffer = [0u8; 64];
+        let mut lines = LineBuffer::new(&mut buffer);
+        assert_eq!(lines.push(b"INFO bench,aes-c"), 16);
+        assert_eq!(lines.line(), None);
+        assert!(!lines.consume_line());
+        lines.push(b"bc,16,100,");
+        assert_eq!(lines.line(), None);
+        lines.push(b"2400\r\nbench,aes-ecb,16");
+        assert_eq!(
+            lines.line().and_then(Measurement::parse),
+            Some(Measurement {
+                name: "aes-cbc",
+                bytes: 16,
+                iterations: 100,
+                cycles: 2400,
+            })
+        );
+        assert!(lines.consume_line());
+        assert_eq!(lines.line(), None);
+        lines.push(b",100,1600\n");
+        assert_eq!(
+            lines.line().and_then(Measurement::parse),
+            Some(Measurement {
+                name: "aes-ecb",
+                bytes: 16,
+                iterations: 100,
+                cycles: 1600,
+            })
+        );
+        assert!(lines.consume_line());
+        assert!(lines.as_bytes().is_empty());
+    }
+
+    #[test]
+    fn push_full_buffer() {
+        let mut buffer = [0u8; 8];
+        let mut lines = LineBuffer::new(&mut buffer);
+        assert_eq!(lines.push(b"bench,aes"), 8);
+        assert_eq!(lines.push(b"x"), 0);
+        lines.clear();
+        assert_eq!(lines.push(b"x\n"), 2);
+        assert_eq!(lines.line(), Some("x"));
+    }
+}
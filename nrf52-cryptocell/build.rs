@@ -1,13 +1,164 @@
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Directory holding the nrf_cc310 library, `external/nrf_cc310` in the SDK
+const ENV_CC310_DIR: &str = "NRF_CC310_DIR";
+/// Root of an nRF5 SDK installation
+const ENV_SDK_ROOT: &str = "NRF5_SDK_ROOT";
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    eprintln!();
+    eprintln!("The nrf_cc310 library is provided by Nordic in the nRF5 SDK,");
+    eprintln!("https://www.nordicsemi.com/Software-and-tools/Software/nRF5-SDK");
+    eprintln!();
+    eprintln!("Either copy the directory `external/nrf_cc310` from the SDK into");
+    eprintln!(
+        "the nrf52-cryptocell crate directory, set {} to the",
+        ENV_SDK_ROOT
+    );
+    eprintln!(
+        "root of the SDK or set {} to the nrf_cc310 directory.",
+        ENV_CC310_DIR
+    );
+    process::exit(1);
+}
+
+fn feature(name: &str) -> bool {
+    env::var_os(format!("CARGO_FEATURE_{}", name)).is_some()
+}
+
+/// Find the nrf_cc310 directory
+fn library_directory() -> PathBuf {
+    if let Some(path) = env::var_os(ENV_CC310_DIR) {
+        return PathBuf::from(path);
+    }
+    if let Some(path) = env::var_os(ENV_SDK_ROOT) {
+        return PathBuf::from(path).join("external").join("nrf_cc310");
+    }
+    PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("nrf_cc310")
+}
+
+/// Select hard or soft float variant, from features or the target
+fn float_abi() -> &'static str {
+    match (feature("HARD_FLOAT"), feature("SOFT_FLOAT")) {
+        (true, true) => fail("The features hard-float and soft-float are mutually exclusive"),
+        (true, false) => "hard-float",
+        (false, true) => "soft-float",
+        (false, false) => {
+            if env::var("TARGET").unwrap().ends_with("eabihf") {
+                "hard-float"
+            } else {
+                "soft-float"
+            }
+        }
+    }
+}
+
+/// Parse the version from a library file name, `libnrf_cc310_<version>.a`
+fn parse_version(file_name: &str) -> Option<(Vec<u32>, String)> {
+    let name = file_name
+        .strip_prefix("lib")?
+        .strip_suffix(".a")?
+        .to_string();
+    let version = name.strip_prefix("nrf_cc310_")?;
+    let numbers = version
+        .split('.')
+        .map(|n| n.parse::<u32>().ok())
+        .collect::<Option<Vec<u32>>>()?;
+    Some((numbers, name))
+}
+
+/// Find the library with the highest version in `path`
+fn find_library(path: &Path) -> Option<String> {
+    fs::read_dir(path)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| parse_version(&entry.file_name().to_string_lossy()))
+        .max()
+        .map(|(_, name)| name)
+}
+
+/// Generate the bindings from wrapper.h, keep in sync with `generate`
+#[cfg(feature = "generate-bindings")]
+fn generate_bindings(cc310_path: &Path) {
+    println!("cargo:rerun-if-changed=wrapper.h");
+    let bindings = bindgen::Builder::default()
+        .header("wrapper.h")
+        .clang_arg(format!("-I{}", cc310_path.join("include").display()))
+        .use_core()
+        .ctypes_prefix("cty")
+        .size_t_is_usize(true)
+        .layout_tests(false)
+        .allowlist_function("SaSi_.*")
+        .allowlist_function("CRYS_HASH_.*")
+        .allowlist_function("CC_AESCCM")
+        .allowlist_function("CRYS_Rnd.*")
+        .allowlist_function("CRYS_RND_.*")
+        .allowlist_function("CRYS_ECPKI_GetEcDomain")
+        .allowlist_function("_DX_ECPKI_BuildPublKey")
+        .allowlist_function("CRYS_ECDSA_Verify")
+        .allowlist_function("CRYS_ECEDW_Verify")
+        .allowlist_var("SASI_AES_.*")
+        .allowlist_var("CRYS_HASH_.*")
+        .allowlist_var("CRYS_AESCCM_.*")
+        .allowlist_var("CRYS_RND_.*")
+        .allowlist_var("CRYS_ECEDW_.*")
+        .allowlist_var("CRYS_ECDSA_VERIFY_.*")
+        .allowlist_var("CRYS_.*_MODULE_ERROR_BASE")
+        .generate()
+        .unwrap_or_else(|_| fail("Failed to generate bindings from wrapper.h"));
+    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap()).join("nrf_cc310.rs");
+    bindings
+        .write_to_file(out_path)
+        .unwrap_or_else(|_| fail("Failed to write bindings"));
+}
 
 fn main() {
-    let crate_path = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    println!("cargo:rerun-if-env-changed={}", ENV_CC310_DIR);
+    println!("cargo:rerun-if-env-changed={}", ENV_SDK_ROOT);
+
+    if !feature("CRYPTOCELL") {
+        return;
+    }
+
+    let cc310_path = library_directory();
+    if !cc310_path.is_dir() {
+        fail(&format!(
+            "nrf_cc310 directory `{}` not found",
+            cc310_path.display()
+        ));
+    }
+
+    let interrupts = if feature("INTERRUPTS") {
+        "interrupts"
+    } else {
+        "no-interrupts"
+    };
+    let library_path = cc310_path
+        .join("lib")
+        .join("cortex-m4")
+        .join(float_abi())
+        .join(interrupts);
+
+    let library = match find_library(&library_path) {
+        Some(library) => library,
+        None => fail(&format!(
+            "No nrf_cc310 library found in `{}`",
+            library_path.display()
+        )),
+    };
+
+    #[cfg(feature = "generate-bindings")]
+    generate_bindings(&cc310_path);
+
+    println!("cargo:rerun-if-changed={}", library_path.display());
+    println!("cargo:rustc-link-search={}", library_path.display());
+    println!("cargo:rustc-link-lib=static={}", library);
     println!(
-        "cargo:rustc-link-search={}",
-        crate_path
-            .join("nrf_cc310/lib/cortex-m4/hard-float/no-interrupts")
-            .to_string_lossy()
+        "cargo:rustc-env=NRF_CC310_VERSION={}",
+        library.trim_start_matches("nrf_cc310_")
     );
-    println!("cargo:rustc-link-lib=static=nrf_cc310_0.9.13");
 }
@@ -0,0 +1,166 @@
+//! Standard AES-CCM
+//!
+//! AES-CCM as described in RFC 3610, using the CCM engine of the CryptoCell.
+//! The CCM* used by IEEE 802.15.4 and Zigbee always has a 13 byte nonce, here
+//! the nonce may be 7 to 13 bytes, which gives a length field of 15 minus the
+//! nonce length bytes, and the tag 4 to 16 bytes. BLE link layer encryption
+//! uses a 13 byte nonce and a 4 byte MIC, Bluetooth mesh a 13 byte nonce and a
+//! 4 or 8 byte MIC.
+//!
+//! The CCM function of the library does not process data in place, the input
+//! and output are separate buffers.
+
+use crate::{clear, error::Redacted, nrf_cc310::*, CryptoCell, Error, BLOCK_SIZE, KEY_SIZE};
+
+/// Smallest nonce, gives an 8 byte length field
+pub const MIN_NONCE_SIZE: usize = 7;
+/// Largest nonce, gives a 2 byte length field
+pub const MAX_NONCE_SIZE: usize = 13;
+/// Smallest tag
+pub const MIN_TAG_SIZE: usize = 4;
+/// Largest tag
+pub const MAX_TAG_SIZE: usize = BLOCK_SIZE;
+
+/// AES-CCM with a 128-bit key
+pub struct AesCcm<'a> {
+    cryptocell: &'a CryptoCell,
+    key: CRYS_AESCCM_Key_t,
+}
+
+impl<'a> AesCcm<'a> {
+    pub fn new(cryptocell: &'a CryptoCell, key: &[u8]) -> Self {
+        assert!(key.len() == KEY_SIZE);
+        let mut ccm = Self {
+            cryptocell,
+            key: [0u8; CRYS_AESCCM_KEY_SIZE_WORDS as usize * 4],
+        };
+        ccm.key[..KEY_SIZE].copy_from_slice(key);
+        ccm
+    }
+
+    /// Encrypt `input` into `output` and write the tag to `tag`
+    ///
+    /// `output` must be at least as long as `input`. The length of `tag` is
+    /// the tag length, 4, 6, 8, 10, 12, 14 or 16 bytes.
+    pub fn encrypt(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        input: &[u8],
+        output: &mut [u8],
+        tag: &mut [u8],
+    ) -> Result<(), Error> {
+        let mut mac = [0u8; MAX_TAG_SIZE];
+        let result = self.process(
+            SaSiAesEncryptMode_t_SASI_AES_ENCRYPT,
+            nonce,
+            aad,
+            input,
+            output,
+            tag.len(),
+            &mut mac,
+        )?;
+        if result != 0 {
+            return Err(Error::Other(result));
+        }
+        tag.copy_from_slice(&mac[..tag.len()]);
+        Ok(())
+    }
+
+    /// Decrypt `input` into `output` and validate `tag`
+    ///
+    /// `output` must be at least as long as `input`. Returns false if the tag
+    /// is invalid, `output` is then cleared.
+    pub fn decrypt(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        input: &[u8],
+        output: &mut [u8],
+        tag: &[u8],
+    ) -> Result<bool, Error> {
+        let mut mac = [0u8; MAX_TAG_SIZE];
+        mac[..tag.len()].copy_from_slice(tag);
+        let result = self.process(
+            SaSiAesEncryptMode_t_SASI_AES_DECRYPT,
+            nonce,
+            aad,
+            input,
+            output,
+            tag.len(),
+            &mut mac,
+        )?;
+        match result {
+            0 => Ok(true),
+            CRYS_AESCCM_CCM_MAC_INVALID_ERROR => {
+                clear(output);
+                Ok(false)
+            }
+            _ => {
+                clear(output);
+                Err(Error::Other(result))
+            }
+        }
+    }
+
+    /// Run the whole CCM operation, `mac` holds the tag to validate when
+    /// decrypting and receives the tag when encrypting
+    ///
+    /// Returns the result code of the library.
+    #[allow(clippy::too_many_arguments)]
+    fn process(
+        &self,
+        mode: SaSiAesEncryptMode_t,
+        nonce: &[u8],
+        aad: &[u8],
+        input: &[u8],
+        output: &mut [u8],
+        tag_length: usize,
+        mac: &mut CRYS_AESCCM_Mac_Res_t,
+    ) -> Result<CRYSError_t, Error> {
+        assert!((MIN_NONCE_SIZE..=MAX_NONCE_SIZE).contains(&nonce.len()));
+        assert!((MIN_TAG_SIZE..=MAX_TAG_SIZE).contains(&tag_length) && tag_length & 1 == 0);
+        // The length field is 15 - N bytes
+        let length_field = BLOCK_SIZE - 1 - nonce.len();
+        assert!(length_field >= 4 || input.len() < 1 << (8 * length_field));
+        assert!(input.len() <= u32::MAX as usize && aad.len() <= u32::MAX as usize);
+        assert!(output.len() >= input.len());
+
+        let _access = self.cryptocell.access()?;
+        // The library takes mutable pointers, use copies for the inputs
+        let mut key = self.key;
+        let mut n = [0u8; MAX_NONCE_SIZE];
+        n[..nonce.len()].copy_from_slice(nonce);
+        let result = unsafe {
+            CC_AESCCM(
+                mode,
+                key.as_mut_ptr(),
+                CRYS_AESCCM_KeySize_t_CRYS_AES_Key128BitSize,
+                n.as_mut_ptr(),
+                nonce.len() as u8,
+                aad.as_ptr() as *mut u8,
+                aad.len() as u32,
+                input.as_ptr() as *mut u8,
+                input.len() as u32,
+                output.as_mut_ptr(),
+                tag_length as u8,
+                mac.as_mut_ptr(),
+                CRYS_AESCCM_MODE_CCM,
+            )
+        };
+        clear(&mut key);
+        Ok(result)
+    }
+}
+
+impl<'a> core::ops::Drop for AesCcm<'a> {
+    fn drop(&mut self) {
+        clear(&mut self.key);
+    }
+}
+
+impl<'a> core::fmt::Debug for AesCcm<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("AesCcm").field("key", &Redacted).finish()
+    }
+}
@@ -0,0 +1,618 @@
+//! CCM* construction
+//!
+//! CCM* built from a single block encryption function. Used by backends that
+//! only provide AES-ECB, the counter mode encryption and the CBC-MAC are done
+//! in software around the block encryption.
+//!
+//! The length of the nonce decides the size of the length field, L = 15 - N,
+//! so nonces from 7 to 13 bytes are supported. Zigbee uses 13 byte nonces.
+
+use crate::{clear, make_flag, Error, BLOCK_SIZE};
+
+/// Shortest supported nonce, in bytes
+pub(crate) const MIN_NONCE_SIZE: usize = 7;
+/// Longest supported nonce, in bytes
+pub(crate) const MAX_NONCE_SIZE: usize = 13;
+
+/// Write `value` big-endian into the last `length` bytes of `block`
+fn write_length_field(block: &mut [u8; BLOCK_SIZE], length: usize, value: usize) {
+    for (n, b) in block[BLOCK_SIZE - length..].iter_mut().rev().enumerate() {
+        *b = if n < core::mem::size_of::<usize>() {
+            (value >> (n * 8)) as u8
+        } else {
+            0
+        };
+    }
+}
+
+/// Size of the length field
+fn length_field_length(nonce: &[u8]) -> usize {
+    assert!(nonce.len() >= MIN_NONCE_SIZE && nonce.len() <= MAX_NONCE_SIZE);
+    BLOCK_SIZE - 1 - nonce.len()
+}
+
+/// Returns true if a message of `message_length` bytes fits the length field
+/// for a nonce of `nonce_length` bytes, and `aad_length` bytes of additional
+/// data fit the two byte length encoding
+pub(crate) fn lengths_valid(nonce_length: usize, message_length: usize, aad_length: usize) -> bool {
+    let big_l = BLOCK_SIZE - 1 - nonce_length;
+    (big_l >= core::mem::size_of::<usize>() || message_length >> (big_l * 8) == 0)
+        && aad_length < 0xff00
+}
+
+/// Create the counter block A<index>
+fn counter_block(nonce: &[u8], index: usize) -> [u8; BLOCK_SIZE] {
+    let big_l = length_field_length(nonce);
+    let mut block = [0u8; BLOCK_SIZE];
+    block[0] = make_flag(0, 0, big_l);
+    block[1..=nonce.len()].copy_from_slice(nonce);
+    write_length_field(&mut block, big_l, index);
+    block
+}
+
+/// Feed `data` into the CBC-MAC, zero padding the last block
+fn mac_feed<F>(encrypt: &mut F, mac: &mut [u8; BLOCK_SIZE], data: &[u8]) -> Result<(), Error>
+where
+    F: FnMut(&mut [u8; BLOCK_SIZE]) -> Result<(), Error>,
+{
+    for chunk in data.chunks(BLOCK_SIZE) {
+        for (m, d) in mac.iter_mut().zip(chunk.iter()) {
+            *m ^= *d;
+        }
+        encrypt(mac)?;
+    }
+    Ok(())
+}
+
+/// Calculate the unencrypted authentication tag
+fn authenticate<F>(
+    encrypt: &mut F,
+    nonce: &[u8],
+    message: &[u8],
+    mic_length: usize,
+    aad: &[u8],
+) -> Result<[u8; BLOCK_SIZE], Error>
+where
+    F: FnMut(&mut [u8; BLOCK_SIZE]) -> Result<(), Error>,
+{
+    let big_l = length_field_length(nonce);
+    assert!(lengths_valid(nonce.len(), message.len(), aad.len()));
+
+    let mut mac = [0u8; BLOCK_SIZE];
+    mac[0] = make_flag(aad.len(), mic_length, big_l);
+    mac[1..=nonce.len()].copy_from_slice(nonce);
+    write_length_field(&mut mac, big_l, message.len());
+    encrypt(&mut mac)?;
+
+    if !aad.is_empty() {
+        // The first block holds the length of the additional data
+        let aad_length = aad.len() as u16;
+        let (first, rest) = aad.split_at(aad.len().min(BLOCK_SIZE - 2));
+        let mut block = [0u8; BLOCK_SIZE];
+        block[0] = (aad_length >> 8) as u8;
+        block[1] = (aad_length & 0x00ff) as u8;
+        block[2..2 + first.len()].copy_from_slice(first);
+        mac_feed(encrypt, &mut mac, &block)?;
+        mac_feed(encrypt, &mut mac, rest)?;
+    }
+
+    mac_feed(encrypt, &mut mac, message)?;
+
+    Ok(mac)
+}
+
+/// XOR `data` with the key stream starting at counter block A1
+fn apply_keystream<F>(encrypt: &mut F, nonce: &[u8], data: &mut [u8]) -> Result<(), Error>
+where
+    F: FnMut(&mut [u8; BLOCK_SIZE]) -> Result<(), Error>,
+{
+    for (index, chunk) in data.chunks_mut(BLOCK_SIZE).enumerate() {
+        let mut stream = counter_block(nonce, index + 1);
+        encrypt(&mut stream)?;
+        for (d, s) in chunk.iter_mut().zip(stream.iter()) {
+            *d ^= *s;
+        }
+    }
+    Ok(())
+}
+
+/// Encrypt the authentication tag with counter block A0
+fn encrypt_tag<F>(encrypt: &mut F, nonce: &[u8], tag: &mut [u8]) -> Result<(), Error>
+where
+    F: FnMut(&mut [u8; BLOCK_SIZE]) -> Result<(), Error>,
+{
+    let mut stream = counter_block(nonce, 0);
+    encrypt(&mut stream)?;
+    for (t, s) in tag.iter_mut().zip(stream.iter()) {
+        *t ^= *s;
+    }
+    Ok(())
+}
+
+/// CCM* encrypt `data` in place and generate the MIC
+pub(crate) fn encrypt_in_place<F>(
+    encrypt: &mut F,
+    nonce: &[u8],
+    data: &mut [u8],
+    mic: &mut [u8],
+    aad: &[u8],
+) -> Result<(), Error>
+where
+    F: FnMut(&mut [u8; BLOCK_SIZE]) -> Result<(), Error>,
+{
+    assert!(mic.len() <= BLOCK_SIZE);
+
+    let mut tag = authenticate(encrypt, nonce, data, mic.len(), aad)?;
+    mic.copy_from_slice(&tag[..mic.len()]);
+    clear(&mut tag);
+    encrypt_tag(encrypt, nonce, mic)?;
+    apply_keystream(encrypt, nonce, data)
+}
+
+/// CCM* decrypt `data` in place and validate the MIC
+///
+/// Returns true if the MIC is valid. The decrypted data is cleared if the MIC
+/// is invalid.
+pub(crate) fn decrypt_in_place<F>(
+    encrypt: &mut F,
+    nonce: &[u8],
+    data: &mut [u8],
+    mic: &[u8],
+    aad: &[u8],
+) -> Result<bool, Error>
+where
+    F: FnMut(&mut [u8; BLOCK_SIZE]) -> Result<(), Error>,
+{
+    assert!(mic.len() <= BLOCK_SIZE);
+
+    apply_keystream(encrypt, nonce, data)?;
+    let mut tag = authenticate(encrypt, nonce, data, mic.len(), aad)?;
+    encrypt_tag(encrypt, nonce, &mut tag[..mic.len()])?;
+
+    let mut difference = 0u8;
+    for (a, b) in tag[..mic.len()].iter().zip(mic.iter()) {
+        difference |= a ^ b;
+    }
+    clear(&mut tag);
+
+    if difference != 0 {
+        clear(data);
+    }
+    Ok(difference == 0)
+}
+
+/// CCM* encrypt `message` into `output` and generate the MIC
+#[cfg(all(feature = "ecb", feature = "psila"))]
+pub(crate) fn encrypt<F>(
+    encrypt: &mut F,
+    nonce: &[u8],
+    message: &[u8],
+    mic: &mut [u8],
+    aad: &[u8],
+    output: &mut [u8],
+) -> Result<usize, Error>
+where
+    F: FnMut(&mut [u8; BLOCK_SIZE]) -> Result<(), Error>,
+{
+    assert!(output.len() >= message.len());
+
+    let output = &mut output[..message.len()];
+    output.copy_from_slice(message);
+    encrypt_in_place(encrypt, nonce, output, mic, aad)?;
+    Ok(message.len())
+}
+
+/// CCM* decrypt `encrypted` into `decrypted` and validate the MIC
+///
+/// Returns the length of the decrypted data, or zero if the MIC is invalid.
+#[cfg(all(feature = "ecb", feature = "psila"))]
+pub(crate) fn decrypt<F>(
+    encrypt: &mut F,
+    nonce: &[u8],
+    encrypted: &[u8],
+    mic: &[u8],
+    aad: &[u8],
+    decrypted: &mut [u8],
+) -> Result<usize, Error>
+where
+    F: FnMut(&mut [u8; BLOCK_SIZE]) -> Result<(), Error>,
+{
+    assert!(decrypted.len() >= encrypted.len());
+
+    let decrypted = &mut decrypted[..encrypted.len()];
+    decrypted.copy_from_slice(encrypted);
+    if decrypt_in_place(encrypt, nonce, decrypted, mic, aad)? {
+        Ok(encrypted.len())
+    } else {
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use aes::{
+        cipher::{generic_array::GenericArray, BlockEncrypt, NewBlockCipher},
+        Aes128,
+    };
+
+    struct Vector {
+        key: &'static [u8],
+        nonce: &'static [u8],
+        aad: &'static [u8],
+        message: &'static [u8],
+        encrypted: &'static [u8],
+        mic: &'static [u8],
+    }
+
+    /// Known answers, the expected output of the vectors generated with
+    /// OpenSSL is also checked against an independent CCM* implementation
+    const VECTORS: [Vector; 8] = [
+        // RFC 3610, packet vector #1
+        Vector {
+            key: &[
+                0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcc, 0xcd,
+                0xce, 0xcf,
+            ],
+            nonce: &[
+                0x00, 0x00, 0x00, 0x03, 0x02, 0x01, 0x00, 0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5,
+            ],
+            aad: &[0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07],
+            message: &[
+                0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15,
+                0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e,
+            ],
+            encrypted: &[
+                0x58, 0x8c, 0x97, 0x9a, 0x61, 0xc6, 0x63, 0xd2, 0xf0, 0x66, 0xd0, 0xc2, 0xc0, 0xf9,
+                0x89, 0x80, 0x6d, 0x5f, 0x6b, 0x61, 0xda, 0xc3, 0x84,
+            ],
+            mic: &[0x17, 0xe8, 0xd1, 0x2c, 0xfd, 0xf9, 0x26, 0xe0],
+        },
+        // IEEE 802.15.4-2006, C.2.1, beacon frame, MIC-64
+        Vector {
+            key: &[
+                0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcc, 0xcd,
+                0xce, 0xcf,
+            ],
+            nonce: &[
+                0xac, 0xde, 0x48, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x02,
+            ],
+            aad: &[
+                0x08, 0xd0, 0x84, 0x21, 0x43, 0x01, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x02,
+                0x05, 0x00, 0x00, 0x00, 0x55, 0xcf, 0x00, 0x00, 0x51, 0x52, 0x53, 0x54,
+            ],
+            message: &[],
+            encrypted: &[],
+            mic: &[0x22, 0x3b, 0xc1, 0xec, 0x84, 0x1a, 0xb5, 0x53],
+        },
+        // IEEE 802.15.4-2006, C.2.2, data frame, ENC
+        Vector {
+            key: &[
+                0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcc, 0xcd,
+                0xce, 0xcf,
+            ],
+            nonce: &[
+                0xac, 0xde, 0x48, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x04,
+            ],
+            aad: &[],
+            message: &[0x61, 0x62, 0x63, 0x64],
+            encrypted: &[0xd4, 0x3e, 0x02, 0x2b],
+            mic: &[],
+        },
+        // NIST SP800-38C, example 1
+        Vector {
+            key: &[
+                0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d,
+                0x4e, 0x4f,
+            ],
+            nonce: &[0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16],
+            aad: &[0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07],
+            message: &[0x20, 0x21, 0x22, 0x23],
+            encrypted: &[0x71, 0x62, 0x01, 0x5b],
+            mic: &[0x4d, 0xac, 0x25, 0x5d],
+        },
+        // NIST SP800-38C, example 2
+        Vector {
+            key: &[
+                0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d,
+                0x4e, 0x4f,
+            ],
+            nonce: &[0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17],
+            aad: &[
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+                0x0e, 0x0f,
+            ],
+            message: &[
+                0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2a, 0x2b, 0x2c, 0x2d,
+                0x2e, 0x2f,
+            ],
+            encrypted: &[
+                0xd2, 0xa1, 0xf0, 0xe0, 0x51, 0xea, 0x5f, 0x62, 0x08, 0x1a, 0x77, 0x92, 0x07, 0x3d,
+                0x59, 0x3d,
+            ],
+            mic: &[0x1f, 0xc6, 0x4f, 0xbf, 0xac, 0xcd],
+        },
+        // NIST SP800-38C, example 3
+        Vector {
+            key: &[
+                0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d,
+                0x4e, 0x4f,
+            ],
+            nonce: &[
+                0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+            ],
+            aad: &[
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+                0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13,
+            ],
+            message: &[
+                0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2a, 0x2b, 0x2c, 0x2d,
+                0x2e, 0x2f, 0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37,
+            ],
+            encrypted: &[
+                0xe3, 0xb2, 0x01, 0xa9, 0xf5, 0xb7, 0x1a, 0x7a, 0x9b, 0x1c, 0xea, 0xec, 0xcd, 0x97,
+                0xe7, 0x0b, 0x61, 0x76, 0xaa, 0xd9, 0xa4, 0x42, 0x8a, 0xa5,
+            ],
+            mic: &[0x48, 0x43, 0x92, 0xfb, 0xc1, 0xb0, 0x99, 0x51],
+        },
+        // RFC 3610 packet vector #1 input with a 16 byte MIC, generated with OpenSSL
+        Vector {
+            key: &[
+                0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcc, 0xcd,
+                0xce, 0xcf,
+            ],
+            nonce: &[
+                0x00, 0x00, 0x00, 0x03, 0x02, 0x01, 0x00, 0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5,
+            ],
+            aad: &[0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07],
+            message: &[
+                0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15,
+                0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e,
+            ],
+            encrypted: &[
+                0x58, 0x8c, 0x97, 0x9a, 0x61, 0xc6, 0x63, 0xd2, 0xf0, 0x66, 0xd0, 0xc2, 0xc0, 0xf9,
+                0x89, 0x80, 0x6d, 0x5f, 0x6b, 0x61, 0xda, 0xc3, 0x84,
+            ],
+            mic: &[
+                0x50, 0x9d, 0xa6, 0x54, 0xe3, 0x2d, 0xea, 0xc3, 0x69, 0xc2, 0xda, 0xe7, 0x13, 0x3c,
+                0xb0, 0x8d,
+            ],
+        },
+        // 802.15.4 nonce, additional data and payload longer than two blocks,
+        // generated with the Python cryptography package
+        Vector {
+            key: &[
+                0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcc, 0xcd,
+                0xce, 0xcf,
+            ],
+            nonce: &[
+                0xac, 0xde, 0x48, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x05,
+            ],
+            aad: &[
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+                0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+                0x1c, 0x1d, 0x1e, 0x1f, 0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27,
+            ],
+            message: &[
+                0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d,
+                0x4e, 0x4f, 0x50, 0x51, 0x52, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x5b,
+                0x5c, 0x5d, 0x5e, 0x5f, 0x60, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+                0x6a, 0x6b, 0x6c,
+            ],
+            encrypted: &[
+                0x14, 0x45, 0x9c, 0x55, 0x90, 0x2e, 0xe2, 0x96, 0xde, 0x3d, 0x38, 0x31, 0xf4, 0x95,
+                0xd5, 0x6e, 0x8f, 0xfb, 0x0e, 0xd4, 0xf7, 0x96, 0x6c, 0xd5, 0x8c, 0x92, 0xb4, 0xa9,
+                0xaf, 0x1b, 0xa1, 0xcc, 0xb1, 0xec, 0x9e, 0x9f, 0xd3, 0xff, 0x7d, 0xa5, 0x43, 0xab,
+                0x80, 0xa8, 0xc2,
+            ],
+            mic: &[0xbc, 0x97, 0x47, 0x76, 0x22, 0xc0, 0x07, 0x36],
+        },
+    ];
+
+    /// Software CCM* for the tests of the frame security helpers
+    pub(crate) struct SoftwareCcmStar;
+
+    impl crate::CcmStarInPlace for SoftwareCcmStar {
+        fn ccmstar_encrypt_in_place(
+            &mut self,
+            key: &[u8],
+            nonce: &[u8],
+            data: &mut [u8],
+            mic: &mut [u8],
+            aad: &[u8],
+        ) -> Result<(), Error> {
+            encrypt_in_place(&mut block_encrypt(key), nonce, data, mic, aad)
+        }
+
+        fn ccmstar_decrypt_in_place(
+            &mut self,
+            key: &[u8],
+            nonce: &[u8],
+            data: &mut [u8],
+            mic: &[u8],
+            aad: &[u8],
+        ) -> Result<bool, Error> {
+            decrypt_in_place(&mut block_encrypt(key), nonce, data, mic, aad)
+        }
+    }
+
+    fn block_encrypt(key: &[u8]) -> impl FnMut(&mut [u8; BLOCK_SIZE]) -> Result<(), Error> {
+        let cipher = Aes128::new(GenericArray::from_slice(key));
+        move |block: &mut [u8; BLOCK_SIZE]| {
+            cipher.encrypt_block(GenericArray::from_mut_slice(block));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn encrypt_vectors() {
+        for vector in VECTORS.iter() {
+            let mut encrypt = block_encrypt(vector.key);
+            let mut data = [0u8; 64];
+            let data = &mut data[..vector.message.len()];
+            data.copy_from_slice(vector.message);
+            let mut mic = [0u8; BLOCK_SIZE];
+            let mic = &mut mic[..vector.mic.len()];
+            encrypt_in_place(&mut encrypt, vector.nonce, data, mic, vector.aad).unwrap();
+            assert_eq!(data, vector.encrypted);
+            assert_eq!(mic, vector.mic);
+        }
+    }
+
+    #[test]
+    fn decrypt_vectors() {
+        for vector in VECTORS.iter() {
+            let mut encrypt = block_encrypt(vector.key);
+            let mut data = [0u8; 64];
+            let data = &mut data[..vector.encrypted.len()];
+            data.copy_from_slice(vector.encrypted);
+            let valid =
+                decrypt_in_place(&mut encrypt, vector.nonce, data, vector.mic, vector.aad).unwrap();
+            assert!(valid);
+            assert_eq!(data, vector.message);
+        }
+    }
+
+    #[test]
+    fn mic_lengths() {
+        // All MIC lengths of CCM*, decryption must reject a truncated MIC
+        let vector = &VECTORS[0];
+        let mut full = [0u8; BLOCK_SIZE];
+        let mut data = [0u8; 64];
+        let data = &mut data[..vector.message.len()];
+        data.copy_from_slice(vector.message);
+        encrypt_in_place(
+            &mut block_encrypt(vector.key),
+            vector.nonce,
+            data,
+            &mut full,
+            vector.aad,
+        )
+        .unwrap();
+        assert_eq!(data, vector.encrypted);
+        assert_eq!(&full[..], VECTORS[6].mic);
+
+        for &length in [0usize, 4, 6, 8, 10, 12, 14, 16].iter() {
+            let mut encrypt = block_encrypt(vector.key);
+            let mut data = [0u8; 64];
+            let data = &mut data[..vector.message.len()];
+            data.copy_from_slice(vector.message);
+            let mut mic = [0u8; BLOCK_SIZE];
+            let mic = &mut mic[..length];
+            encrypt_in_place(&mut encrypt, vector.nonce, data, mic, vector.aad).unwrap();
+            assert_eq!(data, vector.encrypted);
+            if length == vector.mic.len() {
+                assert_eq!(mic, vector.mic);
+            } else if length > 0 && length < BLOCK_SIZE {
+                // The MIC length is part of B0, a shorter MIC is not a
+                // truncated longer MIC
+                assert_ne!(mic, &full[..length]);
+            }
+            let valid =
+                decrypt_in_place(&mut encrypt, vector.nonce, data, mic, vector.aad).unwrap();
+            assert!(valid);
+            assert_eq!(data, vector.message);
+        }
+    }
+
+    #[test]
+    fn reject_tampering() {
+        for vector in VECTORS.iter().filter(|v| !v.mic.is_empty()) {
+            let mut encrypt = block_encrypt(vector.key);
+            let mut data = [0u8; 64];
+            let length = vector.encrypted.len();
+            let mut mic = [0u8; BLOCK_SIZE];
+            let mic = &mut mic[..vector.mic.len()];
+            let mut aad = [0u8; 64];
+            let aad = &mut aad[..vector.aad.len()];
+
+            // Flipped bit in the encrypted data
+            if length > 0 {
+                data[..length].copy_from_slice(vector.encrypted);
+                data[length - 1] ^= 0x01;
+                let valid = decrypt_in_place(
+                    &mut encrypt,
+                    vector.nonce,
+                    &mut data[..length],
+                    vector.mic,
+                    vector.aad,
+                )
+                .unwrap();
+                assert!(!valid);
+                // The decrypted data is cleared
+                assert!(data[..length].iter().all(|&b| b == 0));
+            }
+
+            // Flipped bit in the MIC
+            data[..length].copy_from_slice(vector.encrypted);
+            mic.copy_from_slice(vector.mic);
+            mic[0] ^= 0x80;
+            let valid = decrypt_in_place(
+                &mut encrypt,
+                vector.nonce,
+                &mut data[..length],
+                mic,
+                vector.aad,
+            )
+            .unwrap();
+            assert!(!valid);
+
+            // Flipped bit in the additional data
+            if !aad.is_empty() {
+                data[..length].copy_from_slice(vector.encrypted);
+                aad.copy_from_slice(vector.aad);
+                aad[0] ^= 0x01;
+                let valid = decrypt_in_place(
+                    &mut encrypt,
+                    vector.nonce,
+                    &mut data[..length],
+                    vector.mic,
+                    aad,
+                )
+                .unwrap();
+                assert!(!valid);
+            }
+
+            // Other nonce
+            let mut nonce = [0u8; MAX_NONCE_SIZE];
+            let nonce = &mut nonce[..vector.nonce.len()];
+            nonce.copy_from_slice(vector.nonce);
+            nonce[0] ^= 0x01;
+            data[..length].copy_from_slice(vector.encrypted);
+            let valid = decrypt_in_place(
+                &mut encrypt,
+                nonce,
+                &mut data[..length],
+                vector.mic,
+                vector.aad,
+            )
+            .unwrap();
+            assert!(!valid);
+        }
+    }
+
+    #[test]
+    fn block_encrypt_error() {
+        let mut encrypt = |_: &mut [u8; BLOCK_SIZE]| Err(Error::EcbAborted);
+        let mut data = [0u8; 4];
+        let mut mic = [0u8; 4];
+        let nonce = [0u8; MAX_NONCE_SIZE];
+        assert_eq!(
+            encrypt_in_place(&mut encrypt, &nonce, &mut data, &mut mic, &[]),
+            Err(Error::EcbAborted)
+        );
+        assert_eq!(
+            decrypt_in_place(&mut encrypt, &nonce, &mut data, &mic, &[]),
+            Err(Error::EcbAborted)
+        );
+    }
+
+    #[test]
+    fn length_limits() {
+        // A 13 byte nonce leaves a two byte length field
+        assert!(lengths_valid(MAX_NONCE_SIZE, 0xffff, 0xfeff));
+        assert!(!lengths_valid(MAX_NONCE_SIZE, 0x10000, 0));
+        assert!(!lengths_valid(MAX_NONCE_SIZE, 0, 0xff00));
+        assert!(lengths_valid(MIN_NONCE_SIZE, 0x10000, 0));
+    }
+}
@@ -0,0 +1,222 @@
+//! CryptoCell access token
+//!
+//! The nrf_cc310 library keeps global state that is set up by
+//! `SaSi_LibInit`. [`CryptoCell`] owns the peripheral and the initialised
+//! library. AES contexts and backends borrow the token, so they can not be
+//! created before the library is initialised and can not outlive it.
+//!
+//! The token can be shared between tasks. Operations are serialised by a busy
+//! flag, an operation started while another operation is running, for example
+//! from a higher priority task, fails with [`Error::Busy`] instead of
+//! disturbing the running operation.
+//!
+//! The conflict is detected at run time rather than by the borrow checker.
+//! Contexts, backends and the DRBG keep a shared reference to the token for
+//! as long as they exist, the key cache relies on keyed contexts outliving a
+//! single operation. Requiring `&mut CryptoCell` per operation would mean
+//! setting up every context again for each operation. An operation runs to
+//! completion and the busy flag is released when it returns, so `Busy` only
+//! occurs when tasks of different priorities use the CryptoCell at the same
+//! time. Run the operations from a single task, or share the CryptoCell as a
+//! RTIC resource and lock it, and `Busy` is never returned, otherwise retry
+//! the operation later.
+//!
+//! The device root key (KDR) used for key derivation is not set by the
+//! library. It has to be loaded into the KDR registers after every reset, see
+//! [`CryptoCell::load_root_key`].
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{
+    clear,
+    nrf_cc310::*,
+    pac::{CC_HOST_RGF, CRYPTOCELL, UICR},
+    power::PowerGuard,
+    Error, KEY_SIZE,
+};
+
+/// Owner of the CryptoCell peripheral and the initialised library
+pub struct CryptoCell {
+    /// Owned to ensure exclusive access to the peripheral
+    _cryptocell: CRYPTOCELL,
+    busy: AtomicBool,
+    always_on: Option<PowerGuard>,
+    /// The root key has been loaded into the KDR registers
+    root_key: bool,
+}
+
+// Safety: `CRYPTOCELL` is only owned, it isn't accessed through a shared
+// reference. The methods taking `&self` use the atomic busy flag, read the
+// root key flag, or run library operations while holding the busy flag. The
+// flag is taken with a compare and swap, so only one context at a time uses
+// the library state and the CryptoCell registers. The power guards count
+// their users in a critical section. The other fields are only written
+// through `&mut self`.
+unsafe impl Sync for CryptoCell {}
+
+impl CryptoCell {
+    /// Initialise the library
+    ///
+    /// Fails with the `SA_SilibRetCode_t` code if the library can't be
+    /// initialised, for example when the hardware version doesn't match.
+    pub fn new(cryptocell: CRYPTOCELL) -> Result<Self, Error> {
+        let _power = PowerGuard::acquire();
+
+        let result = unsafe { SaSi_LibInit() };
+        if result != SA_SilibRetCode_t_SA_SILIB_RET_OK {
+            return Err(Error::Other(result));
+        }
+
+        Ok(Self {
+            _cryptocell: cryptocell,
+            busy: AtomicBool::new(false),
+            always_on: None,
+            root_key: false,
+        })
+    }
+
+    /// Keep the CryptoCell enabled between operations
+    ///
+    /// By default the CryptoCell is only enabled during operations. Keeping it
+    /// enabled removes the enable overhead from each operation at the cost of
+    /// higher current consumption.
+    pub fn set_always_on(&mut self, always_on: bool) {
+        self.always_on = if always_on {
+            Some(self.always_on.take().unwrap_or_else(PowerGuard::acquire))
+        } else {
+            None
+        };
+    }
+
+    /// Load the device root key (KDR)
+    ///
+    /// The root key is the base for [`DerivationKey::Root`]. The hardware
+    /// doesn't provide one, the application loads a 128-bit device unique key,
+    /// generated and stored at production, after every reset. The KDR
+    /// registers are write only, so the key can not be read back from the
+    /// CryptoCell. The hard-coded RTL key is locked at the same time, so only
+    /// the loaded key can be used as hardware key.
+    ///
+    /// The KDR registers are in the always-on power domain, the key is
+    /// retained while the CryptoCell is disabled between operations.
+    ///
+    /// Fails with [`Error::RootKeyLoaded`] if a root key already is loaded,
+    /// and with [`Error::RootKeyNotRetained`] if the CryptoCell didn't retain
+    /// the key.
+    ///
+    /// [`DerivationKey::Root`]: crate::key_derivation::DerivationKey::Root
+    pub fn load_root_key(&mut self, key: &[u8]) -> Result<(), Error> {
+        assert!(key.len() == KEY_SIZE);
+        if self.root_key {
+            return Err(Error::RootKeyLoaded);
+        }
+        {
+            let _access = self.access()?;
+            let word = |n: usize| {
+                u32::from_le_bytes([key[4 * n], key[4 * n + 1], key[4 * n + 2], key[4 * n + 3]])
+            };
+            // Safety: The registers are only written here, while holding the
+            // busy flag
+            let registers = unsafe { &*CC_HOST_RGF::ptr() };
+            registers
+                .host_iot_kdr0
+                .write(|w| unsafe { w.bits(word(0)) });
+            registers
+                .host_iot_kdr1
+                .write(|w| unsafe { w.bits(word(1)) });
+            registers
+                .host_iot_kdr2
+                .write(|w| unsafe { w.bits(word(2)) });
+            registers
+                .host_iot_kdr3
+                .write(|w| unsafe { w.bits(word(3)) });
+            registers
+                .host_iot_kprtl_lock
+                .write(|w| unsafe { w.bits(1) });
+            // Reads as one once all of the key is retained
+            if registers.host_iot_kdr0.read().bits() != 1 {
+                return Err(Error::RootKeyNotRetained);
+            }
+        }
+        self.root_key = true;
+        Ok(())
+    }
+
+    /// Load the device root key from the UICR
+    ///
+    /// Reads the key from the four customer registers starting at `index`,
+    /// see [`load_root_key`](Self::load_root_key). An erased key, all ones,
+    /// fails with [`Error::NoRootKey`]. The UICR can be read by all code
+    /// running on the device, and by a debugger unless the access port is
+    /// protected with APPROTECT.
+    pub fn load_root_key_from_uicr(&mut self, index: usize) -> Result<(), Error> {
+        let mut key = [0u8; KEY_SIZE];
+        // Safety: The customer registers are only read
+        let uicr = unsafe { &*UICR::ptr() };
+        for (n, bytes) in key.chunks_exact_mut(4).enumerate() {
+            bytes.copy_from_slice(&uicr.customer[index + n].read().bits().to_le_bytes());
+        }
+        let result = if key.iter().all(|&b| b == 0xff) {
+            Err(Error::NoRootKey)
+        } else {
+            self.load_root_key(&key)
+        };
+        clear(&mut key);
+        result
+    }
+
+    /// Returns true if the device root key is loaded
+    pub fn has_root_key(&self) -> bool {
+        self.root_key
+    }
+
+    /// Returns true if an operation is running
+    pub fn is_busy(&self) -> bool {
+        self.busy.load(Ordering::Relaxed)
+    }
+
+    /// Get exclusive access to the CryptoCell for an operation
+    pub(crate) fn access(&self) -> Result<Access<'_>, Error> {
+        if self
+            .busy
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(Error::Busy);
+        }
+        Ok(Access {
+            busy: &self.busy,
+            _power: PowerGuard::acquire(),
+        })
+    }
+}
+
+impl core::ops::Drop for CryptoCell {
+    fn drop(&mut self) {
+        self.always_on = None;
+        let _power = PowerGuard::acquire();
+        unsafe { SaSi_LibFini() }
+    }
+}
+
+impl core::fmt::Debug for CryptoCell {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("CryptoCell")
+            .field("busy", &self.is_busy())
+            .field("always_on", &self.always_on.is_some())
+            .field("root_key", &self.root_key)
+            .finish()
+    }
+}
+
+/// Exclusive access to the CryptoCell, held during an operation
+pub(crate) struct Access<'a> {
+    busy: &'a AtomicBool,
+    _power: PowerGuard,
+}
+
+impl<'a> core::ops::Drop for Access<'a> {
+    fn drop(&mut self) {
+        self.busy.store(false, Ordering::Release);
+    }
+}
@@ -0,0 +1,632 @@
+//! Deterministic random bit generator
+//!
+//! CTR_DRBG as described in NIST SP800-90A. [`CryptoCellDrbg`] uses the DRBG
+//! of the nrf_cc310 library, seeded from the CryptoCell TRNG. The library runs
+//! health tests on the entropy it collects and a continuous test on the
+//! output, failures are reported as [`DrbgError::HealthTest`].
+//!
+//! [`SoftwareDrbg`], enabled by the `software` feature, is AES-128 CTR_DRBG
+//! without derivation function, seeded by the caller. With a fixed seed the
+//! output is reproducible, for tests on the host.
+//!
+//! Both implement the `rand_core` `RngCore` and `CryptoRng` traits.
+
+use core::num::NonZeroU32;
+
+#[cfg(any(feature = "cryptocell", feature = "software"))]
+use rand_core::{CryptoRng, RngCore};
+
+use crate::Error;
+
+#[cfg(feature = "cryptocell")]
+use crate::{nrf_cc310::*, CryptoCell};
+
+#[cfg(feature = "software")]
+use aes::{
+    cipher::{generic_array::GenericArray, BlockEncrypt, NewBlockCipher},
+    Aes128,
+};
+#[cfg(feature = "software")]
+use rand_core::SeedableRng;
+
+#[cfg(any(feature = "cryptocell", feature = "software"))]
+use crate::{clear, error::Redacted};
+#[cfg(feature = "software")]
+use crate::{BLOCK_SIZE, KEY_SIZE};
+
+/// Error reported by a DRBG
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DrbgError {
+    /// The DRBG is not instantiated
+    NotInstantiated,
+    /// The reseed interval is exhausted, reseed before generating more
+    ReseedRequired,
+    /// The entropy or the output failed a health test, instantiate again
+    HealthTest(Error),
+    /// The CryptoCell reported another error
+    Backend(Error),
+}
+
+#[cfg(feature = "cryptocell")]
+impl From<Error> for DrbgError {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Other(CRYS_RND_INSTANTIATION_NOT_DONE_ERROR) => DrbgError::NotInstantiated,
+            Error::Other(CRYS_RND_RESEED_COUNTER_OVERFLOW_ERROR) => DrbgError::ReseedRequired,
+            Error::Other(CRYS_RND_TRNG_LOSS_SAMPLES_ERROR)
+            | Error::Other(CRYS_RND_TRNG_TIME_EXCEED_ERROR)
+            | Error::Other(CRYS_RND_TRNG_LOSS_SAMPLES_AND_TIME_EXCEED_ERROR)
+            | Error::Other(CRYS_RND_CPRNG_TEST_FAIL_ERROR) => DrbgError::HealthTest(error),
+            _ => DrbgError::Backend(error),
+        }
+    }
+}
+
+impl From<DrbgError> for rand_core::Error {
+    fn from(error: DrbgError) -> Self {
+        let code = match error {
+            DrbgError::NotInstantiated => rand_core::Error::CUSTOM_START,
+            DrbgError::ReseedRequired => rand_core::Error::CUSTOM_START + 1,
+            DrbgError::HealthTest(error) | DrbgError::Backend(error) => error.code(),
+        };
+        // Error codes are never zero
+        NonZeroU32::new(code).unwrap().into()
+    }
+}
+
+/// Size of the buffer serving short requests
+#[cfg(feature = "cryptocell")]
+const BUFFER_SIZE: usize = 64;
+
+/// Work area used by the library while collecting entropy
+///
+/// Only needed when instantiating and reseeding. The buffer is about 6 kB, so
+/// it can be kept on the stack for these calls instead of being kept around.
+#[cfg(feature = "cryptocell")]
+pub struct WorkBuffer {
+    buffer: CRYS_RND_WorkBuff_t,
+}
+
+#[cfg(feature = "cryptocell")]
+impl WorkBuffer {
+    pub const fn new() -> Self {
+        Self {
+            buffer: CRYS_RND_WorkBuff_t {
+                crysRndWorkBuff: [0u32; CRYS_RND_WORK_BUFFER_SIZE_WORDS as usize],
+            },
+        }
+    }
+}
+
+#[cfg(feature = "cryptocell")]
+impl Default for WorkBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "cryptocell")]
+impl core::fmt::Debug for WorkBuffer {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("WorkBuffer").finish()
+    }
+}
+
+/// CTR_DRBG of the nrf_cc310 library, seeded from the TRNG
+///
+/// Short requests, such as `next_u32` for sequence numbers and backoff, are
+/// served from a buffer of generated output instead of a library call each.
+/// Output is cleared from the buffer when it is handed out.
+///
+/// The state is kept in RAM, the CryptoCell is only enabled while an
+/// operation is running. The library sets up the TRNG from the parameters in
+/// the state for every instantiation and reseed.
+#[cfg(feature = "cryptocell")]
+pub struct CryptoCellDrbg<'a> {
+    cryptocell: &'a CryptoCell,
+    state: CRYS_RND_State_t,
+    buffer: [u8; BUFFER_SIZE],
+    /// Number of unused bytes at the end of `buffer`
+    available: usize,
+}
+
+#[cfg(feature = "cryptocell")]
+fn check(result: CRYSError_t) -> Result<(), Error> {
+    if result != 0 {
+        return Err(Error::Other(result));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "cryptocell")]
+impl<'a> CryptoCellDrbg<'a> {
+    /// Instantiate with entropy from the TRNG
+    ///
+    /// A personalization string, such as a device identifier, of at most 48
+    /// bytes is mixed into the seed if not empty.
+    ///
+    /// `CRYS_RndInit` sets up the state and the TRNG, it is called once per
+    /// state and always instantiates, the library has no call that only
+    /// initialises the state. With a personalization string that instantiation
+    /// is replaced by one using the string, so entropy is collected twice.
+    pub fn new(
+        cryptocell: &'a CryptoCell,
+        work: &mut WorkBuffer,
+        personalization: &[u8],
+    ) -> Result<Self, DrbgError> {
+        let mut drbg = Self {
+            cryptocell,
+            // Safety: Plain C structure that is initialised by the library
+            state: unsafe { core::mem::zeroed() },
+            buffer: [0u8; BUFFER_SIZE],
+            available: 0,
+        };
+        {
+            let _access = drbg.cryptocell.access()?;
+            check(unsafe { CRYS_RndInit(drbg.state(), &mut work.buffer) })?;
+        }
+        if !personalization.is_empty() {
+            drbg.instantiate(work, personalization)?;
+        }
+        Ok(drbg)
+    }
+
+    fn state(&mut self) -> *mut cty::c_void {
+        &mut self.state as *mut CRYS_RND_State_t as *mut cty::c_void
+    }
+
+    /// Instantiate again with fresh entropy and an optional personalization
+    /// string, replacing the current state
+    ///
+    /// Also instantiates the DRBG after [`uninstantiate`](Self::uninstantiate).
+    pub fn instantiate(
+        &mut self,
+        work: &mut WorkBuffer,
+        personalization: &[u8],
+    ) -> Result<(), DrbgError> {
+        self.discard();
+        let _access = self.cryptocell.access()?;
+        self.add_input(personalization)?;
+        check(unsafe { CRYS_RND_Instantiation(self.state(), &mut work.buffer) })?;
+        Ok(())
+    }
+
+    /// Reseed with fresh entropy from the TRNG and optional additional input
+    pub fn reseed(
+        &mut self,
+        work: &mut WorkBuffer,
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        self.discard();
+        let _access = self.cryptocell.access()?;
+        self.add_input(additional_input)?;
+        check(unsafe { CRYS_RND_Reseeding(self.state(), &mut work.buffer) })?;
+        Ok(())
+    }
+
+    /// Fill `output` with random bytes, with optional additional input
+    pub fn generate(
+        &mut self,
+        output: &mut [u8],
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        let _access = self.cryptocell.access()?;
+        self.add_input(additional_input)?;
+        for chunk in output.chunks_mut(CRYS_RND_MAX_GEN_VECTOR_SIZE_BYTES as usize) {
+            check(unsafe {
+                CRYS_RND_GenerateVector(self.state(), chunk.len() as u16, chunk.as_mut_ptr())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Clear the state, generating fails until instantiated again
+    pub fn uninstantiate(&mut self) -> Result<(), DrbgError> {
+        self.discard();
+        let _access = self.cryptocell.access()?;
+        check(unsafe { CRYS_RND_UnInstantiation(self.state()) })?;
+        Ok(())
+    }
+
+    /// Set the additional input for the next operation, the caller holds the
+    /// CryptoCell access
+    fn add_input(&mut self, input: &[u8]) -> Result<(), Error> {
+        if input.is_empty() {
+            return Ok(());
+        }
+        const MAX_INPUT_SIZE: usize = CRYS_RND_ADDITINAL_INPUT_MAX_SIZE_WORDS as usize * 4;
+        assert!(input.len() <= MAX_INPUT_SIZE);
+        // The library takes a mutable pointer, use a copy
+        let mut buffer = [0u8; MAX_INPUT_SIZE];
+        buffer[..input.len()].copy_from_slice(input);
+        let result = unsafe {
+            CRYS_RND_AddAdditionalInput(self.state(), buffer.as_mut_ptr(), input.len() as u16)
+        };
+        clear(&mut buffer);
+        check(result)
+    }
+
+    /// Drop the buffered output
+    fn discard(&mut self) {
+        clear(&mut self.buffer);
+        self.available = 0;
+    }
+
+    /// Fill `output` from the buffer, refilling it when it runs out
+    fn fill_buffered(&mut self, mut output: &mut [u8]) -> Result<(), DrbgError> {
+        while !output.is_empty() {
+            if self.available == 0 {
+                let mut buffer = [0u8; BUFFER_SIZE];
+                self.generate(&mut buffer, &[])?;
+                self.buffer = buffer;
+                clear(&mut buffer);
+                self.available = BUFFER_SIZE;
+            }
+            let start = BUFFER_SIZE - self.available;
+            let length = output.len().min(self.available);
+            let (head, tail) = output.split_at_mut(length);
+            head.copy_from_slice(&self.buffer[start..start + length]);
+            clear(&mut self.buffer[start..start + length]);
+            self.available -= length;
+            output = tail;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "cryptocell")]
+impl<'a> RngCore for CryptoCellDrbg<'a> {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if let Err(error) = self.try_fill_bytes(dest) {
+            panic!("Failed to generate random data {}", error);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        if dest.len() < BUFFER_SIZE {
+            self.fill_buffered(dest)?;
+        } else {
+            self.generate(dest, &[])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "cryptocell")]
+impl<'a> CryptoRng for CryptoCellDrbg<'a> {}
+
+#[cfg(feature = "cryptocell")]
+impl<'a> core::ops::Drop for CryptoCellDrbg<'a> {
+    fn drop(&mut self) {
+        self.discard();
+        // Safety: The state is plain data, all zero is the uninstantiated state
+        self.state = unsafe { core::mem::zeroed() };
+    }
+}
+
+#[cfg(feature = "cryptocell")]
+impl<'a> core::fmt::Debug for CryptoCellDrbg<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("CryptoCellDrbg")
+            .field("state", &Redacted)
+            .field("available", &self.available)
+            .finish()
+    }
+}
+
+/// Size of the seed of [`SoftwareDrbg`], the key and V
+#[cfg(feature = "software")]
+pub const SEED_SIZE: usize = KEY_SIZE + BLOCK_SIZE;
+
+/// Requests allowed between reseeds, SP800-90A table 3
+#[cfg(feature = "software")]
+const RESEED_INTERVAL: u64 = 1 << 48;
+
+/// Largest request, 2^19 bits
+#[cfg(feature = "software")]
+const MAX_REQUEST_SIZE: usize = 1 << 16;
+
+/// AES-128 CTR_DRBG without derivation function
+///
+/// The entropy input must have full entropy. Personalization strings and
+/// additional input are at most [`SEED_SIZE`] bytes.
+#[cfg(feature = "software")]
+pub struct SoftwareDrbg {
+    key: [u8; KEY_SIZE],
+    v: [u8; BLOCK_SIZE],
+    reseed_counter: u64,
+}
+
+/// Pad `input` with zeroes to the seed length
+#[cfg(feature = "software")]
+fn pad(input: &[u8]) -> [u8; SEED_SIZE] {
+    assert!(input.len() <= SEED_SIZE);
+    let mut padded = [0u8; SEED_SIZE];
+    padded[..input.len()].copy_from_slice(input);
+    padded
+}
+
+/// Increment a big-endian counter block
+#[cfg(feature = "software")]
+fn increment(block: &mut [u8; BLOCK_SIZE]) {
+    for b in block.iter_mut().rev() {
+        *b = b.wrapping_add(1);
+        if *b != 0 {
+            break;
+        }
+    }
+}
+
+#[cfg(feature = "software")]
+impl SoftwareDrbg {
+    /// Instantiate with `entropy` and an optional personalization string
+    pub fn new(entropy: &[u8; SEED_SIZE], personalization: &[u8]) -> Self {
+        let mut drbg = Self {
+            key: [0u8; KEY_SIZE],
+            v: [0u8; BLOCK_SIZE],
+            reseed_counter: 1,
+        };
+        drbg.reseed(entropy, personalization);
+        drbg
+    }
+
+    /// Reseed with `entropy` and optional additional input
+    pub fn reseed(&mut self, entropy: &[u8; SEED_SIZE], additional_input: &[u8]) {
+        let mut seed_material = pad(additional_input);
+        for (s, e) in seed_material.iter_mut().zip(entropy.iter()) {
+            *s ^= e;
+        }
+        self.update(&seed_material);
+        clear(&mut seed_material);
+        self.reseed_counter = 1;
+    }
+
+    /// Fill `output` with random bytes, with optional additional input
+    ///
+    /// Output longer than the largest request is generated with multiple
+    /// requests, each using the additional input.
+    pub fn generate(
+        &mut self,
+        output: &mut [u8],
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        let mut additional_input = if additional_input.is_empty() {
+            None
+        } else {
+            Some(pad(additional_input))
+        };
+        let result = output
+            .chunks_mut(MAX_REQUEST_SIZE)
+            .try_for_each(|chunk| self.request(chunk, additional_input.as_ref()));
+        if let Some(input) = additional_input.as_mut() {
+            clear(input);
+        }
+        result
+    }
+
+    /// Generate function of SP800-90A section 10.2.1.5.1
+    fn request(
+        &mut self,
+        output: &mut [u8],
+        additional_input: Option<&[u8; SEED_SIZE]>,
+    ) -> Result<(), DrbgError> {
+        if self.reseed_counter > RESEED_INTERVAL {
+            return Err(DrbgError::ReseedRequired);
+        }
+        if let Some(input) = additional_input {
+            self.update(input);
+        }
+        let cipher = self.cipher();
+        for chunk in output.chunks_mut(BLOCK_SIZE) {
+            increment(&mut self.v);
+            let mut block = GenericArray::clone_from_slice(&self.v);
+            cipher.encrypt_block(&mut block);
+            chunk.copy_from_slice(&block[..chunk.len()]);
+            clear(&mut block);
+        }
+        self.update(additional_input.unwrap_or(&[0u8; SEED_SIZE]));
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    fn cipher(&self) -> Aes128 {
+        Aes128::new(GenericArray::from_slice(&self.key))
+    }
+
+    /// Update function of SP800-90A section 10.2.1.2
+    fn update(&mut self, provided_data: &[u8; SEED_SIZE]) {
+        let cipher = self.cipher();
+        let mut temp = [0u8; SEED_SIZE];
+        for (chunk, data) in temp
+            .chunks_mut(BLOCK_SIZE)
+            .zip(provided_data.chunks(BLOCK_SIZE))
+        {
+            increment(&mut self.v);
+            let block = GenericArray::from_mut_slice(chunk);
+            block.copy_from_slice(&self.v);
+            cipher.encrypt_block(block);
+            for (t, d) in chunk.iter_mut().zip(data.iter()) {
+                *t ^= d;
+            }
+        }
+        self.key.copy_from_slice(&temp[..KEY_SIZE]);
+        self.v.copy_from_slice(&temp[KEY_SIZE..]);
+        clear(&mut temp);
+    }
+}
+
+#[cfg(feature = "software")]
+impl SeedableRng for SoftwareDrbg {
+    type Seed = [u8; SEED_SIZE];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self::new(&seed, &[])
+    }
+}
+
+#[cfg(feature = "software")]
+impl RngCore for SoftwareDrbg {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if let Err(error) = self.try_fill_bytes(dest) {
+            panic!("Failed to generate random data {}", error);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        Ok(self.generate(dest, &[])?)
+    }
+}
+
+#[cfg(feature = "software")]
+impl CryptoRng for SoftwareDrbg {}
+
+#[cfg(feature = "software")]
+impl core::ops::Drop for SoftwareDrbg {
+    fn drop(&mut self) {
+        clear(&mut self.key);
+        clear(&mut self.v);
+    }
+}
+
+#[cfg(feature = "software")]
+impl core::fmt::Debug for SoftwareDrbg {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("SoftwareDrbg")
+            .field("state", &Redacted)
+            .field("reseed_counter", &self.reseed_counter)
+            .finish()
+    }
+}
+
+#[cfg(all(test, feature = "software"))]
+mod tests {
+    use super::*;
+
+    // The NIST CAVP files are not included, these vectors were computed with
+    // the CTR_DRBG of OpenSSL, AES-128 without derivation function, following
+    // the CAVP procedure: instantiate, optionally reseed, generate twice and
+    // check the output of the second generate. The inputs are SHA-256 hashes
+    // of fixed labels.
+
+    /// Entropy input of the instantiation
+    const ENTROPY: [u8; 32] = [
+        0x67, 0x67, 0x1a, 0x2f, 0x53, 0xdd, 0x91, 0x0a, 0x8b, 0x35, 0x84, 0x0e, 0xdb, 0x6a, 0x0a,
+        0x1e, 0x75, 0x1a, 0xe5, 0x53, 0x21, 0x78, 0xca, 0x7f, 0x02, 0x5b, 0x82, 0x3e, 0xee, 0x31,
+        0x79, 0x92,
+    ];
+    /// Personalization string
+    const PERSONALIZATION: [u8; 32] = [
+        0xfc, 0x0e, 0x1b, 0x7a, 0x7a, 0x08, 0x57, 0xc9, 0x70, 0x97, 0x68, 0x4a, 0xc3, 0xc4, 0x0a,
+        0xf0, 0xf0, 0x69, 0x87, 0xbb, 0xd6, 0xff, 0x71, 0x20, 0x6c, 0x9f, 0xeb, 0x3b, 0x52, 0x05,
+        0x07, 0xa2,
+    ];
+    /// Entropy input of the reseed
+    const RESEED_ENTROPY: [u8; 32] = [
+        0xf3, 0x2a, 0x38, 0xec, 0x9a, 0x21, 0x89, 0xf2, 0xd1, 0x1d, 0x53, 0x74, 0x58, 0x86, 0xbe,
+        0x6f, 0x9b, 0x27, 0xc4, 0x8e, 0xc0, 0xf3, 0x82, 0x48, 0xbe, 0x7d, 0xba, 0x66, 0xff, 0xe8,
+        0x7e, 0x8d,
+    ];
+    /// Additional input of the reseed
+    const RESEED_INPUT: [u8; 32] = [
+        0x50, 0xf5, 0x78, 0x70, 0x51, 0x8c, 0xff, 0x61, 0x57, 0x38, 0x76, 0xaa, 0x8e, 0x1f, 0xa2,
+        0x70, 0xdd, 0xad, 0x42, 0xeb, 0x63, 0x03, 0xe1, 0x47, 0x79, 0x06, 0xa4, 0x23, 0x21, 0xc8,
+        0xb9, 0x35,
+    ];
+    /// Additional input of the first generate
+    const ADDITIONAL_INPUT_1: [u8; 32] = [
+        0x80, 0xb9, 0x8c, 0x55, 0x93, 0xc5, 0xf2, 0x59, 0xf0, 0x9b, 0x45, 0x9b, 0x4d, 0x28, 0x44,
+        0xe1, 0xcc, 0xf0, 0x84, 0x12, 0xee, 0x95, 0xb4, 0x4a, 0x06, 0xc5, 0x81, 0x3a, 0x74, 0x15,
+        0x4c, 0x38,
+    ];
+    /// Additional input of the second generate
+    const ADDITIONAL_INPUT_2: [u8; 32] = [
+        0xbf, 0x75, 0xc1, 0x36, 0x3b, 0x63, 0x8a, 0xef, 0x9a, 0x37, 0xa5, 0xd0, 0x7d, 0x62, 0x42,
+        0x1e, 0x58, 0xa3, 0x81, 0x66, 0x64, 0x77, 0x8a, 0x39, 0xfe, 0x39, 0x22, 0x35, 0xdb, 0x63,
+        0x64, 0x80,
+    ];
+    /// Second output without personalization, reseed or additional input
+    const OUTPUT: [u8; 64] = [
+        0x2c, 0xd9, 0xcf, 0x99, 0xaf, 0xf9, 0x9f, 0x87, 0xa3, 0xcd, 0x1f, 0xa2, 0x63, 0x3c, 0x45,
+        0xdf, 0x8a, 0xe0, 0x0c, 0x4b, 0x5f, 0x9a, 0x25, 0x5b, 0x28, 0xd0, 0x99, 0x76, 0x5c, 0x21,
+        0x6c, 0xc0, 0x33, 0x83, 0x01, 0xb1, 0x70, 0x11, 0x78, 0x40, 0x0e, 0x3a, 0xc4, 0xdb, 0xa3,
+        0x4a, 0x89, 0x9e, 0x7d, 0x9f, 0x5a, 0xe7, 0x9e, 0xfb, 0x6d, 0xd8, 0x05, 0x22, 0x46, 0x1e,
+        0x44, 0x78, 0x0c, 0x99,
+    ];
+    /// Second output with personalization, reseed and additional input
+    const OUTPUT_RESEED: [u8; 64] = [
+        0xfc, 0xcd, 0xfa, 0x30, 0xd4, 0x3d, 0xf9, 0x76, 0x30, 0x11, 0x85, 0x85, 0xf3, 0xda, 0x08,
+        0xb9, 0x7c, 0xe7, 0x5f, 0x32, 0x39, 0x9f, 0x04, 0xdf, 0xad, 0xa3, 0xbf, 0x3b, 0x72, 0x78,
+        0x72, 0x9d, 0x65, 0x5e, 0x97, 0x29, 0xb2, 0x6b, 0x11, 0x08, 0x3c, 0xaf, 0x5f, 0x62, 0xb5,
+        0xd7, 0x17, 0x97, 0x05, 0xbf, 0x91, 0xe0, 0xca, 0xad, 0xc4, 0xdb, 0x8a, 0x71, 0x2f, 0xd2,
+        0x44, 0x8c, 0x17, 0x3d,
+    ];
+    /// Second output with personalization and additional input
+    const OUTPUT_ADDITIONAL_INPUT: [u8; 64] = [
+        0x35, 0xc1, 0xb2, 0xc2, 0xc1, 0xe1, 0x37, 0xa8, 0x2c, 0x54, 0x0f, 0xd3, 0xf8, 0xe7, 0xd7,
+        0x12, 0xdc, 0xfc, 0xcb, 0xa9, 0x07, 0x4c, 0x33, 0x54, 0xcc, 0x7b, 0xa6, 0x78, 0x5c, 0xf4,
+        0xc8, 0x81, 0xf2, 0x3b, 0xf2, 0x38, 0x36, 0x69, 0xd8, 0x7d, 0xed, 0xe7, 0xf8, 0x2e, 0xc2,
+        0xc0, 0xc3, 0x59, 0x23, 0x3b, 0x3f, 0xba, 0xa9, 0x98, 0x2d, 0x9c, 0x3a, 0xae, 0xd4, 0x97,
+        0x96, 0xfe, 0xcc, 0xf3,
+    ];
+
+    #[test]
+    fn generate() {
+        let mut drbg = SoftwareDrbg::new(&ENTROPY, &[]);
+        let mut output = [0u8; 64];
+        drbg.generate(&mut output, &[]).unwrap();
+        drbg.generate(&mut output, &[]).unwrap();
+        assert_eq!(output, OUTPUT);
+    }
+
+    #[test]
+    fn generate_reseed() {
+        let mut drbg = SoftwareDrbg::new(&ENTROPY, &PERSONALIZATION);
+        drbg.reseed(&RESEED_ENTROPY, &RESEED_INPUT);
+        let mut output = [0u8; 64];
+        drbg.generate(&mut output, &ADDITIONAL_INPUT_1).unwrap();
+        drbg.generate(&mut output, &ADDITIONAL_INPUT_2).unwrap();
+        assert_eq!(output, OUTPUT_RESEED);
+    }
+
+    #[test]
+    fn generate_additional_input() {
+        let mut drbg = SoftwareDrbg::new(&ENTROPY, &PERSONALIZATION);
+        let mut output = [0u8; 64];
+        drbg.generate(&mut output, &ADDITIONAL_INPUT_1).unwrap();
+        drbg.generate(&mut output, &ADDITIONAL_INPUT_2).unwrap();
+        assert_eq!(output, OUTPUT_ADDITIONAL_INPUT);
+    }
+
+    #[test]
+    fn same_seed() {
+        let mut first = SoftwareDrbg::from_seed(ENTROPY);
+        let mut second = SoftwareDrbg::from_seed(ENTROPY);
+        let mut a = [0u8; 100];
+        let mut b = [0u8; 100];
+        first.fill_bytes(&mut a);
+        second.fill_bytes(&mut b);
+        assert_eq!(a[..], b[..]);
+        assert_eq!(first.next_u64(), second.next_u64());
+
+        let mut other = SoftwareDrbg::from_seed(RESEED_ENTROPY);
+        other.fill_bytes(&mut b);
+        assert_ne!(a[..], b[..]);
+    }
+}
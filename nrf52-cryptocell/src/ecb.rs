@@ -0,0 +1,219 @@
+//! AES ECB peripheral backend
+//!
+//! Crypto backend for nRF52 devices without a CryptoCell, such as the
+//! nRF52832 and nRF52833. The AES ECB peripheral only provides 128-bit AES
+//! block encryption, CCM* is built on top of it in software.
+//!
+//! Each block is encrypted by starting the peripheral and busy-waiting for
+//! the ENDECB or ERRORECB event, without a timeout. A block normally takes a
+//! few microseconds, but a CCM* operation waits for every block in turn. Do
+//! not use the backend from a high priority interrupt handler, such as the
+//! radio interrupt, as it blocks all lower priority work meanwhile. The ECB
+//! peripheral is aborted, and the operation fails with `Error::EcbAborted`,
+//! when the CCM or AAR peripheral needs the AES core.
+
+use crate::{
+    ccmstar, clear, error::Redacted, pac::ECB, CcmStarInPlace, Error, BLOCK_SIZE, KEY_SIZE,
+};
+
+/// Memory block used by the ECB peripheral
+#[repr(C)]
+struct EcbData {
+    key: [u8; KEY_SIZE],
+    clear_text: [u8; BLOCK_SIZE],
+    cipher_text: [u8; BLOCK_SIZE],
+}
+
+/// Crypto backend using the AES ECB peripheral
+pub struct EcbBackend {
+    ecb: ECB,
+    data: EcbData,
+    /// Key for the psila AES-128-ECB operations
+    #[cfg(feature = "psila")]
+    ecb_key: [u8; KEY_SIZE],
+}
+
+impl EcbBackend {
+    pub fn new(ecb: ECB) -> Self {
+        ecb.intenclr
+            .write(|w| w.endecb().clear().errorecb().clear());
+        Self {
+            ecb,
+            data: EcbData {
+                key: [0u8; KEY_SIZE],
+                clear_text: [0u8; BLOCK_SIZE],
+                cipher_text: [0u8; BLOCK_SIZE],
+            },
+            #[cfg(feature = "psila")]
+            ecb_key: [0u8; KEY_SIZE],
+        }
+    }
+
+    /// Encrypt a single block in place using `key`
+    ///
+    /// Busy-waits for the peripheral, see the module documentation.
+    fn encrypt_block(
+        ecb: &ECB,
+        data: &mut EcbData,
+        key: &[u8],
+        block: &mut [u8; BLOCK_SIZE],
+    ) -> Result<(), Error> {
+        data.key.copy_from_slice(key);
+        data.clear_text.copy_from_slice(block);
+        ecb.ecbdataptr
+            .write(|w| unsafe { w.bits(data as *mut EcbData as u32) });
+        ecb.events_endecb.reset();
+        ecb.events_errorecb.reset();
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+        ecb.tasks_startecb.write(|w| unsafe { w.bits(1) });
+        loop {
+            if ecb.events_endecb.read().bits() != 0 {
+                break;
+            }
+            if ecb.events_errorecb.read().bits() != 0 {
+                return Err(Error::EcbAborted);
+            }
+        }
+        ecb.events_endecb.reset();
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+        block.copy_from_slice(&data.cipher_text);
+        Ok(())
+    }
+
+    /// Encrypt full blocks, the last block is zero padded
+    #[cfg(feature = "psila")]
+    fn encrypt_blocks(&mut self, input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        assert!(output.len() >= ((input.len() + (BLOCK_SIZE - 1)) / BLOCK_SIZE) * BLOCK_SIZE);
+        for (i, o) in input.chunks(BLOCK_SIZE).zip(output.chunks_mut(BLOCK_SIZE)) {
+            let mut block = [0u8; BLOCK_SIZE];
+            block[..i.len()].copy_from_slice(i);
+            Self::encrypt_block(&self.ecb, &mut self.data, &self.ecb_key, &mut block)?;
+            o.copy_from_slice(&block);
+        }
+        Ok(())
+    }
+}
+
+impl core::fmt::Debug for EcbBackend {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("EcbBackend")
+            .field("key", &Redacted)
+            .finish()
+    }
+}
+
+impl core::ops::Drop for EcbBackend {
+    fn drop(&mut self) {
+        clear(&mut self.data.key);
+        clear(&mut self.data.clear_text);
+        clear(&mut self.data.cipher_text);
+        #[cfg(feature = "psila")]
+        clear(&mut self.ecb_key);
+    }
+}
+
+#[cfg(feature = "psila")]
+impl crate::CryptoBackend for EcbBackend {
+    fn ccmstar_decrypt(
+        &mut self,
+        key: &[u8],
+        nonce: &[u8],
+        encrypted: &[u8],
+        mic: &[u8],
+        aad: &[u8],
+        decrypted: &mut [u8],
+    ) -> Result<usize, psila_crypto::Error> {
+        assert!(key.len() == KEY_SIZE);
+        let ecb = &self.ecb;
+        let data = &mut self.data;
+        let mut encrypt = |block: &mut [u8; BLOCK_SIZE]| Self::encrypt_block(ecb, data, key, block);
+        Ok(ccmstar::decrypt(
+            &mut encrypt,
+            nonce,
+            encrypted,
+            mic,
+            aad,
+            decrypted,
+        )?)
+    }
+
+    fn ccmstar_encrypt(
+        &mut self,
+        key: &[u8],
+        nonce: &[u8],
+        message: &[u8],
+        mic: &mut [u8],
+        aad: &[u8],
+        output: &mut [u8],
+    ) -> Result<usize, psila_crypto::Error> {
+        assert!(key.len() == KEY_SIZE);
+        let ecb = &self.ecb;
+        let data = &mut self.data;
+        let mut encrypt = |block: &mut [u8; BLOCK_SIZE]| Self::encrypt_block(ecb, data, key, block);
+        Ok(ccmstar::encrypt(
+            &mut encrypt,
+            nonce,
+            message,
+            mic,
+            aad,
+            output,
+        )?)
+    }
+
+    /// Set the key
+    fn aes128_ecb_encrypt_set_key(&mut self, key: &[u8]) -> Result<(), psila_crypto::Error> {
+        assert!(key.len() == KEY_SIZE);
+        self.ecb_key.copy_from_slice(key);
+        Ok(())
+    }
+    /// Process blocks of data
+    fn aes128_ecb_encrypt_process_block(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), psila_crypto::Error> {
+        Ok(self.encrypt_blocks(input, output)?)
+    }
+    /// Process the last bits and bobs and finish
+    fn aes128_ecb_encrypt_finish(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), psila_crypto::Error> {
+        Ok(self.encrypt_blocks(input, output)?)
+    }
+}
+
+impl CcmStarInPlace for EcbBackend {
+    fn ccmstar_encrypt_in_place(
+        &mut self,
+        key: &[u8],
+        nonce: &[u8],
+        data: &mut [u8],
+        mic: &mut [u8],
+        aad: &[u8],
+    ) -> Result<(), Error> {
+        assert!(key.len() == KEY_SIZE);
+        let ecb = &self.ecb;
+        let data_block = &mut self.data;
+        let mut encrypt =
+            |block: &mut [u8; BLOCK_SIZE]| Self::encrypt_block(ecb, data_block, key, block);
+        ccmstar::encrypt_in_place(&mut encrypt, nonce, data, mic, aad)
+    }
+
+    fn ccmstar_decrypt_in_place(
+        &mut self,
+        key: &[u8],
+        nonce: &[u8],
+        data: &mut [u8],
+        mic: &[u8],
+        aad: &[u8],
+    ) -> Result<bool, Error> {
+        assert!(key.len() == KEY_SIZE);
+        let ecb = &self.ecb;
+        let data_block = &mut self.data;
+        let mut encrypt =
+            |block: &mut [u8; BLOCK_SIZE]| Self::encrypt_block(ecb, data_block, key, block);
+        ccmstar::decrypt_in_place(&mut encrypt, nonce, data, mic, aad)
+    }
+}
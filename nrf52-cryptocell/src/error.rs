@@ -0,0 +1,212 @@
+//! Error codes
+//!
+//! Errors detected by this crate have their own variants. Errors from the
+//! nrf_cc310 library are reported as `Error::Other` with the library code.
+//! [`error_name`] looks up the name of a library code, `Debug` and, with the
+//! `defmt` feature, `defmt` output include the name.
+
+use core::fmt;
+
+/// Error reported by the hardware, the library or this crate
+///
+/// Has the same shape as `psila_crypto::Error`, which it converts into when
+/// the `psila` feature is enabled.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Error {
+    /// The CryptoCell is busy with another operation
+    Busy,
+    /// The PKCS7 padding of decrypted data is invalid
+    InvalidPadding,
+    /// The integrity check of a wrapped key failed
+    UnwrapFailed,
+    /// The ECB peripheral aborted the operation, because a higher priority
+    /// peripheral, such as the CCM or AAR, needed the AES core
+    EcbAborted,
+    /// Malformed or unsupported frame
+    InvalidFrame,
+    /// The MIC of a frame is invalid
+    InvalidMic,
+    /// The source address needed for the nonce is unknown
+    NoSourceAddress,
+    /// The device root key is not loaded
+    NoRootKey,
+    /// A device root key is already loaded, it can only be loaded once after
+    /// reset
+    RootKeyLoaded,
+    /// The CryptoCell didn't retain the device root key written to the KDR
+    /// registers
+    RootKeyNotRetained,
+    /// Library error code, see [`error_name`]
+    Other(u32),
+}
+
+/// First numeric code used for the errors detected by this crate
+///
+/// The library codes have a module base in the upper bits, so codes below
+/// `0x0001_0000` never collide with them.
+const CRATE_ERROR_BASE: u32 = 0x0000_cc00;
+
+impl Error {
+    /// Numeric code of the error
+    ///
+    /// Used where the error has to be passed on as a number, such as
+    /// `psila_crypto::Error::Other` and `rand_core::Error`.
+    pub fn code(&self) -> u32 {
+        match *self {
+            Error::Busy => CRATE_ERROR_BASE + 1,
+            Error::InvalidPadding => CRATE_ERROR_BASE + 2,
+            Error::UnwrapFailed => CRATE_ERROR_BASE + 3,
+            Error::EcbAborted => CRATE_ERROR_BASE + 4,
+            Error::InvalidFrame => CRATE_ERROR_BASE + 5,
+            Error::InvalidMic => CRATE_ERROR_BASE + 6,
+            Error::NoSourceAddress => CRATE_ERROR_BASE + 7,
+            Error::NoRootKey => CRATE_ERROR_BASE + 8,
+            Error::RootKeyLoaded => CRATE_ERROR_BASE + 9,
+            Error::RootKeyNotRetained => CRATE_ERROR_BASE + 10,
+            Error::Other(code) => code,
+        }
+    }
+}
+
+#[cfg(feature = "psila")]
+impl From<Error> for psila_crypto::Error {
+    fn from(error: Error) -> Self {
+        psila_crypto::Error::Other(error.code())
+    }
+}
+
+#[cfg(feature = "cryptocell")]
+use crate::nrf_cc310::*;
+
+/// Mask selecting the module part of a library error code
+#[cfg(feature = "cryptocell")]
+const MODULE_MASK: u32 = 0xffff_ff00;
+
+/// Name of a library error code
+///
+/// Returns the name used in the library headers for AES and RND errors and
+/// the module name for other library errors. Returns `None` for unknown codes
+/// and when the `cryptocell` feature is disabled.
+pub fn error_name(code: u32) -> Option<&'static str> {
+    #[cfg(feature = "cryptocell")]
+    return library_error_name(code);
+    #[cfg(not(feature = "cryptocell"))]
+    {
+        let _ = code;
+        None
+    }
+}
+
+#[cfg(feature = "cryptocell")]
+fn library_error_name(code: u32) -> Option<&'static str> {
+    let name = match code {
+        SASI_AES_INVALID_USER_CONTEXT_POINTER_ERROR => {
+            "SASI_AES_INVALID_USER_CONTEXT_POINTER_ERROR"
+        }
+        SASI_AES_INVALID_IV_OR_TWEAK_PTR_ERROR => "SASI_AES_INVALID_IV_OR_TWEAK_PTR_ERROR",
+        SASI_AES_ILLEGAL_OPERATION_MODE_ERROR => "SASI_AES_ILLEGAL_OPERATION_MODE_ERROR",
+        SASI_AES_ILLEGAL_KEY_SIZE_ERROR => "SASI_AES_ILLEGAL_KEY_SIZE_ERROR",
+        SASI_AES_INVALID_KEY_POINTER_ERROR => "SASI_AES_INVALID_KEY_POINTER_ERROR",
+        SASI_AES_KEY_TYPE_NOT_SUPPORTED_ERROR => "SASI_AES_KEY_TYPE_NOT_SUPPORTED_ERROR",
+        SASI_AES_INVALID_ENCRYPT_MODE_ERROR => "SASI_AES_INVALID_ENCRYPT_MODE_ERROR",
+        SASI_AES_USER_CONTEXT_CORRUPTED_ERROR => "SASI_AES_USER_CONTEXT_CORRUPTED_ERROR",
+        SASI_AES_DATA_IN_POINTER_INVALID_ERROR => "SASI_AES_DATA_IN_POINTER_INVALID_ERROR",
+        SASI_AES_DATA_OUT_POINTER_INVALID_ERROR => "SASI_AES_DATA_OUT_POINTER_INVALID_ERROR",
+        SASI_AES_DATA_IN_SIZE_ILLEGAL => "SASI_AES_DATA_IN_SIZE_ILLEGAL",
+        SASI_AES_DATA_OUT_DATA_IN_OVERLAP_ERROR => "SASI_AES_DATA_OUT_DATA_IN_OVERLAP_ERROR",
+        SASI_AES_DATA_OUT_SIZE_POINTER_INVALID_ERROR => {
+            "SASI_AES_DATA_OUT_SIZE_POINTER_INVALID_ERROR"
+        }
+        SASI_AES_CTX_SIZES_ERROR => "SASI_AES_CTX_SIZES_ERROR",
+        SASI_AES_ILLEGAL_PARAMS_ERROR => "SASI_AES_ILLEGAL_PARAMS_ERROR",
+        SASI_AES_CTR_ILLEGAL_BLOCK_OFFSET_ERROR => "SASI_AES_CTR_ILLEGAL_BLOCK_OFFSET_ERROR",
+        SASI_AES_CTR_ILLEGAL_COUNTER_ERROR => "SASI_AES_CTR_ILLEGAL_COUNTER_ERROR",
+        SASI_AES_NOT_ALLOWED_KEY_TYPE_ON_THIS_PLATFORM => {
+            "SASI_AES_NOT_ALLOWED_KEY_TYPE_ON_THIS_PLATFORM"
+        }
+        SASI_AES_INCORRECT_PADDING_ERROR => "SASI_AES_INCORRECT_PADDING_ERROR",
+        SASI_AES_CORRUPTED_OUTPUT_ERROR => "SASI_AES_CORRUPTED_OUTPUT_ERROR",
+        SASI_AES_IS_NOT_SUPPORTED => "SASI_AES_IS_NOT_SUPPORTED",
+        CRYS_AESCCM_CCM_MAC_INVALID_ERROR => "CRYS_AESCCM_CCM_MAC_INVALID_ERROR",
+        CRYS_RND_RESEED_COUNTER_OVERFLOW_ERROR => "CRYS_RND_RESEED_COUNTER_OVERFLOW_ERROR",
+        CRYS_RND_INSTANTIATION_NOT_DONE_ERROR => "CRYS_RND_INSTANTIATION_NOT_DONE_ERROR",
+        CRYS_RND_TRNG_LOSS_SAMPLES_ERROR => "CRYS_RND_TRNG_LOSS_SAMPLES_ERROR",
+        CRYS_RND_TRNG_TIME_EXCEED_ERROR => "CRYS_RND_TRNG_TIME_EXCEED_ERROR",
+        CRYS_RND_TRNG_LOSS_SAMPLES_AND_TIME_EXCEED_ERROR => {
+            "CRYS_RND_TRNG_LOSS_SAMPLES_AND_TIME_EXCEED_ERROR"
+        }
+        CRYS_RND_CPRNG_TEST_FAIL_ERROR => "CRYS_RND_CPRNG_TEST_FAIL_ERROR",
+        _ => match code & MODULE_MASK {
+            SASI_AES_MODULE_ERROR_BASE => "SASI_AES error",
+            CRYS_AESCCM_MODULE_ERROR_BASE => "CRYS_AESCCM error",
+            CRYS_ECPKI_MODULE_ERROR_BASE => "CRYS_ECPKI error",
+            CRYS_RND_MODULE_ERROR_BASE => "CRYS_RND error",
+            _ => return None,
+        },
+    };
+    Some(name)
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Busy => f.write_str("Busy"),
+            Error::InvalidPadding => f.write_str("InvalidPadding"),
+            Error::UnwrapFailed => f.write_str("UnwrapFailed"),
+            Error::EcbAborted => f.write_str("EcbAborted"),
+            Error::InvalidFrame => f.write_str("InvalidFrame"),
+            Error::InvalidMic => f.write_str("InvalidMic"),
+            Error::NoSourceAddress => f.write_str("NoSourceAddress"),
+            Error::NoRootKey => f.write_str("NoRootKey"),
+            Error::RootKeyLoaded => f.write_str("RootKeyLoaded"),
+            Error::RootKeyNotRetained => f.write_str("RootKeyNotRetained"),
+            Error::Other(code) => match error_name(code) {
+                Some(name) => write!(f, "Other({:#010x} {})", code, name),
+                None => write!(f, "Other({:#010x})", code),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Error {
+    fn format(&self, f: defmt::Formatter) {
+        match *self {
+            Error::Busy => defmt::write!(f, "Busy"),
+            Error::InvalidPadding => defmt::write!(f, "InvalidPadding"),
+            Error::UnwrapFailed => defmt::write!(f, "UnwrapFailed"),
+            Error::EcbAborted => defmt::write!(f, "EcbAborted"),
+            Error::InvalidFrame => defmt::write!(f, "InvalidFrame"),
+            Error::InvalidMic => defmt::write!(f, "InvalidMic"),
+            Error::NoSourceAddress => defmt::write!(f, "NoSourceAddress"),
+            Error::NoRootKey => defmt::write!(f, "NoRootKey"),
+            Error::RootKeyLoaded => defmt::write!(f, "RootKeyLoaded"),
+            Error::RootKeyNotRetained => defmt::write!(f, "RootKeyNotRetained"),
+            Error::Other(code) => match error_name(code) {
+                Some(name) => defmt::write!(f, "Other({=u32:x} {=str})", code, name),
+                None => defmt::write!(f, "Other({=u32:x})", code),
+            },
+        }
+    }
+}
+
+/// Placeholder for key material in `Debug` and `defmt` output
+#[cfg(any(feature = "cryptocell", feature = "ecb", feature = "software"))]
+pub(crate) struct Redacted;
+
+#[cfg(any(feature = "cryptocell", feature = "ecb", feature = "software"))]
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+#[cfg(all(
+    feature = "defmt",
+    any(feature = "cryptocell", feature = "ecb", feature = "software")
+))]
+impl defmt::Format for Redacted {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "<redacted>")
+    }
+}
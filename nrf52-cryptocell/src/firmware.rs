@@ -0,0 +1,737 @@
+//! Signed firmware image verification
+//!
+//! A firmware slot starts with an [`ImageHeader`] followed by the image. The
+//! SHA-256 digest covers the signed part of the header, with the image size,
+//! version and signature algorithm, and the image itself. The signature is
+//! made over the digest, both for ECDSA P-256 and Ed25519, so only the digest
+//! is passed to the signature algorithm.
+//!
+//! [`verify`] parses the header, hashes the image and checks the signature
+//! against a public key using a [`SignatureBackend`]. `CryptoCellBackend`
+//! implements the backend with the CryptoCell. `SoftwareBackend`, enabled by
+//! the `software` feature, uses software implementations and runs on the
+//! host, for signing tools and for testing the verification flow.
+//!
+//! ```text
+//! offset  size  field
+//!      0     4  magic, IMAGE_MAGIC little-endian
+//!      4     2  header size, little-endian, at least HEADER_SIZE
+//!      6     1  signature algorithm
+//!      7     1  reserved, zero
+//!      8     4  image size, little-endian
+//!     12     4  image version, little-endian
+//!     16    64  signature
+//! ```
+
+use core::ops::Range;
+
+use crate::Error;
+
+#[cfg(feature = "cryptocell")]
+use crate::{nrf_cc310::*, CryptoCellBackend};
+
+/// Magic number at the start of a firmware slot
+pub const IMAGE_MAGIC: u32 = 0x4d49_5746;
+/// Size of the image header, the header may be padded to align the image
+pub const HEADER_SIZE: usize = 80;
+/// Size of the part of the header covered by the signature
+pub const SIGNED_HEADER_SIZE: usize = 16;
+/// Size of a SHA-256 digest
+pub const DIGEST_SIZE: usize = 32;
+/// Size of a signature, r and s for ECDSA, R and S for Ed25519
+pub const SIGNATURE_SIZE: usize = 64;
+/// Size of an uncompressed P-256 public key, 0x04 followed by x and y
+pub const P256_PUBLIC_KEY_SIZE: usize = 65;
+/// Size of an Ed25519 public key
+pub const ED25519_PUBLIC_KEY_SIZE: usize = 32;
+
+/// Signature algorithm of an image
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SignatureAlgorithm {
+    /// ECDSA with P-256 over the SHA-256 digest
+    EcdsaP256Sha256 = 1,
+    /// Ed25519 over the SHA-256 digest
+    Ed25519Sha256 = 2,
+}
+
+impl SignatureAlgorithm {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(SignatureAlgorithm::EcdsaP256Sha256),
+            2 => Some(SignatureAlgorithm::Ed25519Sha256),
+            _ => None,
+        }
+    }
+}
+
+/// Public key used to verify images
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PublicKey<'a> {
+    /// Uncompressed P-256 public key
+    EcdsaP256(&'a [u8; P256_PUBLIC_KEY_SIZE]),
+    /// Ed25519 public key
+    Ed25519(&'a [u8; ED25519_PUBLIC_KEY_SIZE]),
+}
+
+/// Reason for an image to fail verification
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum VerifyError {
+    /// No image header, the slot is empty or erased
+    NoImage,
+    /// The slot is too small for the header or the image
+    Truncated,
+    /// The header is malformed
+    InvalidHeader,
+    /// The signature algorithm is unknown
+    UnsupportedAlgorithm(u8),
+    /// The image is signed with another algorithm than the public key uses
+    AlgorithmMismatch,
+    /// The signature does not match the image and public key
+    InvalidSignature,
+    /// The backend failed
+    Backend(Error),
+}
+
+#[cfg(feature = "defmt")]
+impl<'a> defmt::Format for PublicKey<'a> {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            PublicKey::EcdsaP256(key) => defmt::write!(f, "EcdsaP256({=[u8]:x})", &key[..]),
+            PublicKey::Ed25519(key) => defmt::write!(f, "Ed25519({=[u8]:x})", &key[..]),
+        }
+    }
+}
+
+impl From<Error> for VerifyError {
+    fn from(error: Error) -> Self {
+        VerifyError::Backend(error)
+    }
+}
+
+/// Image header
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageHeader {
+    /// Size of the header including padding, the image starts here
+    pub header_size: usize,
+    pub algorithm: SignatureAlgorithm,
+    /// Size of the image in bytes
+    pub image_size: usize,
+    pub version: u32,
+    pub signature: [u8; SIGNATURE_SIZE],
+}
+
+impl ImageHeader {
+    /// Parse the header at the start of `slot`
+    pub fn parse(slot: &[u8]) -> Result<Self, VerifyError> {
+        if slot.len() < HEADER_SIZE {
+            return Err(VerifyError::Truncated);
+        }
+        if u32::from_le_bytes([slot[0], slot[1], slot[2], slot[3]]) != IMAGE_MAGIC {
+            return Err(VerifyError::NoImage);
+        }
+        let header_size = u16::from_le_bytes([slot[4], slot[5]]) as usize;
+        if header_size < HEADER_SIZE || slot[7] != 0 {
+            return Err(VerifyError::InvalidHeader);
+        }
+        let algorithm = SignatureAlgorithm::from_u8(slot[6])
+            .ok_or(VerifyError::UnsupportedAlgorithm(slot[6]))?;
+        let image_size = u32::from_le_bytes([slot[8], slot[9], slot[10], slot[11]]) as usize;
+        let version = u32::from_le_bytes([slot[12], slot[13], slot[14], slot[15]]);
+        let mut signature = [0u8; SIGNATURE_SIZE];
+        signature.copy_from_slice(&slot[SIGNED_HEADER_SIZE..SIGNED_HEADER_SIZE + SIGNATURE_SIZE]);
+        let header = Self {
+            header_size,
+            algorithm,
+            image_size,
+            version,
+            signature,
+        };
+        match header_size.checked_add(image_size) {
+            Some(end) if end <= slot.len() => Ok(header),
+            _ => Err(VerifyError::Truncated),
+        }
+    }
+
+    /// Write the header into `buffer`, used by signing tools
+    ///
+    /// Returns the number of bytes written, the padding up to the header size
+    /// is left as is.
+    pub fn encode(&self, buffer: &mut [u8]) -> usize {
+        assert!(self.header_size >= HEADER_SIZE && self.header_size <= u16::MAX as usize);
+        assert!(buffer.len() >= HEADER_SIZE);
+        buffer[0..4].copy_from_slice(&IMAGE_MAGIC.to_le_bytes());
+        buffer[4..6].copy_from_slice(&(self.header_size as u16).to_le_bytes());
+        buffer[6] = self.algorithm as u8;
+        buffer[7] = 0;
+        buffer[8..12].copy_from_slice(&(self.image_size as u32).to_le_bytes());
+        buffer[12..16].copy_from_slice(&self.version.to_le_bytes());
+        buffer[SIGNED_HEADER_SIZE..HEADER_SIZE].copy_from_slice(&self.signature);
+        HEADER_SIZE
+    }
+
+    /// Location of the image in the slot
+    pub fn image_range(&self) -> Range<usize> {
+        self.header_size..self.header_size + self.image_size
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for ImageHeader {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "ImageHeader {{ header_size: {=usize}, algorithm: {:?}, image_size: {=usize}, version: {=u32}, signature: {=[u8]:x} }}",
+            self.header_size,
+            self.algorithm,
+            self.image_size,
+            self.version,
+            &self.signature[..]
+        )
+    }
+}
+
+/// An image that passed verification
+#[derive(Clone, Debug, PartialEq)]
+pub struct VerifiedImage {
+    pub algorithm: SignatureAlgorithm,
+    pub version: u32,
+    /// Location of the image in the slot
+    pub image: Range<usize>,
+    /// The signed digest
+    pub digest: [u8; DIGEST_SIZE],
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for VerifiedImage {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "VerifiedImage {{ algorithm: {:?}, version: {=u32}, image: {=usize}..{=usize}, digest: {=[u8]:x} }}",
+            self.algorithm,
+            self.version,
+            self.image.start,
+            self.image.end,
+            &self.digest[..]
+        )
+    }
+}
+
+/// Hashing and signature verification used by [`verify`]
+pub trait SignatureBackend {
+    /// Calculate the SHA-256 digest of `parts` concatenated
+    fn sha256(&mut self, parts: &[&[u8]], digest: &mut [u8; DIGEST_SIZE]) -> Result<(), Error>;
+
+    /// Verify an ECDSA P-256 signature of a SHA-256 digest
+    ///
+    /// Returns false if the signature is invalid, or if the public key isn't a
+    /// point on the curve.
+    fn ecdsa_p256_verify(
+        &mut self,
+        public_key: &[u8; P256_PUBLIC_KEY_SIZE],
+        digest: &[u8; DIGEST_SIZE],
+        signature: &[u8; SIGNATURE_SIZE],
+    ) -> Result<bool, Error>;
+
+    /// Verify an Ed25519 signature of a SHA-256 digest, the digest is the
+    /// signed message
+    ///
+    /// Returns false if the signature is invalid.
+    fn ed25519_verify(
+        &mut self,
+        public_key: &[u8; ED25519_PUBLIC_KEY_SIZE],
+        digest: &[u8; DIGEST_SIZE],
+        signature: &[u8; SIGNATURE_SIZE],
+    ) -> Result<bool, Error>;
+}
+
+/// Calculate the digest that is signed for the image in `slot`
+pub fn image_digest<B: SignatureBackend>(
+    backend: &mut B,
+    slot: &[u8],
+    header: &ImageHeader,
+) -> Result<[u8; DIGEST_SIZE], Error> {
+    let mut digest = [0u8; DIGEST_SIZE];
+    backend.sha256(
+        &[&slot[..SIGNED_HEADER_SIZE], &slot[header.image_range()]],
+        &mut digest,
+    )?;
+    Ok(digest)
+}
+
+/// Verify the signed image in `slot` against `public_key`
+pub fn verify<B: SignatureBackend>(
+    backend: &mut B,
+    slot: &[u8],
+    public_key: &PublicKey,
+) -> Result<VerifiedImage, VerifyError> {
+    let header = ImageHeader::parse(slot)?;
+    let digest = image_digest(backend, slot, &header)?;
+    let valid = match (header.algorithm, public_key) {
+        (SignatureAlgorithm::EcdsaP256Sha256, PublicKey::EcdsaP256(key)) => {
+            backend.ecdsa_p256_verify(key, &digest, &header.signature)?
+        }
+        (SignatureAlgorithm::Ed25519Sha256, PublicKey::Ed25519(key)) => {
+            backend.ed25519_verify(key, &digest, &header.signature)?
+        }
+        _ => return Err(VerifyError::AlgorithmMismatch),
+    };
+    if !valid {
+        return Err(VerifyError::InvalidSignature);
+    }
+    Ok(VerifiedImage {
+        algorithm: header.algorithm,
+        version: header.version,
+        image: header.image_range(),
+        digest,
+    })
+}
+
+/// Size of the RAM buffer used for hashing
+#[cfg(feature = "cryptocell")]
+const HASH_CHUNK_SIZE: usize = 256;
+
+#[cfg(feature = "cryptocell")]
+fn sha256_update(context: &mut CRYS_HASHUserContext_t, data: &mut [u8]) -> Result<(), Error> {
+    let result = unsafe {
+        CRYS_HASH_Update(
+            context as *mut CRYS_HASHUserContext_t,
+            data.as_mut_ptr(),
+            data.len(),
+        )
+    };
+    if result != 0 {
+        return Err(Error::Other(result));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "cryptocell")]
+fn sha256_parts(
+    context: &mut CRYS_HASHUserContext_t,
+    parts: &[&[u8]],
+    digest: &mut [u8; DIGEST_SIZE],
+) -> Result<(), Error> {
+    // The CryptoCell can only read from RAM, so the data, which may be in
+    // flash, is copied to a buffer in chunks
+    let mut chunk = [0u8; HASH_CHUNK_SIZE];
+    let mut used = 0;
+    for part in parts.iter() {
+        let mut part = *part;
+        while !part.is_empty() {
+            let length = part.len().min(HASH_CHUNK_SIZE - used);
+            chunk[used..used + length].copy_from_slice(&part[..length]);
+            used += length;
+            part = &part[length..];
+            if used == HASH_CHUNK_SIZE {
+                sha256_update(context, &mut chunk)?;
+                used = 0;
+            }
+        }
+    }
+    if used > 0 {
+        sha256_update(context, &mut chunk[..used])?;
+    }
+    let mut hash: CRYS_HASH_Result_t = [0u32; CRYS_HASH_RESULT_SIZE_IN_WORDS as usize];
+    let result =
+        unsafe { CRYS_HASH_Finish(context as *mut CRYS_HASHUserContext_t, hash.as_mut_ptr()) };
+    if result != 0 {
+        return Err(Error::Other(result));
+    }
+    for (d, h) in digest.chunks_exact_mut(4).zip(hash.iter()) {
+        d.copy_from_slice(&h.to_ne_bytes());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "cryptocell")]
+impl<'a> SignatureBackend for CryptoCellBackend<'a> {
+    fn sha256(&mut self, parts: &[&[u8]], digest: &mut [u8; DIGEST_SIZE]) -> Result<(), Error> {
+        let mut context = CRYS_HASHUserContext_t {
+            buff: [0u32; CRYS_HASH_USER_CTX_SIZE_IN_WORDS as usize],
+        };
+        let _access = self.cryptocell.access()?;
+        let result = unsafe {
+            CRYS_HASH_Init(
+                &mut context as *mut CRYS_HASHUserContext_t,
+                CRYS_HASH_OperationMode_t_CRYS_HASH_SHA256_mode,
+            )
+        };
+        if result != 0 {
+            return Err(Error::Other(result));
+        }
+        let result = sha256_parts(&mut context, parts, digest);
+        unsafe { CRYS_HASH_Free(&mut context as *mut CRYS_HASHUserContext_t) };
+        result
+    }
+
+    fn ecdsa_p256_verify(
+        &mut self,
+        public_key: &[u8; P256_PUBLIC_KEY_SIZE],
+        digest: &[u8; DIGEST_SIZE],
+        signature: &[u8; SIGNATURE_SIZE],
+    ) -> Result<bool, Error> {
+        // Copies in RAM, the keys are typically constants in flash
+        let mut public_key = *public_key;
+        let mut digest = *digest;
+        let mut signature = *signature;
+        // Safety: Plain C structures that are initialised by the library
+        let mut user_key: CRYS_ECPKI_UserPublKey_t = unsafe { core::mem::zeroed() };
+        let mut context: CRYS_ECDSA_VerifyUserContext_t = unsafe { core::mem::zeroed() };
+        let mut temp: CRYS_ECPKI_BUILD_TempData_t = unsafe { core::mem::zeroed() };
+
+        let _access = self.cryptocell.access()?;
+        let result = unsafe {
+            let domain =
+                CRYS_ECPKI_GetEcDomain(CRYS_ECPKI_DomainID_t_CRYS_ECPKI_DomainID_secp256r1);
+            // Check that the point is on the curve and in the right subgroup,
+            // the key may come from outside the device
+            _DX_ECPKI_BuildPublKey(
+                domain,
+                public_key.as_mut_ptr(),
+                public_key.len() as u32,
+                EC_PublKeyCheckMode_t_ECpublKeyFullCheck,
+                &mut user_key as *mut CRYS_ECPKI_UserPublKey_t,
+                &mut temp as *mut CRYS_ECPKI_BUILD_TempData_t,
+            )
+        };
+        if result != 0 {
+            // The key isn't a valid point, the signature can't be verified
+            return Ok(false);
+        }
+        let result = unsafe {
+            CRYS_ECDSA_Verify(
+                &mut context as *mut CRYS_ECDSA_VerifyUserContext_t,
+                &mut user_key as *mut CRYS_ECPKI_UserPublKey_t,
+                CRYS_ECPKI_HASH_OpMode_t_CRYS_ECPKI_AFTER_HASH_SHA256_mode,
+                signature.as_mut_ptr(),
+                signature.len(),
+                digest.as_mut_ptr(),
+                digest.len(),
+            )
+        };
+        match result {
+            0 => Ok(true),
+            CRYS_ECDSA_VERIFY_INCONSISTENT_VERIFY_ERROR => Ok(false),
+            _ => Err(Error::Other(result)),
+        }
+    }
+
+    fn ed25519_verify(
+        &mut self,
+        public_key: &[u8; ED25519_PUBLIC_KEY_SIZE],
+        digest: &[u8; DIGEST_SIZE],
+        signature: &[u8; SIGNATURE_SIZE],
+    ) -> Result<bool, Error> {
+        // The message is hashed by the CryptoCell and must be in RAM
+        let mut digest = *digest;
+        let public_key = *public_key;
+        let signature = *signature;
+        // Safety: Plain C structure used as work area by the library
+        let mut temp: CRYS_ECEDW_TempBuff_t = unsafe { core::mem::zeroed() };
+
+        let _access = self.cryptocell.access()?;
+        let result = unsafe {
+            CRYS_ECEDW_Verify(
+                signature.as_ptr(),
+                signature.len(),
+                public_key.as_ptr(),
+                public_key.len(),
+                digest.as_mut_ptr(),
+                digest.len(),
+                &mut temp as *mut CRYS_ECEDW_TempBuff_t,
+            )
+        };
+        match result {
+            0 => Ok(true),
+            CRYS_ECEDW_SIGN_VERIFY_FAILED_ERROR => Ok(false),
+            _ => Err(Error::Other(result)),
+        }
+    }
+}
+
+/// Signature backend using software implementations
+#[cfg(feature = "software")]
+#[derive(Debug, Default)]
+pub struct SoftwareBackend;
+
+#[cfg(feature = "software")]
+impl SignatureBackend for SoftwareBackend {
+    fn sha256(&mut self, parts: &[&[u8]], digest: &mut [u8; DIGEST_SIZE]) -> Result<(), Error> {
+        use sha2::{Digest, Sha256};
+        let mut hasher = Sha256::new();
+        for part in parts.iter() {
+            hasher.update(part);
+        }
+        digest.copy_from_slice(&hasher.finalize());
+        Ok(())
+    }
+
+    fn ecdsa_p256_verify(
+        &mut self,
+        public_key: &[u8; P256_PUBLIC_KEY_SIZE],
+        digest: &[u8; DIGEST_SIZE],
+        signature: &[u8; SIGNATURE_SIZE],
+    ) -> Result<bool, Error> {
+        use ecdsa::hazmat::VerifyPrimitive;
+        use p256::{
+            ecdsa::Signature, elliptic_curve::ops::Reduce, FieldBytes, PublicKey, Scalar, U256,
+        };
+        let public_key = match PublicKey::from_sec1_bytes(public_key) {
+            Ok(public_key) => public_key,
+            Err(_) => return Ok(false),
+        };
+        let signature = match Signature::from_scalars(
+            *FieldBytes::from_slice(&signature[..32]),
+            *FieldBytes::from_slice(&signature[32..]),
+        ) {
+            Ok(signature) => signature,
+            Err(_) => return Ok(false),
+        };
+        let z = <Scalar as Reduce<U256>>::from_be_bytes_reduced(*FieldBytes::from_slice(digest));
+        Ok(public_key
+            .as_affine()
+            .verify_prehashed(z, &signature)
+            .is_ok())
+    }
+
+    fn ed25519_verify(
+        &mut self,
+        public_key: &[u8; ED25519_PUBLIC_KEY_SIZE],
+        digest: &[u8; DIGEST_SIZE],
+        signature: &[u8; SIGNATURE_SIZE],
+    ) -> Result<bool, Error> {
+        use ed25519_dalek::{PublicKey, Signature, Verifier};
+        let public_key = match PublicKey::from_bytes(public_key) {
+            Ok(public_key) => public_key,
+            Err(_) => return Ok(false),
+        };
+        let signature = match Signature::from_bytes(signature) {
+            Ok(signature) => signature,
+            Err(_) => return Ok(false),
+        };
+        Ok(public_key.verify(digest, &signature).is_ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The signed images were created with the Python cryptography package,
+    // from fixed private keys. The image is 64 bytes of a counting pattern.
+
+    /// Image signed with ECDSA P-256, version 0x00010203
+    const P256_IMAGE: [u8; 144] = [
+        0x46, 0x57, 0x49, 0x4d, 0x50, 0x00, 0x01, 0x00, 0x40, 0x00, 0x00, 0x00, 0x03, 0x02, 0x01,
+        0x00, 0x2d, 0x93, 0xfd, 0x5d, 0xa3, 0x13, 0xd6, 0xe4, 0xe6, 0x94, 0xf7, 0xee, 0x43, 0x5f,
+        0x21, 0xb4, 0x99, 0xba, 0x08, 0x62, 0x81, 0xa5, 0x83, 0x70, 0xe4, 0xcd, 0x0c, 0x72, 0xa5,
+        0xf7, 0x2d, 0x43, 0xe6, 0x0e, 0x0b, 0x08, 0x0a, 0x76, 0x67, 0x4e, 0xd0, 0x78, 0xa7, 0xd4,
+        0x00, 0xcb, 0xe2, 0x3d, 0x5b, 0xa9, 0xbe, 0x86, 0x87, 0x35, 0x28, 0x86, 0x01, 0x92, 0x94,
+        0x24, 0xe6, 0xe4, 0xb3, 0x12, 0x03, 0x0a, 0x11, 0x18, 0x1f, 0x26, 0x2d, 0x34, 0x3b, 0x42,
+        0x49, 0x50, 0x57, 0x5e, 0x65, 0x6c, 0x73, 0x7a, 0x81, 0x88, 0x8f, 0x96, 0x9d, 0xa4, 0xab,
+        0xb2, 0xb9, 0xc0, 0xc7, 0xce, 0xd5, 0xdc, 0xe3, 0xea, 0xf1, 0xf8, 0xff, 0x06, 0x0d, 0x14,
+        0x1b, 0x22, 0x29, 0x30, 0x37, 0x3e, 0x45, 0x4c, 0x53, 0x5a, 0x61, 0x68, 0x6f, 0x76, 0x7d,
+        0x84, 0x8b, 0x92, 0x99, 0xa0, 0xa7, 0xae, 0xb5, 0xbc,
+    ];
+    #[cfg(feature = "software")]
+    const P256_PUBLIC_KEY: [u8; P256_PUBLIC_KEY_SIZE] = [
+        0x04, 0xd8, 0xcd, 0x12, 0xea, 0x5c, 0x67, 0xf2, 0xf8, 0xa0, 0x0c, 0x11, 0x24, 0x89, 0x3e,
+        0xdc, 0xfa, 0x67, 0x54, 0xc4, 0xd6, 0xce, 0xde, 0x6b, 0xe1, 0x3b, 0xdf, 0x22, 0x95, 0xc8,
+        0x10, 0xa9, 0x7f, 0xa5, 0xa8, 0x9d, 0x2d, 0x2a, 0x36, 0x0c, 0x0c, 0xa9, 0xa4, 0xd6, 0xc7,
+        0xc9, 0xed, 0x4b, 0x28, 0xd3, 0xe1, 0x99, 0xd6, 0x62, 0x7f, 0x2e, 0x69, 0x6d, 0x68, 0x9c,
+        0x31, 0x0a, 0x5b, 0x0f, 0x48,
+    ];
+    #[cfg(feature = "software")]
+    const P256_DIGEST: [u8; DIGEST_SIZE] = [
+        0x40, 0x7f, 0x97, 0x0e, 0xfa, 0xb3, 0xe9, 0xca, 0x99, 0xdf, 0x12, 0x13, 0xbe, 0xa9, 0x33,
+        0x5f, 0xf8, 0xe4, 0xea, 0xf7, 0xdf, 0x69, 0x78, 0xad, 0x50, 0x10, 0x1c, 0x3a, 0xb0, 0x20,
+        0xd3, 0x4a,
+    ];
+    #[cfg(feature = "software")]
+    /// Image signed with Ed25519, version 0x00010204
+    const ED25519_IMAGE: [u8; 144] = [
+        0x46, 0x57, 0x49, 0x4d, 0x50, 0x00, 0x02, 0x00, 0x40, 0x00, 0x00, 0x00, 0x04, 0x02, 0x01,
+        0x00, 0x28, 0x86, 0xf6, 0xbd, 0x70, 0x0c, 0xea, 0xf0, 0xda, 0xb4, 0x7d, 0x4c, 0xf1, 0x2c,
+        0x3e, 0x92, 0x7a, 0xb5, 0x17, 0x76, 0xbf, 0x1d, 0x1e, 0xc4, 0x8d, 0xc3, 0x05, 0x79, 0x16,
+        0x1b, 0x4e, 0xc7, 0xf0, 0xc6, 0xe4, 0xd1, 0x71, 0xba, 0xe2, 0x87, 0xaf, 0x10, 0x81, 0x22,
+        0x3a, 0xa2, 0x18, 0xed, 0xd4, 0xc6, 0x7c, 0xab, 0xb9, 0xc3, 0xfc, 0x90, 0xf3, 0xd7, 0x15,
+        0xc1, 0x98, 0x14, 0x08, 0x0a, 0x03, 0x0a, 0x11, 0x18, 0x1f, 0x26, 0x2d, 0x34, 0x3b, 0x42,
+        0x49, 0x50, 0x57, 0x5e, 0x65, 0x6c, 0x73, 0x7a, 0x81, 0x88, 0x8f, 0x96, 0x9d, 0xa4, 0xab,
+        0xb2, 0xb9, 0xc0, 0xc7, 0xce, 0xd5, 0xdc, 0xe3, 0xea, 0xf1, 0xf8, 0xff, 0x06, 0x0d, 0x14,
+        0x1b, 0x22, 0x29, 0x30, 0x37, 0x3e, 0x45, 0x4c, 0x53, 0x5a, 0x61, 0x68, 0x6f, 0x76, 0x7d,
+        0x84, 0x8b, 0x92, 0x99, 0xa0, 0xa7, 0xae, 0xb5, 0xbc,
+    ];
+    #[cfg(feature = "software")]
+    const ED25519_PUBLIC_KEY: [u8; ED25519_PUBLIC_KEY_SIZE] = [
+        0x03, 0xa1, 0x07, 0xbf, 0xf3, 0xce, 0x10, 0xbe, 0x1d, 0x70, 0xdd, 0x18, 0xe7, 0x4b, 0xc0,
+        0x99, 0x67, 0xe4, 0xd6, 0x30, 0x9b, 0xa5, 0x0d, 0x5f, 0x1d, 0xdc, 0x86, 0x64, 0x12, 0x55,
+        0x31, 0xb8,
+    ];
+    #[cfg(feature = "software")]
+    const ED25519_DIGEST: [u8; DIGEST_SIZE] = [
+        0xe4, 0x31, 0x69, 0xf1, 0x19, 0xae, 0xc6, 0x29, 0xa4, 0x4f, 0x32, 0xae, 0x3b, 0x4d, 0x15,
+        0x76, 0xed, 0x61, 0x05, 0x7a, 0xf6, 0xdd, 0x1d, 0xed, 0x74, 0xe2, 0xe1, 0x77, 0xe1, 0xe4,
+        0x48, 0x13,
+    ];
+
+    #[test]
+    fn header_round_trip() {
+        let header = ImageHeader::parse(&P256_IMAGE).unwrap();
+        assert_eq!(header.header_size, HEADER_SIZE);
+        assert_eq!(header.algorithm, SignatureAlgorithm::EcdsaP256Sha256);
+        assert_eq!(header.image_size, 64);
+        assert_eq!(header.version, 0x0001_0203);
+        assert_eq!(&header.signature[..], &P256_IMAGE[16..80]);
+        assert_eq!(header.image_range(), 80..144);
+        let mut buffer = [0u8; HEADER_SIZE];
+        assert_eq!(header.encode(&mut buffer), HEADER_SIZE);
+        assert_eq!(&buffer[..], &P256_IMAGE[..HEADER_SIZE]);
+
+        // Padded header
+        let mut slot = [0u8; 256];
+        let padded = ImageHeader {
+            header_size: 128,
+            algorithm: SignatureAlgorithm::Ed25519Sha256,
+            image_size: 128,
+            version: 7,
+            signature: [0x5a; SIGNATURE_SIZE],
+        };
+        padded.encode(&mut slot);
+        assert_eq!(ImageHeader::parse(&slot), Ok(padded));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            ImageHeader::parse(&P256_IMAGE[..HEADER_SIZE - 1]),
+            Err(VerifyError::Truncated)
+        );
+        // The image extends past the end of the slot
+        assert_eq!(
+            ImageHeader::parse(&P256_IMAGE[..143]),
+            Err(VerifyError::Truncated)
+        );
+        // Erased flash
+        assert_eq!(ImageHeader::parse(&[0xff; 144]), Err(VerifyError::NoImage));
+
+        let mut slot = P256_IMAGE;
+        slot[7] = 1;
+        assert_eq!(ImageHeader::parse(&slot), Err(VerifyError::InvalidHeader));
+
+        // Header size smaller than the header
+        let mut slot = P256_IMAGE;
+        slot[4] = (HEADER_SIZE - 1) as u8;
+        assert_eq!(ImageHeader::parse(&slot), Err(VerifyError::InvalidHeader));
+
+        // Header size beyond the slot
+        let mut slot = P256_IMAGE;
+        slot[4] = 0xff;
+        slot[5] = 0xff;
+        assert_eq!(ImageHeader::parse(&slot), Err(VerifyError::Truncated));
+
+        let mut slot = P256_IMAGE;
+        slot[6] = 9;
+        assert_eq!(
+            ImageHeader::parse(&slot),
+            Err(VerifyError::UnsupportedAlgorithm(9))
+        );
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn verify_p256() {
+        let public_key = PublicKey::EcdsaP256(&P256_PUBLIC_KEY);
+        assert_eq!(
+            verify(&mut SoftwareBackend, &P256_IMAGE, &public_key),
+            Ok(VerifiedImage {
+                algorithm: SignatureAlgorithm::EcdsaP256Sha256,
+                version: 0x0001_0203,
+                image: 80..144,
+                digest: P256_DIGEST,
+            })
+        );
+
+        // Image, signed header and signature
+        for &position in [100, 12, 40].iter() {
+            let mut slot = P256_IMAGE;
+            slot[position] ^= 0x01;
+            assert_eq!(
+                verify(&mut SoftwareBackend, &slot, &public_key),
+                Err(VerifyError::InvalidSignature)
+            );
+        }
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn verify_ed25519() {
+        let public_key = PublicKey::Ed25519(&ED25519_PUBLIC_KEY);
+        assert_eq!(
+            verify(&mut SoftwareBackend, &ED25519_IMAGE, &public_key),
+            Ok(VerifiedImage {
+                algorithm: SignatureAlgorithm::Ed25519Sha256,
+                version: 0x0001_0204,
+                image: 80..144,
+                digest: ED25519_DIGEST,
+            })
+        );
+
+        for &position in [100, 12, 40].iter() {
+            let mut slot = ED25519_IMAGE;
+            slot[position] ^= 0x01;
+            assert_eq!(
+                verify(&mut SoftwareBackend, &slot, &public_key),
+                Err(VerifyError::InvalidSignature)
+            );
+        }
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn verify_algorithm_mismatch() {
+        assert_eq!(
+            verify(
+                &mut SoftwareBackend,
+                &P256_IMAGE,
+                &PublicKey::Ed25519(&ED25519_PUBLIC_KEY)
+            ),
+            Err(VerifyError::AlgorithmMismatch)
+        );
+        assert_eq!(
+            verify(
+                &mut SoftwareBackend,
+                &ED25519_IMAGE,
+                &PublicKey::EcdsaP256(&P256_PUBLIC_KEY)
+            ),
+            Err(VerifyError::AlgorithmMismatch)
+        );
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn verify_wrong_key() {
+        let mut public_key = ED25519_PUBLIC_KEY;
+        public_key[0] ^= 0x01;
+        assert_eq!(
+            verify(
+                &mut SoftwareBackend,
+                &ED25519_IMAGE,
+                &PublicKey::Ed25519(&public_key)
+            ),
+            Err(VerifyError::InvalidSignature)
+        );
+        // Not a point on the curve
+        let mut public_key = P256_PUBLIC_KEY;
+        public_key[64] ^= 0x01;
+        assert_eq!(
+            verify(
+                &mut SoftwareBackend,
+                &P256_IMAGE,
+                &PublicKey::EcdsaP256(&public_key)
+            ),
+            Err(VerifyError::InvalidSignature)
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! In-place CCM*
+//!
+//! CCM* encryption and decryption of a single buffer. The payload is
+//! encrypted or decrypted where it is, so a frame can be secured in the buffer
+//! it was received in or built in, with the header as additional data. This
+//! avoids a second frame sized buffer on the stack of the calling task.
+
+use crate::Error;
+
+#[cfg(feature = "cryptocell")]
+use crate::{
+    clear, make_flag, power::PowerGuard, AesContext, BlockCipher as _, CryptoCellBackend,
+    BLOCK_SIZE, KEY_SIZE, LENGTH_FIELD_LENGTH,
+};
+
+/// CCM* operating on a single buffer
+pub trait CcmStarInPlace {
+    /// Encrypt `data` in place and write the MIC to `mic`
+    ///
+    /// The length of `mic` is the MIC length, 0, 4, 8 or 16 bytes.
+    fn ccmstar_encrypt_in_place(
+        &mut self,
+        key: &[u8],
+        nonce: &[u8],
+        data: &mut [u8],
+        mic: &mut [u8],
+        aad: &[u8],
+    ) -> Result<(), Error>;
+
+    /// Decrypt `data` in place and validate `mic`
+    ///
+    /// Returns false if the MIC is invalid, `data` is then cleared.
+    fn ccmstar_decrypt_in_place(
+        &mut self,
+        key: &[u8],
+        nonce: &[u8],
+        data: &mut [u8],
+        mic: &[u8],
+        aad: &[u8],
+    ) -> Result<bool, Error>;
+}
+
+/// CBC-MAC that keeps back the last block, the CryptoCell requires the last
+/// block to be passed to finish
+#[cfg(feature = "cryptocell")]
+struct CbcMac<'c, 'a> {
+    cipher: &'c mut AesContext<'a>,
+    mac: [u8; BLOCK_SIZE],
+    last: [u8; BLOCK_SIZE],
+}
+
+#[cfg(feature = "cryptocell")]
+impl<'c, 'a> CbcMac<'c, 'a> {
+    /// Start with the B0 block
+    fn new(cipher: &'c mut AesContext<'a>, b0: [u8; BLOCK_SIZE]) -> Self {
+        Self {
+            cipher,
+            mac: [0u8; BLOCK_SIZE],
+            last: b0,
+        }
+    }
+
+    /// Feed `data`, zero padding a partial last block
+    fn update(&mut self, data: &[u8]) -> Result<(), Error> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.cipher.process_block(&self.last, &mut self.mac)?;
+        let split = ((data.len() - 1) / BLOCK_SIZE) * BLOCK_SIZE;
+        // The MAC is a single block, so feed the data a block at a time
+        for block in data[..split].chunks_exact(BLOCK_SIZE) {
+            self.cipher.process_block(block, &mut self.mac)?;
+        }
+        self.last = [0u8; BLOCK_SIZE];
+        self.last[..data.len() - split].copy_from_slice(&data[split..]);
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<[u8; BLOCK_SIZE], Error> {
+        self.cipher.finish(&self.last, &mut self.mac)?;
+        clear(&mut self.last);
+        Ok(self.mac)
+    }
+}
+
+/// Encrypt or decrypt `data` in place with a counter mode context
+#[cfg(feature = "cryptocell")]
+fn ctr_in_place(cipher: &mut AesContext, data: &mut [u8]) -> Result<(), Error> {
+    if data.is_empty() {
+        return Ok(());
+    }
+    let split = ((data.len() - 1) / BLOCK_SIZE) * BLOCK_SIZE;
+    let (blocks, last) = data.split_at_mut(split);
+    if !blocks.is_empty() {
+        cipher.process_block_in_place(blocks)?;
+    }
+    cipher.finish_in_place(last)?;
+    Ok(())
+}
+
+#[cfg(feature = "cryptocell")]
+impl<'a> CryptoCellBackend<'a> {
+    /// Calculate the unencrypted authentication tag
+    fn authenticate(
+        &mut self,
+        key: &[u8],
+        nonce: &[u8],
+        message: &[u8],
+        mic_length: usize,
+        aad: &[u8],
+    ) -> Result<[u8; BLOCK_SIZE], Error> {
+        assert!(message.len() <= 0xffff);
+        assert!(aad.len() < 0xff00);
+
+        let mut b0 = [0u8; BLOCK_SIZE];
+        b0[0] = make_flag(aad.len(), mic_length, LENGTH_FIELD_LENGTH);
+        b0[1..=nonce.len()].copy_from_slice(nonce);
+        b0[BLOCK_SIZE - 2] = (message.len() >> 8) as u8;
+        b0[BLOCK_SIZE - 1] = (message.len() & 0xff) as u8;
+        let mut mac = CbcMac::new(self.cbc_mac_context(key)?, b0);
+
+        if !aad.is_empty() {
+            // The first block holds the length of the additional data
+            let mut block = [0u8; BLOCK_SIZE];
+            let first = aad.len().min(BLOCK_SIZE - 2);
+            block[0] = (aad.len() >> 8) as u8;
+            block[1] = (aad.len() & 0xff) as u8;
+            block[2..2 + first].copy_from_slice(&aad[..first]);
+            mac.update(&block)?;
+            mac.update(&aad[first..])?;
+        }
+        mac.update(message)?;
+        mac.finish()
+    }
+
+    /// Counter mode context, the first counter block encrypts the MIC
+    fn counter(&mut self, key: &[u8], nonce: &[u8]) -> Result<&mut AesContext<'a>, Error> {
+        let mut a0 = [0u8; BLOCK_SIZE];
+        a0[0] = make_flag(0, 0, LENGTH_FIELD_LENGTH);
+        a0[1..=nonce.len()].copy_from_slice(nonce);
+        let cipher = self.ctr_context(key)?;
+        cipher.set_iv(&a0)?;
+        Ok(cipher)
+    }
+
+    fn encrypt_in_place(
+        &mut self,
+        key: &[u8],
+        nonce: &[u8],
+        data: &mut [u8],
+        mic: &mut [u8],
+        aad: &[u8],
+    ) -> Result<(), Error> {
+        let mut tag = self.authenticate(key, nonce, data, mic.len(), aad)?;
+        tag[mic.len()..].iter_mut().for_each(|b| *b = 0);
+
+        let cipher = self.counter(key, nonce)?;
+        cipher.process_block_in_place(&mut tag)?;
+        ctr_in_place(cipher, data)?;
+
+        mic.copy_from_slice(&tag[..mic.len()]);
+        Ok(())
+    }
+
+    fn decrypt_in_place(
+        &mut self,
+        key: &[u8],
+        nonce: &[u8],
+        data: &mut [u8],
+        mic: &[u8],
+        aad: &[u8],
+    ) -> Result<bool, Error> {
+        let mut tag = [0u8; BLOCK_SIZE];
+        tag[..mic.len()].copy_from_slice(mic);
+
+        {
+            let cipher = self.counter(key, nonce)?;
+            cipher.process_block_in_place(&mut tag)?;
+            ctr_in_place(cipher, data)?;
+        }
+
+        let expected = self.authenticate(key, nonce, data, mic.len(), aad)?;
+        let mut difference = 0u8;
+        for (a, b) in tag[..mic.len()].iter().zip(expected[..mic.len()].iter()) {
+            difference |= a ^ b;
+        }
+        if difference == 0 {
+            Ok(true)
+        } else {
+            clear(data);
+            Ok(false)
+        }
+    }
+}
+
+#[cfg(feature = "cryptocell")]
+impl<'a> CcmStarInPlace for CryptoCellBackend<'a> {
+    fn ccmstar_encrypt_in_place(
+        &mut self,
+        key: &[u8],
+        nonce: &[u8],
+        data: &mut [u8],
+        mic: &mut [u8],
+        aad: &[u8],
+    ) -> Result<(), Error> {
+        assert!(key.len() == KEY_SIZE);
+        assert!(nonce.len() == BLOCK_SIZE - 1 - LENGTH_FIELD_LENGTH);
+        assert!(mic.len() <= BLOCK_SIZE);
+
+        // Keep the CryptoCell enabled for the whole operation
+        let _power = PowerGuard::acquire();
+        let result = self.encrypt_in_place(key, nonce, data, mic, aad);
+        self.release_contexts();
+        result
+    }
+
+    fn ccmstar_decrypt_in_place(
+        &mut self,
+        key: &[u8],
+        nonce: &[u8],
+        data: &mut [u8],
+        mic: &[u8],
+        aad: &[u8],
+    ) -> Result<bool, Error> {
+        assert!(key.len() == KEY_SIZE);
+        assert!(nonce.len() == BLOCK_SIZE - 1 - LENGTH_FIELD_LENGTH);
+        assert!(mic.len() <= BLOCK_SIZE);
+
+        // Keep the CryptoCell enabled for the whole operation
+        let _power = PowerGuard::acquire();
+        let result = self.decrypt_in_place(key, nonce, data, mic, aad);
+        self.release_contexts();
+        result
+    }
+}
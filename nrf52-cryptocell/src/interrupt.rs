@@ -0,0 +1,30 @@
+//! CryptoCell interrupt handling
+//!
+//! When the `interrupts` feature is enabled the interrupt enabled variant of
+//! the nrf_cc310 library is linked. That library sleeps while waiting for the
+//! CryptoCell to finish an operation and relies on the CRYPTOCELL interrupt to
+//! wake up, instead of busy-waiting on the status registers.
+//!
+//! The application owns the interrupt, it must enable it in the NVIC and call
+//! [`on_interrupt`] from the handler. RTIC enables the interrupt of a task
+//! bound to `CRYPTOCELL`. The handler must have a higher priority than the
+//! tasks that run CryptoCell operations.
+//!
+//! An operation runs in the task that calls it, the task sleeps until the
+//! interrupt signals that the CryptoCell is done. Run the operations in a low
+//! priority task, so that time critical tasks preempt them while the task
+//! waits, see [`crate::request`].
+
+#[cfg(feature = "interrupts")]
+extern "C" {
+    /// CryptoCell interrupt handler provided by the library
+    fn CRYPTOCELL_IRQHandler();
+}
+
+/// Handle a CRYPTOCELL interrupt
+///
+/// Forwards the interrupt to the library.
+#[cfg(feature = "interrupts")]
+pub fn on_interrupt() {
+    unsafe { CRYPTOCELL_IRQHandler() };
+}
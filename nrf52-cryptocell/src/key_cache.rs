@@ -0,0 +1,156 @@
+//! Keyed context cache
+//!
+//! Initialising an AES context and setting the key takes longer than
+//! processing a small frame. The backend keeps contexts for the most recently
+//! used keys, typically the network key and a link key. Each operation resets
+//! the IV of the cached context, so no state is carried from one frame to the
+//! next. The library context is opaque and is never copied, each one is freed
+//! once when its key is dropped from the cache.
+//!
+//! A backend created with [`CryptoCellBackend::without_key_cache`] keys new
+//! contexts for every operation and frees them when the operation is done, so
+//! no key is kept in memory between operations.
+use crate::{
+    clear, AesContext, AesOperationMode, BlockCipher as _, CryptoCell, CryptoCellBackend,
+    EncryptDecrypt, Error, PaddingType, BLOCK_SIZE, KEY_SIZE,
+};
+
+/// Number of keys with cached contexts
+pub const KEY_CACHE_SIZE: usize = 2;
+
+/// Contexts keyed with one key
+struct CachedKey<'a> {
+    key: [u8; KEY_SIZE],
+    ctr: AesContext<'a>,
+    cbc_mac: AesContext<'a>,
+}
+
+impl<'a> CachedKey<'a> {
+    fn new(cryptocell: &'a CryptoCell, key: &[u8]) -> Result<Self, Error> {
+        let mut cached = Self {
+            key: [0u8; KEY_SIZE],
+            ctr: keyed_context(cryptocell, AesOperationMode::Ctr, key)?,
+            cbc_mac: keyed_context(cryptocell, AesOperationMode::CbcMac, key)?,
+        };
+        cached.key.copy_from_slice(key);
+        Ok(cached)
+    }
+
+    fn matches(&self, key: &[u8]) -> bool {
+        let mut difference = 0u8;
+        for (a, b) in self.key.iter().zip(key.iter()) {
+            difference |= a ^ b;
+        }
+        difference == 0
+    }
+}
+
+impl<'a> core::ops::Drop for CachedKey<'a> {
+    fn drop(&mut self) {
+        clear(&mut self.key);
+    }
+}
+
+fn keyed_context<'a>(
+    cryptocell: &'a CryptoCell,
+    mode: AesOperationMode,
+    key: &[u8],
+) -> Result<AesContext<'a>, Error> {
+    let mut context =
+        AesContext::new(cryptocell, EncryptDecrypt::Encrypt, mode, PaddingType::None)?;
+    context.set_key(key)?;
+    Ok(context)
+}
+
+/// Contexts for the most recently used keys
+pub(crate) struct KeyCache<'a> {
+    keys: [Option<CachedKey<'a>>; KEY_CACHE_SIZE],
+    /// Slot to replace on the next miss
+    next: usize,
+    /// Keep the contexts when an operation is done
+    enabled: bool,
+}
+
+impl<'a> KeyCache<'a> {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self {
+            keys: [None, None],
+            next: 0,
+            enabled,
+        }
+    }
+
+    /// Get the cached contexts for `key`, keying new contexts on a miss
+    fn get(&mut self, cryptocell: &'a CryptoCell, key: &[u8]) -> Result<&mut CachedKey<'a>, Error> {
+        assert!(key.len() == KEY_SIZE);
+        let index = match self
+            .keys
+            .iter()
+            .position(|cached| matches!(cached, Some(c) if c.matches(key)))
+        {
+            Some(index) => index,
+            None => {
+                let index = self.next;
+                // Drop the old contexts before creating the new ones
+                self.keys[index] = None;
+                self.keys[index] = Some(CachedKey::new(cryptocell, key)?);
+                self.next = (index + 1) % KEY_CACHE_SIZE;
+                index
+            }
+        };
+        Ok(self.keys[index].as_mut().unwrap())
+    }
+
+    fn clear(&mut self) {
+        for cached in self.keys.iter_mut() {
+            *cached = None;
+        }
+        self.next = 0;
+    }
+
+    /// Free the contexts used by an operation, unless they are cached
+    fn release(&mut self) {
+        if !self.enabled {
+            self.clear();
+        }
+    }
+}
+
+impl<'a> core::fmt::Debug for KeyCache<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let cached = self.keys.iter().filter(|cached| cached.is_some()).count();
+        f.debug_struct("KeyCache")
+            .field("enabled", &self.enabled)
+            .field("cached", &cached)
+            .finish()
+    }
+}
+
+impl<'a> CryptoCellBackend<'a> {
+    /// Counter mode context for `key`, the caller sets the IV
+    pub(crate) fn ctr_context(&mut self, key: &[u8]) -> Result<&mut AesContext<'a>, Error> {
+        let cryptocell = self.cryptocell;
+        Ok(&mut self.keys.get(cryptocell, key)?.ctr)
+    }
+
+    /// CBC-MAC context for `key`, with the IV reset to zero
+    pub(crate) fn cbc_mac_context(&mut self, key: &[u8]) -> Result<&mut AesContext<'a>, Error> {
+        let cryptocell = self.cryptocell;
+        let context = &mut self.keys.get(cryptocell, key)?.cbc_mac;
+        context.set_iv(&[0u8; BLOCK_SIZE])?;
+        Ok(context)
+    }
+
+    /// Free the contexts of an operation that is done, unless they are cached
+    pub(crate) fn release_contexts(&mut self) {
+        self.keys.release();
+    }
+
+    /// Drop all cached keys and contexts
+    ///
+    /// The cache holds copies of the keys used for CCM*. Clear it when a key is
+    /// retired, for example after a network key switch.
+    pub fn clear_key_cache(&mut self) {
+        self.keys.clear();
+    }
+}
@@ -0,0 +1,116 @@
+//! Key derivation
+//!
+//! Derive keys using the SP800-108 key derivation function in counter mode
+//! with AES-CMAC as pseudo-random function. The derivation can be based on the
+//! device root key (KDR) or on a user provided key.
+//!
+//! The nRF52840 has no device unique key of its own. The root key is whatever
+//! the application loaded with [`CryptoCell::load_root_key`] since the last
+//! reset, derivation from the root key fails with [`Error::NoRootKey`] until
+//! then. Keys derived from the root key are only device unique if the loaded
+//! key is, for example a random key written to the UICR at production. The
+//! KDR registers can't be read, but the storage the key is loaded from can,
+//! protect it as well as the keys derived from it.
+//!
+//! [`CryptoCell::load_root_key`]: crate::CryptoCell::load_root_key
+
+use crate::{error::Redacted, nrf_cc310::*, CryptoCellBackend, Error, KEY_SIZE};
+
+/// Maximum size of the label, in bytes
+pub const MAX_LABEL_SIZE: usize = 64;
+/// Maximum size of the context, in bytes
+pub const MAX_CONTEXT_SIZE: usize = 64;
+/// Maximum size of a derived key, in bytes
+pub const MAX_DERIVED_KEY_SIZE: usize = 4080;
+
+/// Key used as the base for key derivation
+#[derive(Clone, PartialEq)]
+pub enum DerivationKey<'a> {
+    /// Device root key (KDR), see [`CryptoCell::load_root_key`]
+    ///
+    /// [`CryptoCell::load_root_key`]: crate::CryptoCell::load_root_key
+    Root,
+    /// User provided 128-bit key
+    User(&'a [u8]),
+}
+
+impl<'a> core::fmt::Debug for DerivationKey<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            DerivationKey::Root => f.write_str("Root"),
+            DerivationKey::User(_) => f.debug_tuple("User").field(&Redacted).finish(),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<'a> defmt::Format for DerivationKey<'a> {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            DerivationKey::Root => defmt::write!(f, "Root"),
+            DerivationKey::User(_) => defmt::write!(f, "User({:?})", Redacted),
+        }
+    }
+}
+
+impl<'a> CryptoCellBackend<'a> {
+    /// Derive a key from the root key or a user key
+    ///
+    /// The derived key fills the `derived_key` slice. The same key, label and
+    /// context will always produce the same derived key. Fails with
+    /// [`Error::NoRootKey`] for [`DerivationKey::Root`] if no root key is
+    /// loaded.
+    pub fn derive_key(
+        &mut self,
+        key: DerivationKey,
+        label: &[u8],
+        context: &[u8],
+        derived_key: &mut [u8],
+    ) -> Result<(), Error> {
+        assert!(!label.is_empty() && label.len() <= MAX_LABEL_SIZE);
+        assert!(context.len() <= MAX_CONTEXT_SIZE);
+        assert!(!derived_key.is_empty() && derived_key.len() <= MAX_DERIVED_KEY_SIZE);
+
+        let (key_type, mut user_key) = match key {
+            DerivationKey::Root if !self.cryptocell.has_root_key() => {
+                return Err(Error::NoRootKey);
+            }
+            DerivationKey::Root => (
+                SaSiUtilKeyType_t_SASI_UTIL_ROOT_KEY,
+                SaSiAesUserKeyData_t {
+                    pKey: core::ptr::null_mut(),
+                    keySize: 0,
+                },
+            ),
+            DerivationKey::User(key) => {
+                assert!(key.len() == KEY_SIZE);
+                (
+                    SaSiUtilKeyType_t_SASI_UTIL_USER_KEY,
+                    SaSiAesUserKeyData_t {
+                        pKey: key.as_ptr() as *mut u8,
+                        keySize: key.len(),
+                    },
+                )
+            }
+        };
+        let _access = self.cryptocell.access()?;
+        let result = unsafe {
+            SaSi_UtilKeyDerivation(
+                key_type,
+                &mut user_key as *mut SaSiAesUserKeyData_t,
+                SaSiUtilPrfType_t_SASI_UTIL_PRF_CMAC,
+                CRYS_HASH_OperationMode_t_CRYS_HASH_OperationModeLast,
+                label.as_ptr(),
+                label.len(),
+                context.as_ptr(),
+                context.len(),
+                derived_key.as_mut_ptr(),
+                derived_key.len(),
+            )
+        };
+        if result != 0 {
+            return Err(Error::Other(result));
+        }
+        Ok(())
+    }
+}
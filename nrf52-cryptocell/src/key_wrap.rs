@@ -0,0 +1,266 @@
+//! AES key wrap
+//!
+//! AES-KW as described in RFC 3394 and AES-KWP, key wrap with padding, as
+//! described in RFC 5649. Wrapping encrypts and integrity protects keys and
+//! other small secrets, such as network keys, link keys and frame counters
+//! that are stored in flash.
+//!
+//! The key encryption key can be a user key or derived from the device root
+//! key, see [`KeyWrap::from_root_key`]. Data wrapped with a key derived from
+//! the root key can only be unwrapped with the same root key loaded, on the
+//! same device if the root key is device unique.
+
+use crate::{
+    clear, error::Redacted, key_derivation::DerivationKey, AesContext, AesOperationMode,
+    CryptoCell, CryptoCellBackend, EncryptDecrypt, Error, PaddingType, BLOCK_SIZE, KEY_SIZE,
+};
+
+/// Size of a semiblock, wrapping works on half AES blocks
+pub const SEMIBLOCK_SIZE: usize = BLOCK_SIZE / 2;
+
+/// Initial value for AES-KW, RFC 3394 section 2.2.3.1
+const KW_IV: [u8; SEMIBLOCK_SIZE] = [0xa6; SEMIBLOCK_SIZE];
+/// Alternative initial value prefix for AES-KWP, RFC 5649 section 3
+const KWP_IV: [u8; 4] = [0xa6, 0x59, 0x59, 0xa6];
+
+/// Largest supported key encryption key, AES-256
+const MAX_KEY_SIZE: usize = 2 * KEY_SIZE;
+
+/// AES key wrap with a 128, 192 or 256-bit key encryption key
+pub struct KeyWrap<'a> {
+    cryptocell: &'a CryptoCell,
+    key: [u8; MAX_KEY_SIZE],
+    key_length: usize,
+}
+
+impl<'a> KeyWrap<'a> {
+    pub fn new(cryptocell: &'a CryptoCell, key: &[u8]) -> Self {
+        assert!(key.len() == KEY_SIZE || key.len() == 24 || key.len() == MAX_KEY_SIZE);
+        let mut key_wrap = Self {
+            cryptocell,
+            key: [0u8; MAX_KEY_SIZE],
+            key_length: key.len(),
+        };
+        key_wrap.key[..key.len()].copy_from_slice(key);
+        key_wrap
+    }
+
+    /// Use a 128-bit key derived from the device root key
+    ///
+    /// Different labels give independent key encryption keys. Fails with
+    /// [`Error::NoRootKey`] if the root key isn't loaded, see
+    /// [`CryptoCell::load_root_key`].
+    pub fn from_root_key(backend: &mut CryptoCellBackend<'a>, label: &[u8]) -> Result<Self, Error> {
+        let mut key_wrap = Self {
+            cryptocell: backend.cryptocell(),
+            key: [0u8; MAX_KEY_SIZE],
+            key_length: KEY_SIZE,
+        };
+        backend.derive_key(
+            DerivationKey::Root,
+            label,
+            &[],
+            &mut key_wrap.key[..KEY_SIZE],
+        )?;
+        Ok(key_wrap)
+    }
+
+    fn context(&self, direction: EncryptDecrypt) -> Result<AesContext<'a>, Error> {
+        let mut context = AesContext::new(
+            self.cryptocell,
+            direction,
+            AesOperationMode::Ecb,
+            PaddingType::None,
+        )?;
+        context.set_user_key(&self.key[..self.key_length])?;
+        Ok(context)
+    }
+
+    /// The wrapping function W, `data` holds the semiblocks R[1] to R[n]
+    ///
+    /// Returns the integrity check register A.
+    fn wrap_semiblocks(
+        &self,
+        iv: [u8; SEMIBLOCK_SIZE],
+        data: &mut [u8],
+    ) -> Result<[u8; SEMIBLOCK_SIZE], Error> {
+        let mut cipher = self.context(EncryptDecrypt::Encrypt)?;
+        let n = data.len() / SEMIBLOCK_SIZE;
+        let mut block = [0u8; BLOCK_SIZE];
+        block[..SEMIBLOCK_SIZE].copy_from_slice(&iv);
+        for j in 0..6 {
+            for (i, r) in data.chunks_exact_mut(SEMIBLOCK_SIZE).enumerate() {
+                block[SEMIBLOCK_SIZE..].copy_from_slice(r);
+                cipher.process_block_in_place(&mut block)?;
+                let t = ((n * j) + i + 1) as u64;
+                for (a, t) in block[..SEMIBLOCK_SIZE]
+                    .iter_mut()
+                    .zip(t.to_be_bytes().iter())
+                {
+                    *a ^= t;
+                }
+                r.copy_from_slice(&block[SEMIBLOCK_SIZE..]);
+            }
+        }
+        let mut a = [0u8; SEMIBLOCK_SIZE];
+        a.copy_from_slice(&block[..SEMIBLOCK_SIZE]);
+        clear(&mut block);
+        Ok(a)
+    }
+
+    /// The unwrapping function W⁻¹, `data` holds the semiblocks C[1] to C[n]
+    ///
+    /// Returns the integrity check register A, to be checked by the caller.
+    fn unwrap_semiblocks(&self, a: &[u8], data: &mut [u8]) -> Result<[u8; SEMIBLOCK_SIZE], Error> {
+        let mut cipher = self.context(EncryptDecrypt::Decrypt)?;
+        let n = data.len() / SEMIBLOCK_SIZE;
+        let mut block = [0u8; BLOCK_SIZE];
+        block[..SEMIBLOCK_SIZE].copy_from_slice(a);
+        for j in (0..6).rev() {
+            for (i, r) in data.chunks_exact_mut(SEMIBLOCK_SIZE).enumerate().rev() {
+                let t = ((n * j) + i + 1) as u64;
+                for (a, t) in block[..SEMIBLOCK_SIZE]
+                    .iter_mut()
+                    .zip(t.to_be_bytes().iter())
+                {
+                    *a ^= t;
+                }
+                block[SEMIBLOCK_SIZE..].copy_from_slice(r);
+                cipher.process_block_in_place(&mut block)?;
+                r.copy_from_slice(&block[SEMIBLOCK_SIZE..]);
+            }
+        }
+        let mut a = [0u8; SEMIBLOCK_SIZE];
+        a.copy_from_slice(&block[..SEMIBLOCK_SIZE]);
+        clear(&mut block);
+        Ok(a)
+    }
+
+    /// Wrap `input` into `output` using AES-KW
+    ///
+    /// The input must be whole semiblocks, at least two. The output is one
+    /// semiblock longer than the input.
+    pub fn wrap(&self, input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        assert!(input.len() % SEMIBLOCK_SIZE == 0 && input.len() >= 2 * SEMIBLOCK_SIZE);
+        let length = input.len() + SEMIBLOCK_SIZE;
+        assert!(output.len() >= length);
+        let (a, data) = output[..length].split_at_mut(SEMIBLOCK_SIZE);
+        data.copy_from_slice(input);
+        a.copy_from_slice(&self.wrap_semiblocks(KW_IV, data)?);
+        Ok(length)
+    }
+
+    /// Unwrap `input` into `output` using AES-KW
+    ///
+    /// Fails with [`Error::UnwrapFailed`] if the integrity check fails, the output is
+    /// then cleared.
+    pub fn unwrap(&self, input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        assert!(input.len() % SEMIBLOCK_SIZE == 0 && input.len() >= 3 * SEMIBLOCK_SIZE);
+        let length = input.len() - SEMIBLOCK_SIZE;
+        assert!(output.len() >= length);
+        let data = &mut output[..length];
+        data.copy_from_slice(&input[SEMIBLOCK_SIZE..]);
+        let a = self.unwrap_semiblocks(&input[..SEMIBLOCK_SIZE], data)?;
+        let mut difference = 0u8;
+        for (a, b) in a.iter().zip(KW_IV.iter()) {
+            difference |= a ^ b;
+        }
+        if difference != 0 {
+            clear(data);
+            return Err(Error::UnwrapFailed);
+        }
+        Ok(length)
+    }
+
+    /// Size of the output of [`KeyWrap::wrap_with_padding`] for `length`
+    /// bytes of input
+    pub fn padded_wrapped_length(length: usize) -> usize {
+        ((length + (SEMIBLOCK_SIZE - 1)) / SEMIBLOCK_SIZE) * SEMIBLOCK_SIZE + SEMIBLOCK_SIZE
+    }
+
+    /// Wrap `input` of any length into `output` using AES-KWP
+    pub fn wrap_with_padding(&self, input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        assert!(!input.is_empty() && input.len() <= u32::MAX as usize);
+        let length = Self::padded_wrapped_length(input.len());
+        assert!(output.len() >= length);
+
+        let mut iv = [0u8; SEMIBLOCK_SIZE];
+        iv[..4].copy_from_slice(&KWP_IV);
+        iv[4..].copy_from_slice(&(input.len() as u32).to_be_bytes());
+
+        let (a, data) = output[..length].split_at_mut(SEMIBLOCK_SIZE);
+        data[..input.len()].copy_from_slice(input);
+        clear(&mut data[input.len()..]);
+        if data.len() == SEMIBLOCK_SIZE {
+            // A single semiblock is encrypted as one block with the IV
+            a.copy_from_slice(&iv);
+            let mut cipher = self.context(EncryptDecrypt::Encrypt)?;
+            cipher.process_block_in_place(&mut output[..length])?;
+        } else {
+            a.copy_from_slice(&self.wrap_semiblocks(iv, data)?);
+        }
+        Ok(length)
+    }
+
+    /// Unwrap `input` into `output` using AES-KWP
+    ///
+    /// The output must fit the padded data, one semiblock less than the
+    /// input. Returns the length of the unwrapped data. Fails with
+    /// [`Error::UnwrapFailed`] if the integrity check fails, the output is then
+    /// cleared.
+    pub fn unwrap_with_padding(&self, input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        assert!(input.len() % SEMIBLOCK_SIZE == 0 && input.len() >= 2 * SEMIBLOCK_SIZE);
+        let padded_length = input.len() - SEMIBLOCK_SIZE;
+        assert!(output.len() >= padded_length);
+
+        let a = if padded_length == SEMIBLOCK_SIZE {
+            let mut block = [0u8; BLOCK_SIZE];
+            block.copy_from_slice(input);
+            let mut cipher = self.context(EncryptDecrypt::Decrypt)?;
+            cipher.process_block_in_place(&mut block)?;
+            output[..SEMIBLOCK_SIZE].copy_from_slice(&block[SEMIBLOCK_SIZE..]);
+            let mut a = [0u8; SEMIBLOCK_SIZE];
+            a.copy_from_slice(&block[..SEMIBLOCK_SIZE]);
+            clear(&mut block);
+            a
+        } else {
+            output[..padded_length].copy_from_slice(&input[SEMIBLOCK_SIZE..]);
+            self.unwrap_semiblocks(&input[..SEMIBLOCK_SIZE], &mut output[..padded_length])?
+        };
+
+        // Check the IV, the message length indicator and the padding
+        let mut length_bytes = [0u8; 4];
+        length_bytes.copy_from_slice(&a[4..]);
+        let length = u32::from_be_bytes(length_bytes) as usize;
+        let mut difference = 0u8;
+        for (a, b) in a[..4].iter().zip(KWP_IV.iter()) {
+            difference |= a ^ b;
+        }
+        let valid_length = length > padded_length - SEMIBLOCK_SIZE && length <= padded_length;
+        if valid_length {
+            for b in output[length..padded_length].iter() {
+                difference |= b;
+            }
+        }
+        if difference != 0 || !valid_length {
+            clear(&mut output[..padded_length]);
+            return Err(Error::UnwrapFailed);
+        }
+        Ok(length)
+    }
+}
+
+impl<'a> core::fmt::Debug for KeyWrap<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("KeyWrap")
+            .field("key", &Redacted)
+            .field("key_length", &self.key_length)
+            .finish()
+    }
+}
+
+impl<'a> core::ops::Drop for KeyWrap<'a> {
+    fn drop(&mut self) {
+        clear(&mut self.key);
+    }
+}
@@ -6,22 +6,219 @@
 //! Note that there is a CCM* implementation in the CC310 library provided by
 //! Nordic. But that implementation requires the last byte of the nonce to
 //! only indicate the MIC length and encryption. For the usage in Psila this
-//! byte contains more bits of information.
+//! byte contains more bits of information. The library CCM is used for
+//! standard AES-CCM instead, see the `ccm` module.
+//!
+//! For nRF52 devices without a CryptoCell, the `ecb` feature provides a
+//! backend using the AES ECB peripheral instead.
+//!
+//! The `rustcrypto` feature implements the `cipher` and `aead` traits for the
+//! CryptoCell AES, see the `rustcrypto` module.
+//!
+//! The `drbg` feature adds random number generators implementing the
+//! `rand_core` traits, see the `drbg` module.
+//!
+//! The `psila` feature, enabled by default, implements the `psila_crypto`
+//! traits. Without it the crate has no dependency on the Psila stack.
 
 #![no_std]
 
-use nrf52840_pac::CRYPTOCELL;
-pub use psila_crypto::{
-    BlockCipher, CryptoBackend, Error, BLOCK_SIZE, KEY_SIZE, LENGTH_FIELD_LENGTH,
-};
+#[cfg(not(any(feature = "52832", feature = "52833", feature = "52840")))]
+compile_error!("One of the features 52832, 52833 or 52840 must be enabled");
+
+#[cfg(all(feature = "cryptocell", not(feature = "52840")))]
+compile_error!("The CryptoCell is only available in the nRF52840");
+
+#[cfg(feature = "52832")]
+use nrf52832_pac as pac;
+#[cfg(feature = "52833")]
+use nrf52833_pac as pac;
+#[cfg(feature = "52840")]
+use nrf52840_pac as pac;
+
+#[cfg(feature = "cryptocell")]
+pub mod ccm;
+#[cfg(any(feature = "ecb", feature = "rustcrypto", test))]
+mod ccmstar;
+#[cfg(feature = "cryptocell")]
+pub mod cryptocell;
+#[cfg(feature = "drbg")]
+pub mod drbg;
+#[cfg(feature = "ecb")]
+pub mod ecb;
+pub mod error;
+#[cfg(any(feature = "cryptocell", feature = "software", test))]
+pub mod firmware;
+pub mod in_place;
+#[cfg(feature = "cryptocell")]
+pub mod interrupt;
+#[cfg(feature = "cryptocell")]
+pub mod key_cache;
+#[cfg(feature = "cryptocell")]
+pub mod key_derivation;
+#[cfg(feature = "cryptocell")]
+pub mod key_wrap;
+#[cfg(feature = "ieee802154")]
+pub mod mac_security;
+#[cfg(feature = "cryptocell")]
+pub mod modes;
+#[cfg(all(feature = "cryptocell", not(feature = "generate-bindings")))]
+mod nrf_cc310;
+#[cfg(all(feature = "cryptocell", feature = "generate-bindings"))]
+#[allow(
+    non_upper_case_globals,
+    non_camel_case_types,
+    non_snake_case,
+    dead_code
+)]
+mod nrf_cc310 {
+    include!(concat!(env!("OUT_DIR"), "/nrf_cc310.rs"));
+}
+#[cfg(any(feature = "cryptocell", test))]
+mod padding;
+#[cfg(feature = "cryptocell")]
+pub mod power;
+#[cfg(feature = "cryptocell")]
+pub mod request;
+#[cfg(feature = "rustcrypto")]
+pub mod rustcrypto;
+#[cfg(feature = "cryptocell")]
+pub mod self_test;
+pub mod zigbee;
+
+#[cfg(feature = "cryptocell")]
+pub use cryptocell::CryptoCell;
+#[cfg(feature = "ecb")]
+pub use ecb::EcbBackend;
+pub use in_place::CcmStarInPlace;
+#[cfg(feature = "cryptocell")]
+use nrf_cc310::*;
+
+/// The bindings kept in `src/nrf_cc310.rs`, compared with the bindings
+/// generated from the SDK headers
+#[cfg(all(feature = "cryptocell", feature = "generate-bindings"))]
+#[path = "nrf_cc310.rs"]
+mod checked_in_bindings;
+
+/// Compile time assertion that type `$t` has the same size in the checked in
+/// bindings as in the bindings generated from the SDK headers
+#[cfg(all(feature = "cryptocell", feature = "generate-bindings"))]
+macro_rules! assert_same_size {
+    ($($t:ident),* $(,)?) => {
+        $(
+            const _: [(); core::mem::size_of::<nrf_cc310::$t>()] =
+                [(); core::mem::size_of::<checked_in_bindings::$t>()];
+        )*
+    };
+}
+
+// Building with the `generate-bindings` feature fails if the checked in
+// bindings no longer match the SDK headers, regenerate them with `generate`
+#[cfg(all(feature = "cryptocell", feature = "generate-bindings"))]
+assert_same_size!(
+    SaSiAesUserContext_t,
+    SaSiAesUserKeyData_t,
+    SaSiAesIv_t,
+    SaSiAesEncryptMode_t,
+    SaSiAesOperationMode_t,
+    SaSiAesPaddingType_t,
+    SaSiAesKeyType_t,
+    CRYS_AESCCM_Key_t,
+    CRYS_AESCCM_Mac_Res_t,
+    CRYS_AESCCM_KeySize_t,
+    CRYS_HASHUserContext_t,
+    CRYS_HASH_Result_t,
+    CRYS_HASH_OperationMode_t,
+    CRYS_RND_State_t,
+    CRYS_RND_WorkBuff_t,
+    CRYS_ECPKI_DomainID_t,
+    CRYS_ECPKI_HASH_OpMode_t,
+    CRYS_ECPKI_UserPublKey_t,
+    CRYS_ECDSA_VerifyUserContext_t,
+    CRYS_ECPKI_BUILD_TempData_t,
+    CRYS_ECEDW_TempBuff_t,
+    SaSiUtilKeyType_t,
+    SaSiUtilPrfType_t,
+);
+
+/// Compile time assertion that constant `$c` has the same value in the checked
+/// in bindings as in the bindings generated from the SDK headers
+#[cfg(all(feature = "cryptocell", feature = "generate-bindings"))]
+macro_rules! assert_same_value {
+    ($($c:ident),* $(,)?) => {
+        $(
+            const _: [(); nrf_cc310::$c as usize] = [(); checked_in_bindings::$c as usize];
+        )*
+    };
+}
+
+// The error codes that are matched on, rather than passed on as `Error::Other`
+#[cfg(all(feature = "cryptocell", feature = "generate-bindings"))]
+assert_same_value!(
+    CRYS_ECDSA_VERIFY_INCONSISTENT_VERIFY_ERROR,
+    CRYS_ECEDW_SIGN_VERIFY_FAILED_ERROR,
+);
+
+/// Version of the linked nrf_cc310 library
+#[cfg(feature = "cryptocell")]
+pub const LIBRARY_VERSION: &str = env!("NRF_CC310_VERSION");
+
+pub use error::Error;
+#[cfg(feature = "psila")]
+pub use psila_crypto::CryptoBackend;
+
+/// Size of an AES block, in bytes
+pub const BLOCK_SIZE: usize = 16;
+/// Size of an AES-128 key, in bytes
+pub const KEY_SIZE: usize = 16;
+/// Size of the CCM* length field, in bytes
+pub const LENGTH_FIELD_LENGTH: usize = 2;
+
+/// Block cipher operations
+///
+/// The same operations as the `psila_crypto::BlockCipher` trait, which is
+/// implemented as well when the `psila` feature is enabled.
+pub trait BlockCipher {
+    /// Set the key to be used in the cipher operation
+    fn set_key(&mut self, key: &[u8]) -> Result<(), Error>;
+    /// Set the IV
+    fn set_iv(&mut self, iv: &[u8]) -> Result<(), Error>;
+    /// Get the IV
+    fn get_iv(&mut self, iv: &mut [u8]) -> Result<(), Error>;
+    /// Process a block of data
+    fn process_block(&mut self, input: &[u8], output: &mut [u8]) -> Result<(), Error>;
+    /// Finish the cipher operation
+    fn finish(&mut self, input: &[u8], output: &mut [u8]) -> Result<(), Error>;
+}
 
+#[cfg(any(
+    feature = "cryptocell",
+    feature = "ecb",
+    feature = "software",
+    feature = "ieee802154",
+    test
+))]
 fn clear(slice: &mut [u8]) {
     for v in slice.iter_mut() {
         *v = 0;
     }
 }
 
+/// Create the flag byte of the CCM* B0 and A0 blocks
+#[cfg(any(feature = "cryptocell", feature = "ecb", test))]
+fn make_flag(a_length: usize, big_m: usize, big_l: usize) -> u8 {
+    let mut flag = if a_length > 0 { 0x40 } else { 0 };
+    flag = if big_m > 0 {
+        flag | ((((big_m - 2) / 2) as u8) & 0x07) << 3
+    } else {
+        flag
+    };
+    flag |= 0x07 & ((big_l - 1) as u8);
+    flag
+}
+
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum EncryptDecrypt {
     /// Encryp operation
     Encrypt = 0,
@@ -31,6 +228,7 @@ pub enum EncryptDecrypt {
 
 /// Block cipher key type
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum KeyType {
     /// 128-bit AES key
     Aes128 = 0,
@@ -44,6 +242,7 @@ pub enum KeyType {
 
 /// Block cipher operation mode
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum AesOperationMode {
     /// Electronic codebook
     Ecb = 0,
@@ -67,6 +266,7 @@ pub enum AesOperationMode {
 
 /// Padding type
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PaddingType {
     /// None, padded with zeroes
     None = 0,
@@ -74,135 +274,136 @@ pub enum PaddingType {
     Pkcs7 = 1,
 }
 
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-struct CryptoCellAesContext {
-    buff: [u32; 19usize],
+#[cfg(feature = "cryptocell")]
+/// CryptoCell AES context
+///
+/// The context is kept in RAM, the CryptoCell is only enabled while an
+/// operation is running.
+pub struct AesContext<'a> {
+    cryptocell: &'a CryptoCell,
+    context: SaSiAesUserContext_t,
 }
 
-extern "C" {
-    /// Initialize CryptoCell runtime library
-    fn SaSi_LibInit() -> u32;
-    /// Finalize library operations
-    fn SaSi_LibFini();
-    /// Initialize AES context
-    fn SaSi_AesInit(
-        // The context to initalize
-        context: *mut CryptoCellAesContext,
-        // Encrypt (0) or decrypt (1)
-        decrypt: u32,
-        // AES operation mode,
-        //   0 - ECB
-        //   1 - CBC
-        //   2 - CBC-MAC
-        //   3 - CTR
-        //   4 - XCBC-MAC
-        //   5 - CMAC
-        //   6 - XTS
-        //   7 - CBC-CTS
-        //   8 - OFB
-        operationMode: u32,
-        // Padding type, None (0) or PKCS7 (1)
-        paddingType: u32,
-    ) -> u32;
-    /// Set a key for the AES context
-    fn SaSi_AesSetKey(
-        // AES context
-        context: *mut CryptoCellAesContext,
-        // Key type used
-        //   0 - User key
-        //   1 - Platform key
-        //   2 - Customer key
-        keyType: u32,
-        // Pointer to key struct
-        keyData: *const cty::c_void,
-        // Size of the key struct
-        keyDataSize: usize,
-    ) -> u32;
-    /// Set IV (or counter) for the AES context
-    fn SaSi_AesSetIv(context: *mut CryptoCellAesContext, iv: *const u8) -> u32;
-    /// Get IV (or counter) for the AES context
-    fn SaSi_AesGetIv(context: *mut CryptoCellAesContext, iv: *mut u8) -> u32;
-    /// Process a block of data
-    fn SaSi_AesBlock(
-        // AES context
-        context: *mut CryptoCellAesContext,
-        // Data to process
-        dataIn: *const u8,
-        // Size of data to process
-        dataInSize: usize,
-        // Pointer to output data
-        dataOut: *mut u8,
-    ) -> u32;
-    /// Finalize a cipher operation
-    fn SaSi_AesFinish(
-        // AES context
-        context: *mut CryptoCellAesContext,
-        // Size of data to process
-        dataInSize: usize,
-        // Data to process
-        dataIn: *const u8,
-        // Size of data buffer provided
-        dataInBufferSize: usize,
-        // Pointer to output data
-        dataOut: *mut u8,
-        // Size of output buffer provided
-        DataOutBufferSize: *mut usize,
-    ) -> u32;
-    /// Clean up a AES context
-    fn SaSi_AesFree(
-        // AES context
-        context: *mut CryptoCellAesContext,
-    ) -> u32;
-}
+#[cfg(feature = "cryptocell")]
+impl<'a> AesContext<'a> {
+    /// Initialise a context, the key is set separately
+    pub fn new(
+        cryptocell: &'a CryptoCell,
+        encrypt: EncryptDecrypt,
+        mode: AesOperationMode,
+        padding_type: PaddingType,
+    ) -> Result<Self, Error> {
+        let mut context = SaSiAesUserContext_t {
+            buff: [0u32; SASI_AES_USER_CTX_SIZE_IN_WORDS as usize],
+        };
+        let ctx_ptr = &mut context as *mut SaSiAesUserContext_t;
+        let result = {
+            let _access = cryptocell.access()?;
+            unsafe {
+                SaSi_AesInit(
+                    ctx_ptr,
+                    encrypt as SaSiAesEncryptMode_t,
+                    mode as SaSiAesOperationMode_t,
+                    padding_type as SaSiAesPaddingType_t,
+                )
+            }
+        };
+        if result != 0 {
+            return Err(Error::Other(result));
+        }
 
-/// CryptoCell Key Data
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-struct KeyData {
-    /// Key data
-    pub key: *const u8,
-    /// Key length
-    pub size: usize,
-}
+        Ok(Self {
+            cryptocell,
+            context,
+        })
+    }
 
-/// CryptoCell AES context
-pub struct AesContext {
-    context: CryptoCellAesContext,
-}
+    fn context(&mut self) -> *mut SaSiAesUserContext_t {
+        &mut self.context as *mut SaSiAesUserContext_t
+    }
 
-impl AesContext {
-    pub fn new(encrypt: EncryptDecrypt, mode: AesOperationMode, padding_type: PaddingType) -> Self {
-        let mut context = CryptoCellAesContext { buff: [0u32; 19] };
-        let ctx_ptr = &mut context as *mut CryptoCellAesContext;
-        let result =
-            unsafe { SaSi_AesInit(ctx_ptr, encrypt as u32, mode as u32, padding_type as u32) };
+    /// Set a key of any size supported by the operation mode
+    ///
+    /// AES accepts 16, 24 or 32 byte keys, XTS takes two keys, 32 or 64 bytes.
+    pub(crate) fn set_user_key(&mut self, key: &[u8]) -> Result<(), Error> {
+        assert!(key.len() <= SASI_AES_KEY_MAX_SIZE_IN_BYTES as usize);
+        let mut user_key = SaSiAesUserKeyData_t {
+            pKey: key.as_ptr() as *mut u8,
+            keySize: key.len(),
+        };
+        let _access = self.cryptocell.access()?;
+        let result = unsafe {
+            SaSi_AesSetKey(
+                self.context(),
+                SaSiAesKeyType_t_SASI_AES_USER_KEY,
+                &mut user_key as *mut SaSiAesUserKeyData_t as *mut cty::c_void,
+                core::mem::size_of::<SaSiAesUserKeyData_t>(),
+            )
+        };
         if result != 0 {
-            panic!("Failed to initialize AES context {:08x}", result);
+            return Err(Error::Other(result));
         }
-
-        Self { context }
+        Ok(())
     }
 
-    fn context(&mut self) -> *mut CryptoCellAesContext {
-        &mut self.context as *mut CryptoCellAesContext
+    /// Finish the cipher operation
+    ///
+    /// Processes all of `input`, which may be of any length the operation mode
+    /// accepts. Returns the number of bytes written to `output`.
+    pub fn finish_with_length(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        let mut output_length = output.len();
+        let _access = self.cryptocell.access()?;
+        let result = unsafe {
+            SaSi_AesFinish(
+                self.context(),
+                input.len(),
+                input.as_ptr() as *mut u8,
+                input.len(),
+                output.as_mut_ptr(),
+                (&mut output_length) as *mut usize,
+            )
+        };
+        if result != 0 {
+            return Err(Error::Other(result));
+        }
+        Ok(output_length)
     }
 }
 
-impl BlockCipher for AesContext {
-    /// Set the key to be used in the cipher operation
-    fn set_key(&mut self, key: &[u8]) -> Result<(), Error> {
-        assert!(key.len() == KEY_SIZE);
-        let user_key = KeyData {
-            key: key.as_ptr(),
-            size: key.len(),
+#[cfg(feature = "cryptocell")]
+impl<'a> AesContext<'a> {
+    /// Process a block of data in place
+    pub(crate) fn process_block_in_place(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        assert!(data.len() <= 65535);
+        let _access = self.cryptocell.access()?;
+        let result = unsafe {
+            SaSi_AesBlock(
+                self.context(),
+                data.as_mut_ptr(),
+                data.len(),
+                data.as_mut_ptr(),
+            )
         };
+        if result != 0 {
+            return Err(Error::Other(result));
+        }
+        Ok(())
+    }
+
+    /// Finish the cipher operation in place
+    ///
+    /// Only for modes where the output has the same length as the input.
+    pub(crate) fn finish_in_place(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        let mut output_length = data.len();
+        let _access = self.cryptocell.access()?;
         let result = unsafe {
-            SaSi_AesSetKey(
+            SaSi_AesFinish(
                 self.context(),
-                0, // User key
-                &user_key as *const KeyData as *const cty::c_void,
-                core::mem::size_of::<KeyData>(),
+                data.len(),
+                data.as_mut_ptr(),
+                data.len(),
+                data.as_mut_ptr(),
+                (&mut output_length) as *mut usize,
             )
         };
         if result != 0 {
@@ -210,11 +411,21 @@ impl BlockCipher for AesContext {
         }
         Ok(())
     }
+}
+
+#[cfg(feature = "cryptocell")]
+impl<'a> BlockCipher for AesContext<'a> {
+    /// Set the key to be used in the cipher operation
+    fn set_key(&mut self, key: &[u8]) -> Result<(), Error> {
+        assert!(key.len() == KEY_SIZE);
+        self.set_user_key(key)
+    }
 
     /// Set the IV
     fn set_iv(&mut self, iv: &[u8]) -> Result<(), Error> {
-        assert!(iv.len() == 16);
-        let result = unsafe { SaSi_AesSetIv(self.context(), iv.as_ptr()) };
+        assert!(iv.len() == SASI_AES_IV_SIZE_IN_BYTES as usize);
+        let _access = self.cryptocell.access()?;
+        let result = unsafe { SaSi_AesSetIv(self.context(), iv.as_ptr() as *mut u8) };
         if result != 0 {
             return Err(Error::Other(result));
         }
@@ -223,7 +434,8 @@ impl BlockCipher for AesContext {
 
     /// Get the IV
     fn get_iv(&mut self, iv: &mut [u8]) -> Result<(), Error> {
-        assert!(iv.len() == 16);
+        assert!(iv.len() == SASI_AES_IV_SIZE_IN_BYTES as usize);
+        let _access = self.cryptocell.access()?;
         let result = unsafe { SaSi_AesGetIv(self.context(), iv.as_mut_ptr()) };
         if result != 0 {
             return Err(Error::Other(result));
@@ -235,10 +447,11 @@ impl BlockCipher for AesContext {
     fn process_block(&mut self, input: &[u8], output: &mut [u8]) -> Result<(), Error> {
         assert!(input.len() <= output.len());
         assert!(input.len() <= 65535);
+        let _access = self.cryptocell.access()?;
         let result = unsafe {
             SaSi_AesBlock(
                 self.context(),
-                input.as_ptr(),
+                input.as_ptr() as *mut u8,
                 input.len(),
                 output.as_mut_ptr(),
             )
@@ -252,75 +465,119 @@ impl BlockCipher for AesContext {
     /// Finish the cipher operation
     fn finish(&mut self, input: &[u8], output: &mut [u8]) -> Result<(), Error> {
         assert!(input.len() <= output.len());
-        let mut output_length = output.len();
-        let result = unsafe {
-            SaSi_AesFinish(
-                self.context(),
-                16,
-                input.as_ptr(),
-                16,
-                output.as_mut_ptr(),
-                (&mut output_length) as *mut usize,
-            )
-        };
-        if result != 0 {
-            return Err(Error::Other(result));
-        }
-        Ok(())
+        self.finish_with_length(input, output).map(|_| ())
     }
 }
 
-impl core::ops::Drop for AesContext {
-    fn drop(&mut self) {
-        unsafe { SaSi_AesFree(self.context()) };
+#[cfg(feature = "cryptocell")]
+impl<'a> core::fmt::Debug for AesContext<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        // The context holds the key
+        f.debug_struct("AesContext")
+            .field("context", &error::Redacted)
+            .finish()
     }
 }
 
-pub struct CryptoCellBackend {
-    cryptocell: CRYPTOCELL,
-    cipher: AesContext,
+#[cfg(all(feature = "cryptocell", feature = "psila"))]
+impl<'a> psila_crypto::BlockCipher for AesContext<'a> {
+    fn set_key(&mut self, key: &[u8]) -> Result<(), psila_crypto::Error> {
+        Ok(BlockCipher::set_key(self, key)?)
+    }
+
+    fn set_iv(&mut self, iv: &[u8]) -> Result<(), psila_crypto::Error> {
+        Ok(BlockCipher::set_iv(self, iv)?)
+    }
+
+    fn get_iv(&mut self, iv: &mut [u8]) -> Result<(), psila_crypto::Error> {
+        Ok(BlockCipher::get_iv(self, iv)?)
+    }
+
+    fn process_block(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), psila_crypto::Error> {
+        Ok(BlockCipher::process_block(self, input, output)?)
+    }
+
+    fn finish(&mut self, input: &[u8], output: &mut [u8]) -> Result<(), psila_crypto::Error> {
+        Ok(BlockCipher::finish(self, input, output)?)
+    }
 }
 
-impl core::ops::Drop for CryptoCellBackend {
+#[cfg(feature = "cryptocell")]
+impl<'a> core::ops::Drop for AesContext<'a> {
     fn drop(&mut self) {
-        unsafe { SaSi_LibFini() }
-        self.cryptocell.enable.write(|w| w.enable().clear_bit());
+        match self.cryptocell.access() {
+            Ok(_access) => unsafe {
+                SaSi_AesFree(self.context());
+            },
+            // Another operation is running, free clears the context in memory
+            // only, so clear it here instead
+            Err(_) => self.context.buff.iter_mut().for_each(|w| *w = 0),
+        }
     }
 }
 
-const AAD_B0_LEN: usize = BLOCK_SIZE - LENGTH_FIELD_LENGTH;
+#[cfg(feature = "cryptocell")]
+pub struct CryptoCellBackend<'a> {
+    cryptocell: &'a CryptoCell,
+    /// Context for the psila AES-128-ECB operations
+    #[cfg(feature = "psila")]
+    cipher: AesContext<'a>,
+    keys: key_cache::KeyCache<'a>,
+}
 
-impl CryptoCellBackend {
-    pub fn new(cryptocell: CRYPTOCELL) -> Self {
-        cryptocell.enable.write(|w| w.enable().set_bit());
+#[cfg(feature = "cryptocell")]
+impl<'a> CryptoCellBackend<'a> {
+    /// Backend keeping keyed contexts for the most recently used keys, see
+    /// [`key_cache`]
+    pub fn new(cryptocell: &'a CryptoCell) -> Result<Self, Error> {
+        Self::with_key_cache(cryptocell, true)
+    }
 
-        if unsafe { SaSi_LibInit() } != 0 {
-            panic!("Failed to initialize SaSi library");
-        }
+    /// Backend that keys new contexts for every CCM* operation and frees them
+    /// when the operation is done
+    ///
+    /// Slower than a backend with the key cache, but no CCM* key or context is
+    /// kept in memory between operations.
+    pub fn without_key_cache(cryptocell: &'a CryptoCell) -> Result<Self, Error> {
+        Self::with_key_cache(cryptocell, false)
+    }
 
-        let cipher = AesContext::new(
-            EncryptDecrypt::Encrypt,
-            AesOperationMode::Ecb,
-            PaddingType::None,
-        );
+    fn with_key_cache(cryptocell: &'a CryptoCell, key_cache: bool) -> Result<Self, Error> {
+        Ok(Self {
+            cryptocell,
+            #[cfg(feature = "psila")]
+            cipher: AesContext::new(
+                cryptocell,
+                EncryptDecrypt::Encrypt,
+                AesOperationMode::Ecb,
+                PaddingType::None,
+            )?,
+            keys: key_cache::KeyCache::new(key_cache),
+        })
+    }
 
-        Self { cryptocell, cipher }
+    /// The CryptoCell used by the backend
+    pub fn cryptocell(&self) -> &'a CryptoCell {
+        self.cryptocell
     }
+}
 
-    /// Create
-    fn make_flag(a_length: usize, big_m: usize, big_l: usize) -> u8 {
-        let mut flag = if a_length > 0 { 0x40 } else { 0 };
-        flag = if big_m > 0 {
-            flag | ((((big_m - 2) / 2) as u8) & 0x07) << 3
-        } else {
-            flag
-        };
-        flag |= 0x07 & ((big_l - 1) as u8);
-        flag
+#[cfg(feature = "cryptocell")]
+impl<'a> core::fmt::Debug for CryptoCellBackend<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("CryptoCellBackend")
+            .field("cryptocell", self.cryptocell)
+            .field("keys", &self.keys)
+            .finish()
     }
 }
 
-impl CryptoBackend for CryptoCellBackend {
+#[cfg(all(feature = "cryptocell", feature = "psila"))]
+impl<'a> CryptoBackend for CryptoCellBackend<'a> {
     fn ccmstar_decrypt(
         &mut self,
         key: &[u8],
@@ -329,140 +586,12 @@ impl CryptoBackend for CryptoCellBackend {
         mic: &[u8],
         aad: &[u8],
         decrypted: &mut [u8],
-    ) -> Result<usize, Error> {
-        assert!(key.len() == KEY_SIZE);
-        assert!(nonce.len() == 13);
+    ) -> Result<usize, psila_crypto::Error> {
         assert!(decrypted.len() >= encrypted.len());
 
-        let enc_full_block_length = (encrypted.len() / BLOCK_SIZE) * BLOCK_SIZE;
-
-        let mut tag = [0; BLOCK_SIZE];
-        {
-            // Decrypt data
-            let mut cipher = AesContext::new(
-                EncryptDecrypt::Decrypt,
-                AesOperationMode::Ctr,
-                PaddingType::None,
-            );
-            cipher.set_key(key)?;
-
-            let mut block = [0u8; BLOCK_SIZE];
-            {
-                let (flag, other) = block.split_at_mut(1);
-                let (_nonce, _counter) = other.split_at_mut(nonce.len());
-                flag[0] = Self::make_flag(0, 0, LENGTH_FIELD_LENGTH);
-                _nonce.copy_from_slice(&nonce);
-            }
-
-            cipher.set_iv(&block)?;
-
-            let mut block = [0u8; BLOCK_SIZE];
-            block[..mic.len()].copy_from_slice(&mic);
-
-            cipher.process_block(&block, &mut tag)?;
-
-            if enc_full_block_length > 0 {
-                cipher.process_block(&encrypted[..enc_full_block_length], decrypted)?;
-            }
-
-            cipher.finish(
-                &encrypted[enc_full_block_length..],
-                &mut decrypted[enc_full_block_length..],
-            )?;
-        }
-        let mut output = [0u8; BLOCK_SIZE];
-        {
-            // Validate MIC using AES128-CBC-MAC
-            let mut cipher = AesContext::new(
-                EncryptDecrypt::Encrypt,
-                AesOperationMode::CbcMac,
-                PaddingType::None,
-            );
-            cipher.set_key(key)?;
-
-            let length_field = encrypted.len() as u16;
-
-            let mut block = [0u8; BLOCK_SIZE];
-            {
-                let (flag, other) = block.split_at_mut(1);
-                let (_nonce, length) = other.split_at_mut(nonce.len());
-                flag[0] = Self::make_flag(aad.len(), mic.len(), LENGTH_FIELD_LENGTH);
-                _nonce.copy_from_slice(&nonce);
-                length[0] = (length_field >> 8) as u8;
-                length[1] = (length_field & 0x00ff) as u8;
-            }
-
-            cipher.process_block(&block, &mut output)?;
-
-            // Feed the additional data
-            let mut block = [0u8; BLOCK_SIZE];
-            let aad_length = aad.len() as u16;
-            block[0] = (aad_length >> 8) as u8;
-            block[1] = (aad_length & 0x00ff) as u8;
-            let len = if aad.len() < AAD_B0_LEN {
-                aad.len()
-            } else {
-                AAD_B0_LEN
-            };
-            block[2..2 + len].copy_from_slice(&aad[..len]);
-
-            cipher.process_block(&block, &mut output)?;
-
-            if aad.len() > AAD_B0_LEN {
-                let mut iter = aad[AAD_B0_LEN..].chunks_exact(BLOCK_SIZE);
-                loop {
-                    match iter.next() {
-                        Some(input) => {
-                            cipher.process_block(input, &mut output)?;
-                        }
-                        None => {
-                            let input = iter.remainder();
-                            if !input.is_empty() {
-                                let mut block = [0u8; BLOCK_SIZE];
-                                block[..input.len()].copy_from_slice(input);
-                                cipher.process_block(&block, &mut output)?;
-                            }
-                            break;
-                        }
-                    }
-                }
-            }
-
-            let block_last = ((encrypted.len() + (BLOCK_SIZE - 1)) / BLOCK_SIZE) - 1;
-            let mut block_index = 0;
-            let mut iter = decrypted[..encrypted.len()].chunks_exact(BLOCK_SIZE);
-            loop {
-                match iter.next() {
-                    Some(input) => {
-                        if block_index < block_last {
-                            cipher.process_block(input, &mut output)?;
-                        } else {
-                            cipher.finish(input, &mut output)?;
-                        }
-                    }
-                    None => {
-                        let mut block = [0u8; BLOCK_SIZE];
-                        let input = iter.remainder();
-                        block[..input.len()].copy_from_slice(input);
-                        // Must feed a full block into finish here, otherwise the
-                        // result will be wrong
-                        cipher.finish(&block, &mut output)?;
-                        break;
-                    }
-                }
-                block_index += 1;
-            }
-        }
-
-        let mut valid = true;
-        for (a, b) in tag[..mic.len()].iter().zip(output[..mic.len()].iter()) {
-            if a != b {
-                valid = false;
-                break;
-            }
-        }
-
-        if valid {
+        let data = &mut decrypted[..encrypted.len()];
+        data.copy_from_slice(encrypted);
+        if self.ccmstar_decrypt_in_place(key, nonce, data, mic, aad)? {
             Ok(encrypted.len())
         } else {
             clear(decrypted);
@@ -479,112 +608,32 @@ impl CryptoBackend for CryptoCellBackend {
         aad: &[u8],
         output: &mut [u8],
     ) -> Result<usize, psila_crypto::Error> {
-        let mut new_mic = [0u8; BLOCK_SIZE];
-        let mut buffer = [0u8; 128];
-        // Generate a MIC
-        {
-            let aad_blocks = (aad.len() + LENGTH_FIELD_LENGTH + (BLOCK_SIZE - 1)) / BLOCK_SIZE;
-            let mut offset = 0;
-
-            buffer[0] = Self::make_flag(aad.len(), mic.len(), LENGTH_FIELD_LENGTH);
-            offset += 1;
-            buffer[offset..offset + nonce.len()].copy_from_slice(nonce);
-            offset += nonce.len();
-            let message_len = message.len() as u16;
-            buffer[offset] = (message_len >> 8) as u8;
-            buffer[offset + 1] = (message_len & 0x00ff) as u8;
-            offset += 2;
-            let aad_len = aad.len() as u16;
-            buffer[offset] = (aad_len >> 8) as u8;
-            buffer[offset + 1] = (aad_len & 0x00ff) as u8;
-            offset += 2;
-            buffer[offset..offset + aad.len()].copy_from_slice(aad);
-            offset += (aad_blocks * BLOCK_SIZE) - 2;
-            buffer[offset..offset + message.len()].copy_from_slice(message);
-            offset += message.len();
-
-            let mut cipher = AesContext::new(
-                EncryptDecrypt::Encrypt,
-                AesOperationMode::CbcMac,
-                PaddingType::None,
-            );
-            cipher.set_key(key)?;
-
-            let block_last = ((offset + (BLOCK_SIZE - 1)) / BLOCK_SIZE) - 1;
-            let mut block_index = 0;
-            let mut iter = buffer[..offset].chunks_exact(BLOCK_SIZE);
-            loop {
-                match iter.next() {
-                    Some(input) => {
-                        if block_index < block_last {
-                            cipher.process_block(&input, &mut new_mic)?;
-                        } else {
-                            cipher.finish(&input, &mut new_mic)?;
-                            break;
-                        }
-                    }
-                    None => {
-                        let mut block = [0u8; BLOCK_SIZE];
-                        block[..iter.remainder().len()].copy_from_slice(iter.remainder());
-                        cipher.finish(&block, &mut new_mic)?;
-                        break;
-                    }
-                }
-                block_index += 1;
-            }
-        }
-        clear(&mut buffer[..]);
-        {
-            let message_blocks = (message.len() + (BLOCK_SIZE - 1)) / BLOCK_SIZE;
-            let mut offset = 0;
-
-            buffer[..message.len()].copy_from_slice(message);
-            offset += message_blocks * BLOCK_SIZE;
-
-            let mut block = [0u8; BLOCK_SIZE];
-            block[0] = Self::make_flag(0, 0, LENGTH_FIELD_LENGTH);
-            block[1..=nonce.len()].copy_from_slice(nonce);
-
-            let mut cipher = AesContext::new(
-                EncryptDecrypt::Encrypt,
-                AesOperationMode::Ctr,
-                PaddingType::None,
-            );
-            cipher.set_key(key)?;
-            cipher.set_iv(&block)?;
-
-            let mut block = [0u8; BLOCK_SIZE];
-            let mut tag = [0u8; 16];
-            block[..mic.len()].copy_from_slice(&new_mic[..mic.len()]);
-            cipher.process_block(&block, &mut tag)?;
-
-            for (o, i) in output[..offset]
-                .chunks_mut(BLOCK_SIZE)
-                .zip(buffer.chunks(BLOCK_SIZE))
-            {
-                cipher.process_block(i, o)?;
-            }
-
-            mic.copy_from_slice(&tag[..mic.len()]);
-        }
+        assert!(output.len() >= message.len());
 
+        let data = &mut output[..message.len()];
+        data.copy_from_slice(message);
+        self.ccmstar_encrypt_in_place(key, nonce, data, mic, aad)?;
         Ok(message.len())
     }
 
     /// Set the key
-    fn aes128_ecb_encrypt_set_key(&mut self, key: &[u8]) -> Result<(), Error> {
-        self.cipher.set_key(key)
+    fn aes128_ecb_encrypt_set_key(&mut self, key: &[u8]) -> Result<(), psila_crypto::Error> {
+        Ok(self.cipher.set_key(key)?)
     }
     /// Process blocks of data
     fn aes128_ecb_encrypt_process_block(
         &mut self,
         input: &[u8],
         output: &mut [u8],
-    ) -> Result<(), Error> {
-        self.cipher.process_block(input, output)
+    ) -> Result<(), psila_crypto::Error> {
+        Ok(self.cipher.process_block(input, output)?)
     }
     /// Process the last bits and bobs and finish
-    fn aes128_ecb_encrypt_finish(&mut self, input: &[u8], output: &mut [u8]) -> Result<(), Error> {
-        self.cipher.finish(input, output)
+    fn aes128_ecb_encrypt_finish(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), psila_crypto::Error> {
+        Ok(self.cipher.finish(input, output)?)
     }
 }
@@ -0,0 +1,704 @@
+//! IEEE 802.15.4 MAC frame security
+//!
+//! Secure and unsecure MAC frames as described in IEEE 802.15.4-2006, using
+//! CCM* from any [`CcmStarInPlace`] backend.
+//!
+//! The `ieee802154` crate does not handle the auxiliary security header, so
+//! the frames are handled as bytes here. [`secure_frame`] encodes an unsecured
+//! frame, inserts the auxiliary security header and secures it.
+//! [`unsecure_frame`] does the reverse and returns the frame decoded as an
+//! unsecured frame. [`secure_encoded_frame`] and [`unsecure_frame_in_place`]
+//! do the same for frames that already are encoded.
+//!
+//! The frames are handled without the FCS.
+
+use ieee802154::mac::{ExtendedAddress, Frame, WriteFooter};
+
+use crate::{clear, CcmStarInPlace, Error, BLOCK_SIZE};
+
+/// Maximum size of a frame, without the FCS
+pub const MAX_FRAME_SIZE: usize = 125;
+/// Size of the MAC CCM* nonce
+pub const NONCE_SIZE: usize = 13;
+
+const FRAME_TYPE_DATA: u16 = 1;
+const FRAME_TYPE_MAC_COMMAND: u16 = 3;
+const SECURITY_ENABLED: u16 = 1 << 3;
+const PAN_ID_COMPRESS: u16 = 1 << 6;
+const FRAME_VERSION_MASK: u16 = 0b11 << 12;
+const FRAME_VERSION_2006: u16 = 1 << 12;
+
+/// Security level
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SecurityLevel {
+    /// No security
+    None = 0,
+    /// Authentication with a 4 byte MIC
+    Mic32 = 1,
+    /// Authentication with a 8 byte MIC
+    Mic64 = 2,
+    /// Authentication with a 16 byte MIC
+    Mic128 = 3,
+    /// Encryption without authentication
+    Enc = 4,
+    /// Encryption and authentication with a 4 byte MIC
+    EncMic32 = 5,
+    /// Encryption and authentication with a 8 byte MIC
+    EncMic64 = 6,
+    /// Encryption and authentication with a 16 byte MIC
+    EncMic128 = 7,
+}
+
+impl SecurityLevel {
+    fn from_bits(bits: u8) -> Self {
+        match bits & 0x07 {
+            1 => SecurityLevel::Mic32,
+            2 => SecurityLevel::Mic64,
+            3 => SecurityLevel::Mic128,
+            4 => SecurityLevel::Enc,
+            5 => SecurityLevel::EncMic32,
+            6 => SecurityLevel::EncMic64,
+            7 => SecurityLevel::EncMic128,
+            _ => SecurityLevel::None,
+        }
+    }
+
+    /// Length of the MIC in bytes
+    pub fn mic_length(self) -> usize {
+        match (self as u8) & 0x03 {
+            1 => 4,
+            2 => 8,
+            3 => 16,
+            _ => 0,
+        }
+    }
+
+    /// Returns true if the payload is encrypted
+    pub fn encrypted(self) -> bool {
+        (self as u8) & 0x04 == 0x04
+    }
+}
+
+/// Key identifier
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum KeyIdentifier {
+    /// Key determined implicitly from the originator and recipient
+    Implicit,
+    /// Key determined from the key index and the default key source
+    Index(u8),
+    /// Key determined from a 4 byte key source and the key index
+    Source4([u8; 4], u8),
+    /// Key determined from a 8 byte key source and the key index
+    Source8([u8; 8], u8),
+}
+
+impl KeyIdentifier {
+    fn mode(&self) -> u8 {
+        match self {
+            KeyIdentifier::Implicit => 0,
+            KeyIdentifier::Index(_) => 1,
+            KeyIdentifier::Source4(_, _) => 2,
+            KeyIdentifier::Source8(_, _) => 3,
+        }
+    }
+
+    fn length(&self) -> usize {
+        match self {
+            KeyIdentifier::Implicit => 0,
+            KeyIdentifier::Index(_) => 1,
+            KeyIdentifier::Source4(_, _) => 5,
+            KeyIdentifier::Source8(_, _) => 9,
+        }
+    }
+}
+
+/// Auxiliary security header
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AuxiliarySecurityHeader {
+    pub level: SecurityLevel,
+    pub key_identifier: KeyIdentifier,
+    pub frame_counter: u32,
+}
+
+impl AuxiliarySecurityHeader {
+    /// Length of the encoded header
+    pub fn length(&self) -> usize {
+        5 + self.key_identifier.length()
+    }
+
+    /// Decode the header from `data`
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        if data.len() < 5 {
+            return Err(Error::InvalidFrame);
+        }
+        let control = data[0];
+        let level = SecurityLevel::from_bits(control);
+        let mut frame_counter = [0u8; 4];
+        frame_counter.copy_from_slice(&data[1..5]);
+        let frame_counter = u32::from_le_bytes(frame_counter);
+        let data = &data[5..];
+        let key_identifier = match (control >> 3) & 0x03 {
+            0 => KeyIdentifier::Implicit,
+            1 if !data.is_empty() => KeyIdentifier::Index(data[0]),
+            2 if data.len() >= 5 => {
+                let mut source = [0u8; 4];
+                source.copy_from_slice(&data[..4]);
+                KeyIdentifier::Source4(source, data[4])
+            }
+            3 if data.len() >= 9 => {
+                let mut source = [0u8; 8];
+                source.copy_from_slice(&data[..8]);
+                KeyIdentifier::Source8(source, data[8])
+            }
+            _ => return Err(Error::InvalidFrame),
+        };
+        Ok(Self {
+            level,
+            key_identifier,
+            frame_counter,
+        })
+    }
+
+    /// Encode the header into `data`, returns the length
+    pub fn encode(&self, data: &mut [u8]) -> usize {
+        assert!(data.len() >= self.length());
+        data[0] = (self.level as u8) | (self.key_identifier.mode() << 3);
+        data[1..5].copy_from_slice(&self.frame_counter.to_le_bytes());
+        match self.key_identifier {
+            KeyIdentifier::Implicit => (),
+            KeyIdentifier::Index(index) => data[5] = index,
+            KeyIdentifier::Source4(source, index) => {
+                data[5..9].copy_from_slice(&source);
+                data[9] = index;
+            }
+            KeyIdentifier::Source8(source, index) => {
+                data[5..13].copy_from_slice(&source);
+                data[13] = index;
+            }
+        }
+        self.length()
+    }
+}
+
+/// Create the CCM* nonce
+///
+/// The nonce is the extended address of the originator, the frame counter and
+/// the security level.
+pub fn make_nonce(source: ExtendedAddress, frame_counter: u32, level: SecurityLevel) -> [u8; 13] {
+    let mut nonce = [0u8; NONCE_SIZE];
+    nonce[..8].copy_from_slice(&source.0.to_be_bytes());
+    nonce[8..12].copy_from_slice(&frame_counter.to_be_bytes());
+    nonce[12] = level as u8;
+    nonce
+}
+
+fn frame_control(frame: &[u8]) -> Result<u16, Error> {
+    if frame.len() < 3 {
+        return Err(Error::InvalidFrame);
+    }
+    Ok(u16::from(frame[0]) | (u16::from(frame[1]) << 8))
+}
+
+fn write_frame_control(frame: &mut [u8], frame_control: u16) {
+    frame[0] = (frame_control & 0x00ff) as u8;
+    frame[1] = (frame_control >> 8) as u8;
+}
+
+/// Length of the MAC header, up to the auxiliary security header
+fn header_length(frame_control: u16) -> Result<usize, Error> {
+    let address_length = |mode| match mode {
+        0 => Ok(0),
+        2 => Ok(2),
+        3 => Ok(8),
+        _ => Err(Error::InvalidFrame),
+    };
+    let destination_mode = (frame_control >> 10) & 0x03;
+    let source_mode = (frame_control >> 14) & 0x03;
+    let mut length = 3;
+    if destination_mode != 0 {
+        length += 2 + address_length(destination_mode)?;
+    }
+    if source_mode != 0 {
+        if destination_mode == 0 || frame_control & PAN_ID_COMPRESS == 0 {
+            length += 2;
+        }
+        length += address_length(source_mode)?;
+    }
+    Ok(length)
+}
+
+/// Length of the frame content, the unencrypted part before the payload
+fn content_length(frame_control: u16) -> Result<usize, Error> {
+    match frame_control & 0x07 {
+        FRAME_TYPE_DATA => Ok(0),
+        FRAME_TYPE_MAC_COMMAND => Ok(1),
+        _ => Err(Error::InvalidFrame),
+    }
+}
+
+/// Read the auxiliary security header of a secured frame
+///
+/// Can be used to select the key before unsecuring the frame.
+pub fn security_header(frame: &[u8]) -> Result<AuxiliarySecurityHeader, Error> {
+    let frame_control = frame_control(frame)?;
+    if frame_control & SECURITY_ENABLED == 0 {
+        return Err(Error::InvalidFrame);
+    }
+    let offset = header_length(frame_control)?;
+    if frame.len() < offset {
+        return Err(Error::InvalidFrame);
+    }
+    AuxiliarySecurityHeader::decode(&frame[offset..])
+}
+
+/// Encode and secure `frame` into `buffer`
+///
+/// The frame is encoded with the auxiliary security header `security`, the
+/// payload is encrypted and the MIC is appended as the security level
+/// requires. `source` is the extended address of this device. Only data and
+/// MAC command frames can be secured, security level `None` is rejected as
+/// the frame would claim to be secured without any protection.
+///
+/// Returns the length of the secured frame.
+pub fn secure_frame<B: CcmStarInPlace>(
+    backend: &mut B,
+    key: &[u8],
+    source: ExtendedAddress,
+    frame: &Frame,
+    security: &AuxiliarySecurityHeader,
+    buffer: &mut [u8],
+) -> Result<usize, Error> {
+    let mut unsecured = [0u8; MAX_FRAME_SIZE];
+    let length = frame.encode(&mut unsecured, WriteFooter::No);
+    let result = secure_encoded_frame(backend, key, source, &unsecured[..length], security, buffer);
+    clear(&mut unsecured);
+    result
+}
+
+/// Secure the encoded frame `frame` into `buffer`
+///
+/// As [`secure_frame`], for a frame that already is encoded, without the FCS.
+///
+/// Returns the length of the secured frame.
+pub fn secure_encoded_frame<B: CcmStarInPlace>(
+    backend: &mut B,
+    key: &[u8],
+    source: ExtendedAddress,
+    frame: &[u8],
+    security: &AuxiliarySecurityHeader,
+    buffer: &mut [u8],
+) -> Result<usize, Error> {
+    if security.level == SecurityLevel::None {
+        return Err(Error::InvalidFrame);
+    }
+    let mut frame_control = frame_control(frame)?;
+    let header_length = header_length(frame_control)?;
+    let payload_start = header_length + content_length(frame_control)?;
+    if frame.len() < payload_start {
+        return Err(Error::InvalidFrame);
+    }
+    let payload_length = frame.len() - payload_start;
+    let mic_length = security.level.mic_length();
+    let secured_length = frame.len() + security.length() + mic_length;
+    if secured_length > MAX_FRAME_SIZE || buffer.len() < secured_length {
+        return Err(Error::InvalidFrame);
+    }
+
+    frame_control |= SECURITY_ENABLED;
+    if frame_control & FRAME_VERSION_MASK == 0 {
+        frame_control |= FRAME_VERSION_2006;
+    }
+
+    // Header, auxiliary security header, content and payload
+    buffer[..header_length].copy_from_slice(&frame[..header_length]);
+    write_frame_control(buffer, frame_control);
+    let mut offset = header_length;
+    offset += security.encode(&mut buffer[offset..]);
+    buffer[offset..offset + frame.len() - header_length].copy_from_slice(&frame[header_length..]);
+    offset += payload_start - header_length;
+
+    let nonce = make_nonce(source, security.frame_counter, security.level);
+    if security.level.encrypted() {
+        let (open, private) = buffer.split_at_mut(offset);
+        let (payload, mic) = private.split_at_mut(payload_length);
+        backend.ccmstar_encrypt_in_place(key, &nonce, payload, &mut mic[..mic_length], open)?;
+    } else {
+        let (aad, mic) = buffer.split_at_mut(offset + payload_length);
+        backend.ccmstar_encrypt_in_place(key, &nonce, &mut [], &mut mic[..mic_length], aad)?;
+    }
+    Ok(secured_length)
+}
+
+/// Unsecure the frame in `buffer`
+///
+/// The MIC is validated and the payload decrypted as the security level in
+/// the auxiliary security header requires. `source` is the extended address
+/// of the originator of the frame. The frame is then rewritten as an
+/// unsecured frame, without the auxiliary security header and MIC, and
+/// decoded.
+///
+/// A frame with the security enabled bit set and security level `None` is
+/// rejected with `Error::InvalidFrame`, as the UNSUPPORTED_SECURITY status of
+/// the standard. There is nothing to authenticate in such a frame.
+///
+/// Returns the auxiliary security header, for frame counter and key checks,
+/// and the decoded frame.
+pub fn unsecure_frame<'a, B: CcmStarInPlace>(
+    backend: &mut B,
+    key: &[u8],
+    source: ExtendedAddress,
+    buffer: &'a mut [u8],
+) -> Result<(AuxiliarySecurityHeader, Frame<'a>), Error> {
+    let (security, unsecured_length) = unsecure_frame_in_place(backend, key, source, buffer)?;
+    let buffer: &'a [u8] = buffer;
+    let frame =
+        Frame::decode(&buffer[..unsecured_length], false).map_err(|_| Error::InvalidFrame)?;
+    Ok((security, frame))
+}
+
+/// Unsecure the frame in `buffer` without decoding it
+///
+/// As [`unsecure_frame`], the unsecured frame is left encoded at the start of
+/// `buffer`.
+///
+/// Returns the auxiliary security header and the length of the unsecured
+/// frame.
+pub fn unsecure_frame_in_place<B: CcmStarInPlace>(
+    backend: &mut B,
+    key: &[u8],
+    source: ExtendedAddress,
+    buffer: &mut [u8],
+) -> Result<(AuxiliarySecurityHeader, usize), Error> {
+    let security = security_header(buffer)?;
+    if security.level == SecurityLevel::None {
+        return Err(Error::InvalidFrame);
+    }
+    let frame_control = frame_control(buffer)?;
+    let header_length = header_length(frame_control)?;
+    let open_length = header_length + security.length() + content_length(frame_control)?;
+    let mic_length = security.level.mic_length();
+    if buffer.len() < open_length + mic_length {
+        return Err(Error::InvalidFrame);
+    }
+    let payload_length = buffer.len() - open_length - mic_length;
+    let nonce = make_nonce(source, security.frame_counter, security.level);
+
+    let (open, private) = buffer.split_at_mut(open_length);
+    let (payload, mic) = private.split_at_mut(payload_length);
+    if security.level.encrypted() && !payload.is_empty() {
+        if !backend.ccmstar_decrypt_in_place(key, &nonce, payload, mic, open)? {
+            return Err(Error::InvalidMic);
+        }
+    } else if mic_length > 0 {
+        // Nothing to decrypt, validate the MIC by generating it
+        let aad_length = if security.level.encrypted() {
+            open_length
+        } else {
+            open_length + payload_length
+        };
+        let mut tag = [0u8; BLOCK_SIZE];
+        backend.ccmstar_encrypt_in_place(
+            key,
+            &nonce,
+            &mut [],
+            &mut tag[..mic_length],
+            &buffer[..aad_length],
+        )?;
+        let mut difference = 0u8;
+        for (a, b) in tag[..mic_length].iter().zip(buffer[aad_length..].iter()) {
+            difference |= a ^ b;
+        }
+        if difference != 0 {
+            return Err(Error::InvalidMic);
+        }
+    }
+
+    // Remove the auxiliary security header and the MIC
+    let unsecured_length = buffer.len() - security.length() - mic_length;
+    buffer.copy_within(header_length + security.length().., header_length);
+    write_frame_control(buffer, frame_control & !SECURITY_ENABLED);
+    Ok((security, unsecured_length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ccmstar::tests::SoftwareCcmStar;
+
+    /// IEEE 802.15.4-2006 annex C.2 key
+    const KEY: [u8; 16] = [
+        0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcc, 0xcd, 0xce,
+        0xcf,
+    ];
+    /// Extended address of the originator in annex C.2
+    const SOURCE: ExtendedAddress = ExtendedAddress(0xacde_4800_0000_0001);
+    /// Data frame from annex C.2.2 with the payload "abcd", unsecured
+    const UNSECURED: [u8; 25] = [
+        0x61, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x01, 0x00,
+        0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x61, 0x62, 0x63, 0x64,
+    ];
+    /// Annex C.2.2, the data frame secured with security level 4, ENC
+    const SECURED_ENC: [u8; 30] = [
+        0x69, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x01, 0x00,
+        0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x04, 0x05, 0x00, 0x00, 0x00, 0xd4, 0x3e, 0x02, 0x2b,
+    ];
+    /// The data frame secured with security level 5, ENC-MIC-32, key identifier
+    /// mode 1, computed with a separate Python CCM* implementation
+    const SECURED_ENC_MIC_32: [u8; 35] = [
+        0x69, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x01, 0x00,
+        0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x0d, 0x06, 0x00, 0x00, 0x00, 0x01, 0x53, 0xf9, 0x0a,
+        0xcc, 0x36, 0xc8, 0x4e, 0x3d,
+    ];
+    /// The data frame secured with security level 2, MIC-64, key identifier
+    /// mode 2, computed with a separate Python CCM* implementation
+    const SECURED_MIC_64: [u8; 43] = [
+        0x69, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x01, 0x00,
+        0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x12, 0x07, 0x00, 0x00, 0x00, 0x11, 0x22, 0x33, 0x44,
+        0x02, 0x61, 0x62, 0x63, 0x64, 0x11, 0x73, 0xaf, 0x4f, 0xf4, 0x36, 0xd4, 0x93,
+    ];
+    /// The data frame secured with security level 7, ENC-MIC-128, key
+    /// identifier mode 3, computed with a separate Python CCM* implementation
+    const SECURED_ENC_MIC_128: [u8; 55] = [
+        0x69, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x01, 0x00,
+        0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x1f, 0x08, 0x00, 0x00, 0x00, 0x01, 0x02, 0x03, 0x04,
+        0x05, 0x06, 0x07, 0x08, 0x03, 0xc3, 0x59, 0xdc, 0xa3, 0x27, 0x16, 0xa1, 0x14, 0x83, 0xfa,
+        0x6d, 0xca, 0xdf, 0x7b, 0x1a, 0xdb, 0x1e, 0xe5, 0x67, 0x62,
+    ];
+    /// Annex C.2.1, beacon frame up to the beacon payload, security level 2
+    const BEACON: [u8; 26] = [
+        0x08, 0xd0, 0x84, 0x21, 0x43, 0x01, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x02, 0x05,
+        0x00, 0x00, 0x00, 0x55, 0xcf, 0x00, 0x00, 0x51, 0x52, 0x53, 0x54,
+    ];
+
+    fn vectors() -> [(AuxiliarySecurityHeader, &'static [u8]); 4] {
+        [
+            (
+                AuxiliarySecurityHeader {
+                    level: SecurityLevel::Enc,
+                    key_identifier: KeyIdentifier::Implicit,
+                    frame_counter: 5,
+                },
+                &SECURED_ENC,
+            ),
+            (
+                AuxiliarySecurityHeader {
+                    level: SecurityLevel::EncMic32,
+                    key_identifier: KeyIdentifier::Index(1),
+                    frame_counter: 6,
+                },
+                &SECURED_ENC_MIC_32,
+            ),
+            (
+                AuxiliarySecurityHeader {
+                    level: SecurityLevel::Mic64,
+                    key_identifier: KeyIdentifier::Source4([0x11, 0x22, 0x33, 0x44], 2),
+                    frame_counter: 7,
+                },
+                &SECURED_MIC_64,
+            ),
+            (
+                AuxiliarySecurityHeader {
+                    level: SecurityLevel::EncMic128,
+                    key_identifier: KeyIdentifier::Source8([1, 2, 3, 4, 5, 6, 7, 8], 3),
+                    frame_counter: 8,
+                },
+                &SECURED_ENC_MIC_128,
+            ),
+        ]
+    }
+
+    #[test]
+    fn security_levels() {
+        let levels = [
+            (SecurityLevel::None, 0, false),
+            (SecurityLevel::Mic32, 4, false),
+            (SecurityLevel::Mic64, 8, false),
+            (SecurityLevel::Mic128, 16, false),
+            (SecurityLevel::Enc, 0, true),
+            (SecurityLevel::EncMic32, 4, true),
+            (SecurityLevel::EncMic64, 8, true),
+            (SecurityLevel::EncMic128, 16, true),
+        ];
+        for &(level, mic_length, encrypted) in levels.iter() {
+            assert_eq!(SecurityLevel::from_bits(level as u8), level);
+            assert_eq!(level.mic_length(), mic_length);
+            assert_eq!(level.encrypted(), encrypted);
+        }
+    }
+
+    #[test]
+    fn header_round_trip() {
+        for (security, secured) in vectors().iter() {
+            let encoded = &secured[21..21 + security.length()];
+            assert_eq!(AuxiliarySecurityHeader::decode(encoded), Ok(*security));
+            let mut buffer = [0u8; 14];
+            let length = security.encode(&mut buffer);
+            assert_eq!(&buffer[..length], encoded);
+        }
+    }
+
+    #[test]
+    fn header_length() {
+        let header = |key_identifier| AuxiliarySecurityHeader {
+            level: SecurityLevel::EncMic32,
+            key_identifier,
+            frame_counter: 0,
+        };
+        assert_eq!(header(KeyIdentifier::Implicit).length(), 5);
+        assert_eq!(header(KeyIdentifier::Index(1)).length(), 6);
+        assert_eq!(header(KeyIdentifier::Source4([0; 4], 1)).length(), 10);
+        assert_eq!(header(KeyIdentifier::Source8([0; 8], 1)).length(), 14);
+    }
+
+    #[test]
+    fn header_truncated() {
+        assert_eq!(
+            AuxiliarySecurityHeader::decode(&[0x05, 0x01, 0x00, 0x00]),
+            Err(Error::InvalidFrame)
+        );
+        // Key identifier mode 1 without the key index
+        assert_eq!(
+            AuxiliarySecurityHeader::decode(&[0x0d, 0x01, 0x00, 0x00, 0x00]),
+            Err(Error::InvalidFrame)
+        );
+        // Key identifier mode 3 with a short key source
+        assert_eq!(
+            AuxiliarySecurityHeader::decode(&SECURED_ENC_MIC_128[21..29]),
+            Err(Error::InvalidFrame)
+        );
+    }
+
+    #[test]
+    fn nonce() {
+        // Annex C.2.2
+        assert_eq!(
+            make_nonce(SOURCE, 5, SecurityLevel::Enc),
+            [0xac, 0xde, 0x48, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x04]
+        );
+    }
+
+    #[test]
+    fn beacon_security_header() {
+        assert_eq!(
+            security_header(&BEACON),
+            Ok(AuxiliarySecurityHeader {
+                level: SecurityLevel::Mic64,
+                key_identifier: KeyIdentifier::Implicit,
+                frame_counter: 5,
+            })
+        );
+        // Not secured
+        assert_eq!(security_header(&UNSECURED), Err(Error::InvalidFrame));
+    }
+
+    #[test]
+    fn secure() {
+        for (security, secured) in vectors().iter() {
+            let mut buffer = [0u8; MAX_FRAME_SIZE];
+            let length = secure_encoded_frame(
+                &mut SoftwareCcmStar,
+                &KEY,
+                SOURCE,
+                &UNSECURED,
+                security,
+                &mut buffer,
+            )
+            .unwrap();
+            assert_eq!(&buffer[..length], *secured);
+        }
+    }
+
+    #[test]
+    fn unsecure() {
+        for (security, secured) in vectors().iter() {
+            let mut buffer = [0u8; MAX_FRAME_SIZE];
+            buffer[..secured.len()].copy_from_slice(secured);
+            let result = unsecure_frame_in_place(
+                &mut SoftwareCcmStar,
+                &KEY,
+                SOURCE,
+                &mut buffer[..secured.len()],
+            );
+            assert_eq!(result, Ok((*security, UNSECURED.len())));
+            assert_eq!(&buffer[..UNSECURED.len()], &UNSECURED[..]);
+        }
+    }
+
+    #[test]
+    fn unsecure_tampered() {
+        for (_, secured) in vectors().iter().skip(1) {
+            // Header, auxiliary security header, payload and MIC
+            for &position in [2, 22, secured.len() - 5, secured.len() - 1].iter() {
+                let mut buffer = [0u8; MAX_FRAME_SIZE];
+                buffer[..secured.len()].copy_from_slice(secured);
+                buffer[position] ^= 0x01;
+                let result = unsecure_frame_in_place(
+                    &mut SoftwareCcmStar,
+                    &KEY,
+                    SOURCE,
+                    &mut buffer[..secured.len()],
+                );
+                assert_eq!(result, Err(Error::InvalidMic));
+            }
+            // Wrong originator
+            let mut buffer = [0u8; MAX_FRAME_SIZE];
+            buffer[..secured.len()].copy_from_slice(secured);
+            let result = unsecure_frame_in_place(
+                &mut SoftwareCcmStar,
+                &KEY,
+                ExtendedAddress(0xacde_4800_0000_0002),
+                &mut buffer[..secured.len()],
+            );
+            assert_eq!(result, Err(Error::InvalidMic));
+        }
+    }
+
+    #[test]
+    fn secure_level_none() {
+        let security = AuxiliarySecurityHeader {
+            level: SecurityLevel::None,
+            key_identifier: KeyIdentifier::Implicit,
+            frame_counter: 5,
+        };
+        let mut buffer = [0u8; MAX_FRAME_SIZE];
+        let result = secure_encoded_frame(
+            &mut SoftwareCcmStar,
+            &KEY,
+            SOURCE,
+            &UNSECURED,
+            &security,
+            &mut buffer,
+        );
+        assert_eq!(result, Err(Error::InvalidFrame));
+    }
+
+    #[test]
+    fn unsecure_level_none() {
+        // Security enabled with security level 0, nothing to authenticate
+        let mut buffer = [0u8; MAX_FRAME_SIZE];
+        buffer[..SECURED_ENC.len()].copy_from_slice(&SECURED_ENC);
+        buffer[21] = 0x00;
+        let result = unsecure_frame_in_place(
+            &mut SoftwareCcmStar,
+            &KEY,
+            SOURCE,
+            &mut buffer[..SECURED_ENC.len()],
+        );
+        assert_eq!(result, Err(Error::InvalidFrame));
+        // The frame is left as it was
+        assert_eq!(&buffer[..2], &SECURED_ENC[..2]);
+    }
+
+    #[test]
+    fn unsecure_truncated() {
+        let mut buffer = [0u8; MAX_FRAME_SIZE];
+        let length = 23;
+        buffer[..length].copy_from_slice(&SECURED_MIC_64[..length]);
+        let result =
+            unsecure_frame_in_place(&mut SoftwareCcmStar, &KEY, SOURCE, &mut buffer[..length]);
+        assert_eq!(result, Err(Error::InvalidFrame));
+    }
+}
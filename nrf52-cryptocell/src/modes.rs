@@ -0,0 +1,352 @@
+//! Block cipher modes
+//!
+//! One-shot AES encryption and decryption in CBC, CBC-CTS, OFB and XTS mode
+//! for input of any length the mode accepts. Each call uses a new context
+//! initialised with the key and IV, so the mode types can be reused for any
+//! number of messages.
+//!
+//! PKCS7 padding is done in software, the CryptoCell only processes whole
+//! blocks. All functions return the number of bytes written to the output.
+
+use crate::{
+    clear, error::Redacted, padding::unpad, AesContext, AesOperationMode, BlockCipher as _,
+    CryptoCell, EncryptDecrypt, Error, PaddingType, BLOCK_SIZE, KEY_SIZE,
+};
+
+/// Size of the IV, or tweak for XTS
+pub const IV_SIZE: usize = BLOCK_SIZE;
+
+/// Round `length` up to whole blocks
+const fn block_length(length: usize) -> usize {
+    ((length + (BLOCK_SIZE - 1)) / BLOCK_SIZE) * BLOCK_SIZE
+}
+
+/// Create a context for `mode` with key and IV set
+fn context<'a>(
+    cryptocell: &'a CryptoCell,
+    direction: EncryptDecrypt,
+    mode: AesOperationMode,
+    key: &[u8],
+    iv: &[u8],
+) -> Result<AesContext<'a>, Error> {
+    let mut context = AesContext::new(cryptocell, direction, mode, PaddingType::None)?;
+    context.set_user_key(key)?;
+    context.set_iv(iv)?;
+    Ok(context)
+}
+
+/// Process `input`, the last `tail` bytes are passed to finish
+fn run(
+    context: &mut AesContext,
+    input: &[u8],
+    output: &mut [u8],
+    tail: usize,
+) -> Result<usize, Error> {
+    assert!(output.len() >= input.len());
+    let split = input.len() - tail;
+    if split > 0 {
+        context.process_block(&input[..split], &mut output[..split])?;
+    }
+    let length = context.finish_with_length(&input[split..], &mut output[split..])?;
+    Ok(split + length)
+}
+
+/// Number of bytes to pass to finish, the last block including a partial block
+fn last_block(length: usize) -> usize {
+    match length % BLOCK_SIZE {
+        0 => length.min(BLOCK_SIZE),
+        partial => partial,
+    }
+}
+
+/// AES-CBC with or without PKCS7 padding
+pub struct Cbc<'a> {
+    cryptocell: &'a CryptoCell,
+    key: [u8; KEY_SIZE],
+    padding: PaddingType,
+}
+
+impl<'a> Cbc<'a> {
+    pub fn new(cryptocell: &'a CryptoCell, key: &[u8], padding: PaddingType) -> Self {
+        assert!(key.len() == KEY_SIZE);
+        let mut cbc = Self {
+            cryptocell,
+            key: [0u8; KEY_SIZE],
+            padding,
+        };
+        cbc.key.copy_from_slice(key);
+        cbc
+    }
+
+    /// Size of the encrypted output for `length` bytes of input
+    ///
+    /// Without padding the input must be whole blocks. With PKCS7 padding a
+    /// full block of padding is added to input that is whole blocks.
+    pub fn encrypted_length(&self, length: usize) -> usize {
+        match self.padding {
+            PaddingType::None => length,
+            PaddingType::Pkcs7 => block_length(length + 1),
+        }
+    }
+
+    /// Encrypt `input` into `output`
+    pub fn encrypt(&self, iv: &[u8], input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        assert!(output.len() >= self.encrypted_length(input.len()));
+        let mut context = context(
+            self.cryptocell,
+            EncryptDecrypt::Encrypt,
+            AesOperationMode::Cbc,
+            &self.key,
+            iv,
+        )?;
+        match self.padding {
+            PaddingType::None => {
+                assert!(input.len() % BLOCK_SIZE == 0);
+                run(&mut context, input, output, last_block(input.len()))
+            }
+            PaddingType::Pkcs7 => {
+                let full = (input.len() / BLOCK_SIZE) * BLOCK_SIZE;
+                let partial = input.len() - full;
+                let mut block = [(BLOCK_SIZE - partial) as u8; BLOCK_SIZE];
+                block[..partial].copy_from_slice(&input[full..]);
+                if full > 0 {
+                    context.process_block(&input[..full], &mut output[..full])?;
+                }
+                let length =
+                    context.finish_with_length(&block, &mut output[full..full + BLOCK_SIZE])?;
+                clear(&mut block);
+                Ok(full + length)
+            }
+        }
+    }
+
+    /// Decrypt `input` into `output`
+    ///
+    /// With PKCS7 padding the padding is validated and removed, the output
+    /// must still fit the padded data.
+    pub fn decrypt(&self, iv: &[u8], input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        assert!(input.len() % BLOCK_SIZE == 0);
+        let mut context = context(
+            self.cryptocell,
+            EncryptDecrypt::Decrypt,
+            AesOperationMode::Cbc,
+            &self.key,
+            iv,
+        )?;
+        let length = run(&mut context, input, output, last_block(input.len()))?;
+        match self.padding {
+            PaddingType::None => Ok(length),
+            PaddingType::Pkcs7 => match unpad(&output[..length]) {
+                Some(length) => Ok(length),
+                None => {
+                    clear(&mut output[..length]);
+                    Err(Error::InvalidPadding)
+                }
+            },
+        }
+    }
+}
+
+impl<'a> core::fmt::Debug for Cbc<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Cbc")
+            .field("key", &Redacted)
+            .field("padding", &self.padding)
+            .finish()
+    }
+}
+
+impl<'a> core::ops::Drop for Cbc<'a> {
+    fn drop(&mut self) {
+        clear(&mut self.key);
+    }
+}
+
+/// AES-CBC with ciphertext stealing
+///
+/// Input of any length of at least one block is accepted, the output has the
+/// same length as the input.
+pub struct CbcCts<'a> {
+    cryptocell: &'a CryptoCell,
+    key: [u8; KEY_SIZE],
+}
+
+impl<'a> CbcCts<'a> {
+    pub fn new(cryptocell: &'a CryptoCell, key: &[u8]) -> Self {
+        assert!(key.len() == KEY_SIZE);
+        let mut cts = Self {
+            cryptocell,
+            key: [0u8; KEY_SIZE],
+        };
+        cts.key.copy_from_slice(key);
+        cts
+    }
+
+    fn process(
+        &self,
+        direction: EncryptDecrypt,
+        iv: &[u8],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<usize, Error> {
+        assert!(input.len() >= BLOCK_SIZE);
+        let mut context = context(
+            self.cryptocell,
+            direction,
+            AesOperationMode::CbcCts,
+            &self.key,
+            iv,
+        )?;
+        // The last two blocks are passed to finish, where the stealing happens
+        let tail = if input.len() > BLOCK_SIZE {
+            last_block(input.len()) + BLOCK_SIZE
+        } else {
+            input.len()
+        };
+        run(&mut context, input, output, tail)
+    }
+
+    /// Encrypt `input` into `output`
+    pub fn encrypt(&self, iv: &[u8], input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        self.process(EncryptDecrypt::Encrypt, iv, input, output)
+    }
+
+    /// Decrypt `input` into `output`
+    pub fn decrypt(&self, iv: &[u8], input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        self.process(EncryptDecrypt::Decrypt, iv, input, output)
+    }
+}
+
+impl<'a> core::fmt::Debug for CbcCts<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("CbcCts").field("key", &Redacted).finish()
+    }
+}
+
+impl<'a> core::ops::Drop for CbcCts<'a> {
+    fn drop(&mut self) {
+        clear(&mut self.key);
+    }
+}
+
+/// AES-OFB
+///
+/// Input of any length is accepted, the output has the same length as the
+/// input. Encryption and decryption are the same operation.
+pub struct Ofb<'a> {
+    cryptocell: &'a CryptoCell,
+    key: [u8; KEY_SIZE],
+}
+
+impl<'a> Ofb<'a> {
+    pub fn new(cryptocell: &'a CryptoCell, key: &[u8]) -> Self {
+        assert!(key.len() == KEY_SIZE);
+        let mut ofb = Self {
+            cryptocell,
+            key: [0u8; KEY_SIZE],
+        };
+        ofb.key.copy_from_slice(key);
+        ofb
+    }
+
+    fn process(&self, iv: &[u8], input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        let mut context = context(
+            self.cryptocell,
+            EncryptDecrypt::Encrypt,
+            AesOperationMode::Ofb,
+            &self.key,
+            iv,
+        )?;
+        run(&mut context, input, output, last_block(input.len()))
+    }
+
+    /// Encrypt `input` into `output`
+    pub fn encrypt(&self, iv: &[u8], input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        self.process(iv, input, output)
+    }
+
+    /// Decrypt `input` into `output`
+    pub fn decrypt(&self, iv: &[u8], input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        self.process(iv, input, output)
+    }
+}
+
+impl<'a> core::fmt::Debug for Ofb<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Ofb").field("key", &Redacted).finish()
+    }
+}
+
+impl<'a> core::ops::Drop for Ofb<'a> {
+    fn drop(&mut self) {
+        clear(&mut self.key);
+    }
+}
+
+/// XTS-AES, as used for storage encryption
+///
+/// The key is the data key followed by the tweak key, 32 bytes for XTS-AES-128
+/// and 64 bytes for XTS-AES-256. The tweak is the data unit sequence number as
+/// 16 little-endian bytes. A data unit of any length of at least one block is
+/// accepted and processed in one operation.
+pub struct Xts<'a> {
+    cryptocell: &'a CryptoCell,
+    key: [u8; 2 * 2 * KEY_SIZE],
+    key_length: usize,
+}
+
+impl<'a> Xts<'a> {
+    pub fn new(cryptocell: &'a CryptoCell, key: &[u8]) -> Self {
+        assert!(key.len() == 2 * KEY_SIZE || key.len() == 2 * 2 * KEY_SIZE);
+        let mut xts = Self {
+            cryptocell,
+            key: [0u8; 2 * 2 * KEY_SIZE],
+            key_length: key.len(),
+        };
+        xts.key[..key.len()].copy_from_slice(key);
+        xts
+    }
+
+    fn process(
+        &self,
+        direction: EncryptDecrypt,
+        tweak: &[u8],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<usize, Error> {
+        assert!(input.len() >= BLOCK_SIZE);
+        let mut context = context(
+            self.cryptocell,
+            direction,
+            AesOperationMode::Xts,
+            &self.key[..self.key_length],
+            tweak,
+        )?;
+        run(&mut context, input, output, input.len())
+    }
+
+    /// Encrypt the data unit `input` into `output`
+    pub fn encrypt(&self, tweak: &[u8], input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        self.process(EncryptDecrypt::Encrypt, tweak, input, output)
+    }
+
+    /// Decrypt the data unit `input` into `output`
+    pub fn decrypt(&self, tweak: &[u8], input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        self.process(EncryptDecrypt::Decrypt, tweak, input, output)
+    }
+}
+
+impl<'a> core::fmt::Debug for Xts<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Xts")
+            .field("key", &Redacted)
+            .field("key_length", &self.key_length)
+            .finish()
+    }
+}
+
+impl<'a> core::ops::Drop for Xts<'a> {
+    fn drop(&mut self) {
+        clear(&mut self.key);
+    }
+}
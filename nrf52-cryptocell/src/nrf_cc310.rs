@@ -0,0 +1,422 @@
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+
+use cty;
+/* automatically generated by rust-bindgen 0.58.1 */
+
+pub const SASI_AES_USER_CTX_SIZE_IN_WORDS: u32 = 19;
+pub const SASI_AES_BLOCK_SIZE_IN_WORDS: u32 = 4;
+pub const SASI_AES_BLOCK_SIZE_IN_BYTES: u32 = 16;
+pub const SASI_AES_IV_SIZE_IN_WORDS: u32 = 4;
+pub const SASI_AES_IV_SIZE_IN_BYTES: u32 = 16;
+pub const SASI_AES_KEY_MAX_SIZE_IN_WORDS: u32 = 16;
+pub const SASI_AES_KEY_MAX_SIZE_IN_BYTES: u32 = 64;
+pub const SASI_AES_MODULE_ERROR_BASE: u32 = 15739136;
+pub const SASI_AES_INVALID_USER_CONTEXT_POINTER_ERROR: u32 = 15739136;
+pub const SASI_AES_INVALID_IV_OR_TWEAK_PTR_ERROR: u32 = 15739137;
+pub const SASI_AES_ILLEGAL_OPERATION_MODE_ERROR: u32 = 15739138;
+pub const SASI_AES_ILLEGAL_KEY_SIZE_ERROR: u32 = 15739139;
+pub const SASI_AES_INVALID_KEY_POINTER_ERROR: u32 = 15739140;
+pub const SASI_AES_KEY_TYPE_NOT_SUPPORTED_ERROR: u32 = 15739141;
+pub const SASI_AES_INVALID_ENCRYPT_MODE_ERROR: u32 = 15739142;
+pub const SASI_AES_USER_CONTEXT_CORRUPTED_ERROR: u32 = 15739143;
+pub const SASI_AES_DATA_IN_POINTER_INVALID_ERROR: u32 = 15739144;
+pub const SASI_AES_DATA_OUT_POINTER_INVALID_ERROR: u32 = 15739145;
+pub const SASI_AES_DATA_IN_SIZE_ILLEGAL: u32 = 15739146;
+pub const SASI_AES_DATA_OUT_DATA_IN_OVERLAP_ERROR: u32 = 15739147;
+pub const SASI_AES_DATA_OUT_SIZE_POINTER_INVALID_ERROR: u32 = 15739150;
+pub const SASI_AES_CTX_SIZES_ERROR: u32 = 15739151;
+pub const SASI_AES_ILLEGAL_PARAMS_ERROR: u32 = 15739152;
+pub const SASI_AES_CTR_ILLEGAL_BLOCK_OFFSET_ERROR: u32 = 15739153;
+pub const SASI_AES_CTR_ILLEGAL_COUNTER_ERROR: u32 = 15739154;
+pub const SASI_AES_NOT_ALLOWED_KEY_TYPE_ON_THIS_PLATFORM: u32 = 15739155;
+pub const SASI_AES_INCORRECT_PADDING_ERROR: u32 = 15739156;
+pub const SASI_AES_CORRUPTED_OUTPUT_ERROR: u32 = 15739157;
+pub const SASI_AES_IS_NOT_SUPPORTED: u32 = 15739391;
+pub type SaSiError_t = u32;
+pub type SaSiUtilError_t = u32;
+pub const SA_SilibRetCode_t_SA_SILIB_RET_OK: SA_SilibRetCode_t = 0;
+pub const SA_SilibRetCode_t_SA_SILIB_RET_EINVAL_CTX_PTR: SA_SilibRetCode_t = 1;
+pub const SA_SilibRetCode_t_SA_SILIB_RET_EINVAL_WORK_BUF_PTR: SA_SilibRetCode_t = 2;
+pub const SA_SilibRetCode_t_SA_SILIB_RET_HAL: SA_SilibRetCode_t = 3;
+pub const SA_SilibRetCode_t_SA_SILIB_RET_PAL: SA_SilibRetCode_t = 4;
+pub const SA_SilibRetCode_t_SA_SILIB_RET_EINVAL_HW_VERSION: SA_SilibRetCode_t = 5;
+pub const SA_SilibRetCode_t_SA_SILIB_RET_EINVAL_HW_SIGNATURE: SA_SilibRetCode_t = 6;
+pub const SA_SilibRetCode_t_SA_SILIB_RESERVE32B: SA_SilibRetCode_t = 2147483647;
+pub type SA_SilibRetCode_t = cty::c_uint;
+extern "C" {
+    pub fn SaSi_LibInit() -> SA_SilibRetCode_t;
+}
+extern "C" {
+    pub fn SaSi_LibFini();
+}
+pub const SaSiAesEncryptMode_t_SASI_AES_ENCRYPT: SaSiAesEncryptMode_t = 0;
+pub const SaSiAesEncryptMode_t_SASI_AES_DECRYPT: SaSiAesEncryptMode_t = 1;
+pub const SaSiAesEncryptMode_t_SASI_AES_NUM_OF_ENCRYPT_MODES: SaSiAesEncryptMode_t = 2;
+pub const SaSiAesEncryptMode_t_SASI_AES_ENCRYPT_MODE_LAST: SaSiAesEncryptMode_t = 2147483647;
+pub type SaSiAesEncryptMode_t = cty::c_uint;
+pub const SaSiAesOperationMode_t_SASI_AES_MODE_ECB: SaSiAesOperationMode_t = 0;
+pub const SaSiAesOperationMode_t_SASI_AES_MODE_CBC: SaSiAesOperationMode_t = 1;
+pub const SaSiAesOperationMode_t_SASI_AES_MODE_CBC_MAC: SaSiAesOperationMode_t = 2;
+pub const SaSiAesOperationMode_t_SASI_AES_MODE_CTR: SaSiAesOperationMode_t = 3;
+pub const SaSiAesOperationMode_t_SASI_AES_MODE_XCBC_MAC: SaSiAesOperationMode_t = 4;
+pub const SaSiAesOperationMode_t_SASI_AES_MODE_CMAC: SaSiAesOperationMode_t = 5;
+pub const SaSiAesOperationMode_t_SASI_AES_MODE_XTS: SaSiAesOperationMode_t = 6;
+pub const SaSiAesOperationMode_t_SASI_AES_MODE_CBC_CTS: SaSiAesOperationMode_t = 7;
+pub const SaSiAesOperationMode_t_SASI_AES_MODE_OFB: SaSiAesOperationMode_t = 8;
+pub const SaSiAesOperationMode_t_SASI_AES_NUM_OF_OPERATION_MODES: SaSiAesOperationMode_t = 9;
+pub const SaSiAesOperationMode_t_SASI_AES_OPERATION_MODE_LAST: SaSiAesOperationMode_t = 2147483647;
+pub type SaSiAesOperationMode_t = cty::c_uint;
+pub const SaSiAesPaddingType_t_SASI_AES_PADDING_NONE: SaSiAesPaddingType_t = 0;
+pub const SaSiAesPaddingType_t_SASI_AES_PADDING_PKCS7: SaSiAesPaddingType_t = 1;
+pub const SaSiAesPaddingType_t_SASI_AES_NUM_OF_PADDING_TYPES: SaSiAesPaddingType_t = 2;
+pub const SaSiAesPaddingType_t_SASI_AES_PADDING_TYPE_LAST: SaSiAesPaddingType_t = 2147483647;
+pub type SaSiAesPaddingType_t = cty::c_uint;
+pub const SaSiAesKeyType_t_SASI_AES_USER_KEY: SaSiAesKeyType_t = 0;
+pub const SaSiAesKeyType_t_SASI_AES_PLATFORM_KEY: SaSiAesKeyType_t = 1;
+pub const SaSiAesKeyType_t_SASI_AES_CUSTOMER_KEY: SaSiAesKeyType_t = 2;
+pub const SaSiAesKeyType_t_SASI_AES_NUM_OF_KEY_TYPES: SaSiAesKeyType_t = 3;
+pub const SaSiAesKeyType_t_SASI_AES_KEY_TYPE_LAST: SaSiAesKeyType_t = 2147483647;
+pub type SaSiAesKeyType_t = cty::c_uint;
+pub type SaSiAesIv_t = [u8; 16usize];
+pub type SaSiAesKeyBuffer_t = [u8; 64usize];
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct SaSiAesUserContext_t {
+    pub buff: [u32; 19usize],
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct SaSiAesUserKeyData_t {
+    pub pKey: *mut u8,
+    pub keySize: usize,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct SaSiAesHwKeyData_t {
+    pub slotNumber: usize,
+}
+extern "C" {
+    pub fn SaSi_AesInit(
+        pContext: *mut SaSiAesUserContext_t,
+        encryptDecryptFlag: SaSiAesEncryptMode_t,
+        operationMode: SaSiAesOperationMode_t,
+        paddingType: SaSiAesPaddingType_t,
+    ) -> SaSiError_t;
+}
+extern "C" {
+    pub fn SaSi_AesSetKey(
+        pContext: *mut SaSiAesUserContext_t,
+        keyType: SaSiAesKeyType_t,
+        pKeyData: *mut cty::c_void,
+        keyDataSize: usize,
+    ) -> SaSiError_t;
+}
+extern "C" {
+    pub fn SaSi_AesSetIv(pContext: *mut SaSiAesUserContext_t, pIV: *mut u8) -> SaSiError_t;
+}
+extern "C" {
+    pub fn SaSi_AesGetIv(pContext: *mut SaSiAesUserContext_t, pIV: *mut u8) -> SaSiError_t;
+}
+extern "C" {
+    pub fn SaSi_AesBlock(
+        pContext: *mut SaSiAesUserContext_t,
+        pDataIn: *mut u8,
+        dataInSize: usize,
+        pDataOut: *mut u8,
+    ) -> SaSiError_t;
+}
+extern "C" {
+    pub fn SaSi_AesFinish(
+        pContext: *mut SaSiAesUserContext_t,
+        dataSize: usize,
+        pDataIn: *mut u8,
+        dataInBuffSize: usize,
+        pDataOut: *mut u8,
+        dataOutBuffSize: *mut usize,
+    ) -> SaSiError_t;
+}
+extern "C" {
+    pub fn SaSi_AesFree(pContext: *mut SaSiAesUserContext_t) -> SaSiError_t;
+}
+pub const CRYS_AESCCM_KEY_SIZE_WORDS: u32 = 8;
+pub const CRYS_AESCCM_MODE_CCM: u32 = 0;
+pub const CRYS_AESCCM_MODE_STAR: u32 = 1;
+pub const CRYS_AESCCM_MODULE_ERROR_BASE: u32 = 15734016;
+pub const CRYS_AESCCM_CCM_MAC_INVALID_ERROR: u32 = 15734031;
+pub type CRYS_AESCCM_Key_t = [u8; 32usize];
+pub type CRYS_AESCCM_Mac_Res_t = [u8; 16usize];
+pub const CRYS_AESCCM_KeySize_t_CRYS_AES_Key128BitSize: CRYS_AESCCM_KeySize_t = 0;
+pub const CRYS_AESCCM_KeySize_t_CRYS_AES_Key192BitSize: CRYS_AESCCM_KeySize_t = 1;
+pub const CRYS_AESCCM_KeySize_t_CRYS_AES_Key256BitSize: CRYS_AESCCM_KeySize_t = 2;
+pub const CRYS_AESCCM_KeySize_t_CRYS_AES_Key512BitSize: CRYS_AESCCM_KeySize_t = 3;
+pub const CRYS_AESCCM_KeySize_t_CRYS_AES_KeySizeNumOfOptions: CRYS_AESCCM_KeySize_t = 4;
+pub const CRYS_AESCCM_KeySize_t_CRYS_AES_KeySizeLast: CRYS_AESCCM_KeySize_t = 2147483647;
+pub type CRYS_AESCCM_KeySize_t = cty::c_uint;
+extern "C" {
+    pub fn CC_AESCCM(
+        EncrDecrMode: SaSiAesEncryptMode_t,
+        CCM_Key: *mut u8,
+        KeySizeId: CRYS_AESCCM_KeySize_t,
+        N_ptr: *mut u8,
+        SizeOfN: u8,
+        ADataIn_ptr: *mut u8,
+        ADataInSize: u32,
+        TextDataIn_ptr: *mut u8,
+        TextDataInSize: u32,
+        TextDataOut_ptr: *mut u8,
+        SizeOfT: u8,
+        Mac_Res: *mut u8,
+        ccmMode: u32,
+    ) -> CRYSError_t;
+}
+pub const CRYS_HASH_OperationMode_t_CRYS_HASH_SHA1_mode: CRYS_HASH_OperationMode_t = 0;
+pub const CRYS_HASH_OperationMode_t_CRYS_HASH_SHA224_mode: CRYS_HASH_OperationMode_t = 1;
+pub const CRYS_HASH_OperationMode_t_CRYS_HASH_SHA256_mode: CRYS_HASH_OperationMode_t = 2;
+pub const CRYS_HASH_OperationMode_t_CRYS_HASH_SHA384_mode: CRYS_HASH_OperationMode_t = 3;
+pub const CRYS_HASH_OperationMode_t_CRYS_HASH_SHA512_mode: CRYS_HASH_OperationMode_t = 4;
+pub const CRYS_HASH_OperationMode_t_CRYS_HASH_MD5_mode: CRYS_HASH_OperationMode_t = 5;
+pub const CRYS_HASH_OperationMode_t_CRYS_HASH_NumOfModes: CRYS_HASH_OperationMode_t = 6;
+pub const CRYS_HASH_OperationMode_t_CRYS_HASH_OperationModeLast: CRYS_HASH_OperationMode_t =
+    2147483647;
+pub type CRYS_HASH_OperationMode_t = cty::c_uint;
+pub const SaSiUtilKeyType_t_SASI_UTIL_USER_KEY: SaSiUtilKeyType_t = 0;
+pub const SaSiUtilKeyType_t_SASI_UTIL_ROOT_KEY: SaSiUtilKeyType_t = 1;
+pub const SaSiUtilKeyType_t_SASI_UTIL_TOTAL_KEYS: SaSiUtilKeyType_t = 2;
+pub const SaSiUtilKeyType_t_SASI_UTIL_END_OF_KEY_TYPE: SaSiUtilKeyType_t = 2147483647;
+pub type SaSiUtilKeyType_t = cty::c_uint;
+pub const SaSiUtilPrfType_t_SASI_UTIL_PRF_CMAC: SaSiUtilPrfType_t = 0;
+pub const SaSiUtilPrfType_t_SASI_UTIL_PRF_HMAC: SaSiUtilPrfType_t = 1;
+pub const SaSiUtilPrfType_t_SASI_UTIL_TOTAL_PRFS: SaSiUtilPrfType_t = 2;
+pub const SaSiUtilPrfType_t_SASI_UTIL_END_OF_PRF_TYPE: SaSiUtilPrfType_t = 2147483647;
+pub type SaSiUtilPrfType_t = cty::c_uint;
+extern "C" {
+    pub fn SaSi_UtilKeyDerivation(
+        keyType: SaSiUtilKeyType_t,
+        pUserKey: *mut SaSiAesUserKeyData_t,
+        prfType: SaSiUtilPrfType_t,
+        hashMode: CRYS_HASH_OperationMode_t,
+        pLabel: *const u8,
+        labelSize: usize,
+        pContextData: *const u8,
+        contextSize: usize,
+        pDerivedKey: *mut u8,
+        derivedKeySize: usize,
+    ) -> SaSiUtilError_t;
+}
+pub const CRYS_HASH_RESULT_SIZE_IN_WORDS: u32 = 16;
+pub const CRYS_HASH_USER_CTX_SIZE_IN_WORDS: u32 = 60;
+pub const CRYS_ECEDW_ORD_SIZE_IN_BYTES: u32 = 32;
+pub const CRYS_ECEDW_MOD_SIZE_IN_BYTES: u32 = 32;
+pub const CRYS_ECPKI_MODULE_ERROR_BASE: u32 = 15730688;
+pub const CRYS_ECDSA_VERIFY_INCONSISTENT_VERIFY_ERROR: u32 = 15730819;
+pub const CRYS_EC_MONT_EDW_MODULE_ERROR_BASE: u32 = 15737600;
+pub const CRYS_ECEDW_SIGN_VERIFY_FAILED_ERROR: u32 = 15737605;
+pub const CRYS_RND_MODULE_ERROR_BASE: u32 = 15731712;
+pub const CRYS_RND_RESEED_COUNTER_OVERFLOW_ERROR: u32 = 15731717;
+pub const CRYS_RND_INSTANTIATION_NOT_DONE_ERROR: u32 = 15731718;
+pub const CRYS_RND_TRNG_LOSS_SAMPLES_ERROR: u32 = 15731719;
+pub const CRYS_RND_TRNG_TIME_EXCEED_ERROR: u32 = 15731720;
+pub const CRYS_RND_TRNG_LOSS_SAMPLES_AND_TIME_EXCEED_ERROR: u32 = 15731721;
+pub const CRYS_RND_CPRNG_TEST_FAIL_ERROR: u32 = 15731728;
+pub const CRYS_RND_SEED_MAX_SIZE_WORDS: u32 = 12;
+pub const CRYS_RND_ADDITINAL_INPUT_MAX_SIZE_WORDS: u32 = 12;
+pub const CRYS_RND_MAX_GEN_VECTOR_SIZE_BYTES: u32 = 65535;
+pub const CRYS_RND_WORK_BUFFER_SIZE_WORDS: u32 = 1528;
+pub const CRYS_ECEDW_SIGNATURE_BYTES: u32 = 64;
+pub type CRYSError_t = u32;
+pub type CRYS_HASH_Result_t = [u32; 16usize];
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct CRYS_HASHUserContext_t {
+    pub buff: [u32; 60usize],
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct CRYS_RND_State_t {
+    pub Seed: [u32; 12usize],
+    pub PreviousRandValue: [u32; 4usize],
+    pub PreviousAdditionalInput: [u32; 17usize],
+    pub AdditionalInput: [u32; 16usize],
+    pub AddInputSizeWords: u32,
+    pub EntropySourceSizeWords: u32,
+    pub ReseedCounter: u32,
+    pub KeySizeWords: u32,
+    pub StateFlag: u32,
+    pub TrngProcesState: u32,
+    pub ValidTag: u32,
+    pub EntropySizeBits: u32,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct CRYS_RND_WorkBuff_t {
+    pub crysRndWorkBuff: [u32; 1528usize],
+}
+extern "C" {
+    pub fn CRYS_RndInit(
+        rndState_ptr: *mut cty::c_void,
+        rndWorkBuff_ptr: *mut CRYS_RND_WorkBuff_t,
+    ) -> CRYSError_t;
+}
+extern "C" {
+    pub fn CRYS_RND_Instantiation(
+        rndState_ptr: *mut cty::c_void,
+        rndWorkBuff_ptr: *mut CRYS_RND_WorkBuff_t,
+    ) -> CRYSError_t;
+}
+extern "C" {
+    pub fn CRYS_RND_UnInstantiation(rndState_ptr: *mut cty::c_void) -> CRYSError_t;
+}
+extern "C" {
+    pub fn CRYS_RND_Reseeding(
+        rndState_ptr: *mut cty::c_void,
+        rndWorkBuff_ptr: *mut CRYS_RND_WorkBuff_t,
+    ) -> CRYSError_t;
+}
+extern "C" {
+    pub fn CRYS_RND_GenerateVector(
+        rndState_ptr: *mut cty::c_void,
+        outSizeBytes: u16,
+        out_ptr: *mut u8,
+    ) -> CRYSError_t;
+}
+extern "C" {
+    pub fn CRYS_RND_AddAdditionalInput(
+        rndState_ptr: *mut cty::c_void,
+        additonalInput_ptr: *mut u8,
+        additonalInputSize: u16,
+    ) -> CRYSError_t;
+}
+extern "C" {
+    pub fn CRYS_HASH_Init(
+        ContextID_ptr: *mut CRYS_HASHUserContext_t,
+        OperationMode: CRYS_HASH_OperationMode_t,
+    ) -> CRYSError_t;
+}
+extern "C" {
+    pub fn CRYS_HASH_Update(
+        ContextID_ptr: *mut CRYS_HASHUserContext_t,
+        DataIn_ptr: *mut u8,
+        DataInSize: usize,
+    ) -> CRYSError_t;
+}
+extern "C" {
+    pub fn CRYS_HASH_Finish(
+        ContextID_ptr: *mut CRYS_HASHUserContext_t,
+        HashResultBuff: *mut u32,
+    ) -> CRYSError_t;
+}
+extern "C" {
+    pub fn CRYS_HASH_Free(ContextID_ptr: *mut CRYS_HASHUserContext_t) -> CRYSError_t;
+}
+pub const CRYS_ECPKI_DomainID_t_CRYS_ECPKI_DomainID_secp160k1: CRYS_ECPKI_DomainID_t = 0;
+pub const CRYS_ECPKI_DomainID_t_CRYS_ECPKI_DomainID_secp160r1: CRYS_ECPKI_DomainID_t = 1;
+pub const CRYS_ECPKI_DomainID_t_CRYS_ECPKI_DomainID_secp160r2: CRYS_ECPKI_DomainID_t = 2;
+pub const CRYS_ECPKI_DomainID_t_CRYS_ECPKI_DomainID_secp192k1: CRYS_ECPKI_DomainID_t = 3;
+pub const CRYS_ECPKI_DomainID_t_CRYS_ECPKI_DomainID_secp192r1: CRYS_ECPKI_DomainID_t = 4;
+pub const CRYS_ECPKI_DomainID_t_CRYS_ECPKI_DomainID_secp224k1: CRYS_ECPKI_DomainID_t = 5;
+pub const CRYS_ECPKI_DomainID_t_CRYS_ECPKI_DomainID_secp224r1: CRYS_ECPKI_DomainID_t = 6;
+pub const CRYS_ECPKI_DomainID_t_CRYS_ECPKI_DomainID_secp256k1: CRYS_ECPKI_DomainID_t = 7;
+pub const CRYS_ECPKI_DomainID_t_CRYS_ECPKI_DomainID_secp256r1: CRYS_ECPKI_DomainID_t = 8;
+pub const CRYS_ECPKI_DomainID_t_CRYS_ECPKI_DomainID_secp384r1: CRYS_ECPKI_DomainID_t = 9;
+pub const CRYS_ECPKI_DomainID_t_CRYS_ECPKI_DomainID_secp521r1: CRYS_ECPKI_DomainID_t = 10;
+pub const CRYS_ECPKI_DomainID_t_CRYS_ECPKI_DomainID_Builded: CRYS_ECPKI_DomainID_t = 11;
+pub const CRYS_ECPKI_DomainID_t_CRYS_ECPKI_DomainID_OffMode: CRYS_ECPKI_DomainID_t = 12;
+pub const CRYS_ECPKI_DomainID_t_CRYS_ECPKI_DomainIDLast: CRYS_ECPKI_DomainID_t = 2147483647;
+pub type CRYS_ECPKI_DomainID_t = cty::c_uint;
+pub const CRYS_ECPKI_HASH_OpMode_t_CRYS_ECPKI_HASH_SHA1_mode: CRYS_ECPKI_HASH_OpMode_t = 0;
+pub const CRYS_ECPKI_HASH_OpMode_t_CRYS_ECPKI_HASH_SHA224_mode: CRYS_ECPKI_HASH_OpMode_t = 1;
+pub const CRYS_ECPKI_HASH_OpMode_t_CRYS_ECPKI_HASH_SHA256_mode: CRYS_ECPKI_HASH_OpMode_t = 2;
+pub const CRYS_ECPKI_HASH_OpMode_t_CRYS_ECPKI_HASH_SHA384_mode: CRYS_ECPKI_HASH_OpMode_t = 3;
+pub const CRYS_ECPKI_HASH_OpMode_t_CRYS_ECPKI_HASH_SHA512_mode: CRYS_ECPKI_HASH_OpMode_t = 4;
+pub const CRYS_ECPKI_HASH_OpMode_t_CRYS_ECPKI_AFTER_HASH_SHA1_mode: CRYS_ECPKI_HASH_OpMode_t = 5;
+pub const CRYS_ECPKI_HASH_OpMode_t_CRYS_ECPKI_AFTER_HASH_SHA224_mode: CRYS_ECPKI_HASH_OpMode_t = 6;
+pub const CRYS_ECPKI_HASH_OpMode_t_CRYS_ECPKI_AFTER_HASH_SHA256_mode: CRYS_ECPKI_HASH_OpMode_t = 7;
+pub const CRYS_ECPKI_HASH_OpMode_t_CRYS_ECPKI_AFTER_HASH_SHA384_mode: CRYS_ECPKI_HASH_OpMode_t = 8;
+pub const CRYS_ECPKI_HASH_OpMode_t_CRYS_ECPKI_AFTER_HASH_SHA512_mode: CRYS_ECPKI_HASH_OpMode_t = 9;
+pub const CRYS_ECPKI_HASH_OpMode_t_CRYS_ECPKI_HASH_NumOfModes: CRYS_ECPKI_HASH_OpMode_t = 10;
+pub const CRYS_ECPKI_HASH_OpMode_t_CRYS_ECPKI_HASH_OpModeLast: CRYS_ECPKI_HASH_OpMode_t =
+    2147483647;
+pub type CRYS_ECPKI_HASH_OpMode_t = cty::c_uint;
+pub const EC_PublKeyCheckMode_t_CheckPointersAndSizesOnly: EC_PublKeyCheckMode_t = 0;
+pub const EC_PublKeyCheckMode_t_ECpublKeyPartlyCheck: EC_PublKeyCheckMode_t = 1;
+pub const EC_PublKeyCheckMode_t_ECpublKeyFullCheck: EC_PublKeyCheckMode_t = 2;
+pub const EC_PublKeyCheckMode_t_PublKeyChecingOffMode: EC_PublKeyCheckMode_t = 3;
+pub const EC_PublKeyCheckMode_t_EC_PublKeyCheckModeLast: EC_PublKeyCheckMode_t = 2147483647;
+pub type EC_PublKeyCheckMode_t = cty::c_uint;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct CRYS_ECPKI_Domain_t {
+    pub ecP: [u32; 18usize],
+    pub ecA: [u32; 18usize],
+    pub ecB: [u32; 18usize],
+    pub ecR: [u32; 19usize],
+    pub ecGx: [u32; 18usize],
+    pub ecGy: [u32; 18usize],
+    pub ecH: u32,
+    pub llfBuff: [u32; 25usize],
+    pub modSizeInBits: u32,
+    pub ordSizeInBits: u32,
+    pub barrTagSizeInWords: u32,
+    pub DomainID: CRYS_ECPKI_DomainID_t,
+    pub name: [i8; 20usize],
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct CRYS_ECPKI_UserPublKey_t {
+    pub valid_tag: u32,
+    pub PublKeyDbBuff: [u32; 181usize],
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct CRYS_ECDSA_VerifyUserContext_t {
+    pub context_buff: [u32; 314usize],
+    pub valid_tag: u32,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct CRYS_ECPKI_BUILD_TempData_t {
+    pub crysBuildTmpIntBuff: [u32; 72usize],
+}
+extern "C" {
+    pub fn CRYS_ECPKI_GetEcDomain(domainId: CRYS_ECPKI_DomainID_t) -> *const CRYS_ECPKI_Domain_t;
+}
+extern "C" {
+    pub fn _DX_ECPKI_BuildPublKey(
+        pDomain: *const CRYS_ECPKI_Domain_t,
+        PublKeyIn_ptr: *mut u8,
+        PublKeySizeInBytes: u32,
+        CheckMode: EC_PublKeyCheckMode_t,
+        pUserPublKey: *mut CRYS_ECPKI_UserPublKey_t,
+        tempBuff_ptr: *mut CRYS_ECPKI_BUILD_TempData_t,
+    ) -> CRYSError_t;
+}
+extern "C" {
+    pub fn CRYS_ECDSA_Verify(
+        pVerifyUserContext: *mut CRYS_ECDSA_VerifyUserContext_t,
+        pUserPublKey: *mut CRYS_ECPKI_UserPublKey_t,
+        hashMode: CRYS_ECPKI_HASH_OpMode_t,
+        pSignatureIn: *mut u8,
+        SignatureSizeBytes: usize,
+        pMessageDataIn: *mut u8,
+        messageSizeInBytes: usize,
+    ) -> CRYSError_t;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct CRYS_ECEDW_TempBuff_t {
+    pub buff: [u32; 140usize],
+}
+extern "C" {
+    pub fn CRYS_ECEDW_Verify(
+        pInSign: *const u8,
+        signSize: usize,
+        pSignPublKey: *const u8,
+        publKeySize: usize,
+        pMsg: *mut u8,
+        msgSize: usize,
+        pTempBuff: *mut CRYS_ECEDW_TempBuff_t,
+    ) -> CRYSError_t;
+}
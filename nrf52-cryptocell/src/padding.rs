@@ -0,0 +1,75 @@
+//! PKCS7 padding
+//!
+//! The CryptoCell only processes whole blocks, the padding of the block
+//! cipher modes is checked in software.
+
+use crate::BLOCK_SIZE;
+
+/// Length of PKCS7 padded `data` without the padding
+pub(crate) fn unpad(data: &[u8]) -> Option<usize> {
+    let padding = *data.last()? as usize;
+    if padding == 0 || padding > BLOCK_SIZE || padding > data.len() {
+        return None;
+    }
+    let mut difference = 0u8;
+    for b in data[data.len() - padding..].iter() {
+        difference |= b ^ (padding as u8);
+    }
+    if difference == 0 {
+        Some(data.len() - padding)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_padding() {
+        let mut block = [0x5au8; BLOCK_SIZE];
+        block[BLOCK_SIZE - 1] = 1;
+        assert_eq!(unpad(&block), Some(15));
+        block[BLOCK_SIZE - 3..].copy_from_slice(&[3, 3, 3]);
+        assert_eq!(unpad(&block), Some(13));
+        // A whole block of padding
+        assert_eq!(unpad(&[16u8; BLOCK_SIZE]), Some(0));
+        // Only the last block holds padding
+        let mut data = [16u8; 2 * BLOCK_SIZE];
+        data[..BLOCK_SIZE].copy_from_slice(&[0x5a; BLOCK_SIZE]);
+        assert_eq!(unpad(&data), Some(16));
+    }
+
+    #[test]
+    fn zero_padding() {
+        let block = [0u8; BLOCK_SIZE];
+        assert_eq!(unpad(&block), None);
+    }
+
+    #[test]
+    fn padding_longer_than_block() {
+        assert_eq!(unpad(&[17u8; 2 * BLOCK_SIZE]), None);
+        assert_eq!(unpad(&[0xffu8; 2 * BLOCK_SIZE]), None);
+    }
+
+    #[test]
+    fn padding_longer_than_data() {
+        assert_eq!(unpad(&[4u8, 4, 4]), None);
+    }
+
+    #[test]
+    fn mismatched_padding() {
+        let mut block = [4u8; BLOCK_SIZE];
+        block[BLOCK_SIZE - 4] = 3;
+        assert_eq!(unpad(&block), None);
+        block[BLOCK_SIZE - 4] = 4;
+        block[BLOCK_SIZE - 2] = 0x84;
+        assert_eq!(unpad(&block), None);
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(unpad(&[]), None);
+    }
+}
@@ -0,0 +1,71 @@
+//! CryptoCell power management
+//!
+//! The CryptoCell is only enabled while it is in use. Each operation holds a
+//! [`PowerGuard`], the first guard enables the peripheral and the last guard
+//! to be dropped disables it again. Guards can be nested, so an operation
+//! built from several smaller operations keeps the peripheral enabled
+//! throughout.
+//!
+//! Nothing needs to be re-initialised when the CryptoCell is enabled again.
+//! The state set up by `SaSi_LibInit` and the AES and DRBG contexts are kept
+//! in RAM. The library loads the key and IV from the AES context for every
+//! operation and sets up the TRNG for every reseed of the DRBG. The device
+//! root key is kept in the KDR registers, which are in the always-on power
+//! domain of the nRF52840. So only an operation in progress needs the
+//! CryptoCell, guards are held by
+//!
+//! - an operation, for its whole duration
+//! - a `CryptoCell` with [`set_always_on`](crate::CryptoCell::set_always_on)
+
+use core::cell::Cell;
+
+use crate::pac::CRYPTOCELL;
+use cortex_m::interrupt::{self, Mutex};
+
+/// Number of active power guards
+static USERS: Mutex<Cell<usize>> = Mutex::new(Cell::new(0));
+
+fn set_enable(enable: bool) {
+    // Safety: The enable register is only written from within a critical
+    // section, here and when the `CryptoCell` is constructed or dropped
+    let cryptocell = unsafe { &*CRYPTOCELL::ptr() };
+    cryptocell.enable.write(|w| w.enable().bit(enable));
+}
+
+/// Keeps the CryptoCell enabled while alive
+#[derive(Debug)]
+pub struct PowerGuard {
+    _private: (),
+}
+
+impl PowerGuard {
+    /// Enable the CryptoCell, if not already enabled
+    pub fn acquire() -> Self {
+        interrupt::free(|cs| {
+            let users = USERS.borrow(cs);
+            if users.get() == 0 {
+                set_enable(true);
+            }
+            users.set(users.get() + 1);
+        });
+        Self { _private: () }
+    }
+}
+
+impl core::ops::Drop for PowerGuard {
+    fn drop(&mut self) {
+        interrupt::free(|cs| {
+            let users = USERS.borrow(cs);
+            let count = users.get() - 1;
+            if count == 0 {
+                set_enable(false);
+            }
+            users.set(count);
+        });
+    }
+}
+
+/// Returns true if the CryptoCell is enabled by a power guard
+pub fn is_enabled() -> bool {
+    interrupt::free(|cs| USERS.borrow(cs).get() > 0)
+}
@@ -0,0 +1,235 @@
+//! Deferred CCM* operations
+//!
+//! A [`CcmStarRequest`] is a single entry deferral queue. It holds a copy of
+//! everything needed for a CCM* operation. The request is submitted from a
+//! time critical context, such as the radio interrupt, and processed later by
+//! a low priority task using [`CryptoCellBackend::process`]. The submitter
+//! polls the request for the result. This keeps long CCM* operations out of
+//! the high priority tasks.
+//!
+//! [`CryptoCellBackend::process`] runs the operation in the task that calls
+//! it and marks the request complete before returning. With the `interrupts`
+//! feature that task sleeps until the CRYPTOCELL interrupt signals that the
+//! CryptoCell is done, higher priority tasks run in the meantime. The
+//! application decides when the request is processed, typically by spawning
+//! the low priority task when submitting.
+//!
+//! The psila backend traits are called by the psila service while it handles
+//! a frame and expect the result on return. The psila examples defer the
+//! service instead, the radio and timer interrupts only queue received frames
+//! and timer events, the service and its CCM* operations run in a lower
+//! priority task.
+//!
+//! The data is encrypted or decrypted in place, the output replaces the input
+//! in the request.
+
+use crate::{
+    clear, error::Redacted, CcmStarInPlace, CryptoCellBackend, EncryptDecrypt, Error, BLOCK_SIZE,
+    KEY_SIZE,
+};
+
+/// Maximum size of the data and additional data in a request, in bytes
+pub const MAX_REQUEST_DATA_SIZE: usize = 128;
+/// Size of the CCM* nonce, in bytes
+pub const NONCE_SIZE: usize = 13;
+
+/// State of a request
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RequestState {
+    /// No operation requested
+    Idle,
+    /// Waiting to be processed
+    Pending,
+    /// Processed, holds the length of the output or an error
+    Complete(Result<usize, Error>),
+}
+
+/// CCM* operation request
+pub struct CcmStarRequest {
+    state: RequestState,
+    operation: EncryptDecrypt,
+    key: [u8; KEY_SIZE],
+    nonce: [u8; NONCE_SIZE],
+    mic: [u8; BLOCK_SIZE],
+    mic_length: usize,
+    aad: [u8; MAX_REQUEST_DATA_SIZE],
+    aad_length: usize,
+    data: [u8; MAX_REQUEST_DATA_SIZE],
+    data_length: usize,
+}
+
+impl core::fmt::Debug for CcmStarRequest {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        // Only the state, the buffers hold the key and the plaintext
+        f.debug_struct("CcmStarRequest")
+            .field("state", &self.state)
+            .field("operation", &self.operation)
+            .field("key", &Redacted)
+            .field("data_length", &self.data_length)
+            .finish()
+    }
+}
+
+impl Default for CcmStarRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CcmStarRequest {
+    pub const fn new() -> Self {
+        Self {
+            state: RequestState::Idle,
+            operation: EncryptDecrypt::Encrypt,
+            key: [0u8; KEY_SIZE],
+            nonce: [0u8; NONCE_SIZE],
+            mic: [0u8; BLOCK_SIZE],
+            mic_length: 0,
+            aad: [0u8; MAX_REQUEST_DATA_SIZE],
+            aad_length: 0,
+            data: [0u8; MAX_REQUEST_DATA_SIZE],
+            data_length: 0,
+        }
+    }
+
+    fn prepare(
+        &mut self,
+        operation: EncryptDecrypt,
+        key: &[u8],
+        nonce: &[u8],
+        data: &[u8],
+        aad: &[u8],
+    ) -> bool {
+        if self.state == RequestState::Pending {
+            return false;
+        }
+        assert!(key.len() == KEY_SIZE);
+        assert!(nonce.len() == NONCE_SIZE);
+        assert!(data.len() <= MAX_REQUEST_DATA_SIZE);
+        assert!(aad.len() <= MAX_REQUEST_DATA_SIZE);
+        self.operation = operation;
+        self.key.copy_from_slice(key);
+        self.nonce.copy_from_slice(nonce);
+        self.data[..data.len()].copy_from_slice(data);
+        self.data_length = data.len();
+        self.aad[..aad.len()].copy_from_slice(aad);
+        self.aad_length = aad.len();
+        true
+    }
+
+    /// Request encryption of `message`, producing a MIC of `mic_length` bytes
+    ///
+    /// Returns false if a request already is pending.
+    pub fn submit_encrypt(
+        &mut self,
+        key: &[u8],
+        nonce: &[u8],
+        message: &[u8],
+        mic_length: usize,
+        aad: &[u8],
+    ) -> bool {
+        assert!(mic_length <= BLOCK_SIZE);
+        if !self.prepare(EncryptDecrypt::Encrypt, key, nonce, message, aad) {
+            return false;
+        }
+        self.mic_length = mic_length;
+        self.state = RequestState::Pending;
+        true
+    }
+
+    /// Request decryption of `encrypted`, validated with `mic`
+    ///
+    /// Returns false if a request already is pending.
+    pub fn submit_decrypt(
+        &mut self,
+        key: &[u8],
+        nonce: &[u8],
+        encrypted: &[u8],
+        mic: &[u8],
+        aad: &[u8],
+    ) -> bool {
+        assert!(mic.len() <= BLOCK_SIZE);
+        if !self.prepare(EncryptDecrypt::Decrypt, key, nonce, encrypted, aad) {
+            return false;
+        }
+        self.mic[..mic.len()].copy_from_slice(mic);
+        self.mic_length = mic.len();
+        self.state = RequestState::Pending;
+        true
+    }
+
+    /// Current state of the request
+    pub fn state(&self) -> RequestState {
+        self.state.clone()
+    }
+
+    /// Returns true if the request is waiting to be processed
+    pub fn is_pending(&self) -> bool {
+        self.state == RequestState::Pending
+    }
+
+    /// Take the result of a completed request
+    ///
+    /// Returns `None` if the request hasn't been processed. The output stays
+    /// available until the next submit.
+    pub fn poll(&mut self) -> Option<Result<usize, Error>> {
+        match core::mem::replace(&mut self.state, RequestState::Idle) {
+            RequestState::Complete(result) => Some(result),
+            state => {
+                self.state = state;
+                None
+            }
+        }
+    }
+
+    /// Output data, encrypted or decrypted depending on the operation
+    pub fn output(&self) -> &[u8] {
+        &self.data[..self.data_length]
+    }
+
+    /// MIC, generated by encryption
+    pub fn mic(&self) -> &[u8] {
+        &self.mic[..self.mic_length]
+    }
+}
+
+impl<'a> CryptoCellBackend<'a> {
+    /// Process a pending request
+    ///
+    /// Runs the operation to completion before returning, call it from a low
+    /// priority task. Returns true if a request was processed, the result is
+    /// then available through [`CcmStarRequest::poll`].
+    pub fn process(&mut self, request: &mut CcmStarRequest) -> bool {
+        if request.state != RequestState::Pending {
+            return false;
+        }
+        let length = request.data_length;
+        let aad_length = request.aad_length;
+        let mic_length = request.mic_length;
+        let data = &mut request.data[..length];
+        let result = match request.operation {
+            EncryptDecrypt::Encrypt => self
+                .ccmstar_encrypt_in_place(
+                    &request.key,
+                    &request.nonce,
+                    data,
+                    &mut request.mic[..mic_length],
+                    &request.aad[..aad_length],
+                )
+                .map(|_| length),
+            EncryptDecrypt::Decrypt => self
+                .ccmstar_decrypt_in_place(
+                    &request.key,
+                    &request.nonce,
+                    data,
+                    &request.mic[..mic_length],
+                    &request.aad[..aad_length],
+                )
+                .map(|valid| if valid { length } else { 0 }),
+        };
+        clear(&mut request.key);
+        request.state = RequestState::Complete(result);
+        true
+    }
+}
@@ -0,0 +1,314 @@
+//! RustCrypto trait implementations
+//!
+//! Implements the traits from the `cipher` and `aead` crates using the
+//! CryptoCell AES, so that crates built on those traits get hardware
+//! acceleration.
+//!
+//! * [`Aes128`] implements `BlockEncrypt` and `BlockDecrypt`
+//! * [`Aes128Ctr`] implements `StreamCipher`
+//! * [`Aes128Ccm`] implements `AeadInPlace`, for tag sizes of 4 to 16 bytes
+//!   and nonce sizes of 7 to 13 bytes
+//!
+//! The types borrow the [`CryptoCell`], so they are created with `new`
+//! functions taking the CryptoCell and the key instead of implementing the
+//! `NewBlockCipher`, `NewCipher` and `NewAead` traits. Crates that take an
+//! existing block cipher, such as `cmac`, work with [`Aes128`].
+//!
+//! # Exclusive access
+//!
+//! The `BlockEncrypt`, `BlockDecrypt` and `StreamCipher` methods can't report
+//! errors. They need exclusive access to the CryptoCell and panic if another
+//! operation is running, which otherwise fails with `Error::Busy`. Exclusive
+//! access is a precondition of [`Aes128`] and [`Aes128Ctr`], it isn't checked
+//! at compile time since the CryptoCell is shared by reference. Use them from
+//! a single task, or make sure that operations from different tasks can't
+//! overlap, for example by sharing the CryptoCell as a RTIC resource and
+//! locking it. [`Aes128Ccm`] reports a busy CryptoCell, and a message or
+//! additional data too long for the length fields, as `aead::Error`.
+
+use core::cell::RefCell;
+use core::marker::PhantomData;
+
+use aead::{AeadCore, AeadInPlace};
+use cipher::{
+    consts::{U0, U1, U16},
+    errors::LoopError,
+    generic_array::{ArrayLength, GenericArray},
+    BlockDecrypt, BlockEncrypt, StreamCipher,
+};
+
+use crate::{
+    ccmstar, clear, error::Redacted, AesContext, AesOperationMode, BlockCipher as _, CryptoCell,
+    EncryptDecrypt, Error, PaddingType, BLOCK_SIZE,
+};
+
+/// Create a context with `key` set
+fn keyed_context<'a>(
+    cryptocell: &'a CryptoCell,
+    encrypt: EncryptDecrypt,
+    mode: AesOperationMode,
+    key: &[u8],
+) -> Result<AesContext<'a>, Error> {
+    let mut context = AesContext::new(cryptocell, encrypt, mode, PaddingType::None)?;
+    context.set_key(key)?;
+    Ok(context)
+}
+
+/// AES-128 block cipher
+///
+/// # Panics
+///
+/// Encrypting or decrypting panics if another CryptoCell operation is
+/// running, the caller must ensure exclusive access to the CryptoCell while
+/// the cipher is used, see the module documentation.
+pub struct Aes128<'a> {
+    encrypt: RefCell<AesContext<'a>>,
+    decrypt: RefCell<AesContext<'a>>,
+}
+
+impl<'a> Aes128<'a> {
+    pub fn new(cryptocell: &'a CryptoCell, key: &GenericArray<u8, U16>) -> Result<Self, Error> {
+        Ok(Self {
+            encrypt: RefCell::new(keyed_context(
+                cryptocell,
+                EncryptDecrypt::Encrypt,
+                AesOperationMode::Ecb,
+                key,
+            )?),
+            decrypt: RefCell::new(keyed_context(
+                cryptocell,
+                EncryptDecrypt::Decrypt,
+                AesOperationMode::Ecb,
+                key,
+            )?),
+        })
+    }
+}
+
+impl<'a> core::fmt::Debug for Aes128<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Aes128")
+            .field("encrypt", &self.encrypt)
+            .field("decrypt", &self.decrypt)
+            .finish()
+    }
+}
+
+impl<'a> cipher::BlockCipher for Aes128<'a> {
+    type BlockSize = U16;
+    type ParBlocks = U1;
+}
+
+/// # Panics
+///
+/// Panics if the CryptoCell is busy, see the module documentation.
+impl<'a> BlockEncrypt for Aes128<'a> {
+    fn encrypt_block(&self, block: &mut GenericArray<u8, U16>) {
+        let input = *block;
+        self.encrypt
+            .borrow_mut()
+            .process_block(&input, block)
+            .expect("AES encryption failed");
+    }
+}
+
+/// # Panics
+///
+/// Panics if the CryptoCell is busy, see the module documentation.
+impl<'a> BlockDecrypt for Aes128<'a> {
+    fn decrypt_block(&self, block: &mut GenericArray<u8, U16>) {
+        let input = *block;
+        self.decrypt
+            .borrow_mut()
+            .process_block(&input, block)
+            .expect("AES decryption failed");
+    }
+}
+
+/// AES-128 in counter mode, with a 128-bit big-endian counter
+///
+/// The nonce is the initial counter block.
+///
+/// # Panics
+///
+/// Applying the key stream panics if another CryptoCell operation is running,
+/// the caller must ensure exclusive access to the CryptoCell while the cipher
+/// is used, see the module documentation.
+pub struct Aes128Ctr<'a> {
+    context: AesContext<'a>,
+    /// Key stream left over from the last partial block
+    keystream: [u8; BLOCK_SIZE],
+    position: usize,
+}
+
+impl<'a> Aes128Ctr<'a> {
+    pub fn new(
+        cryptocell: &'a CryptoCell,
+        key: &GenericArray<u8, U16>,
+        nonce: &GenericArray<u8, U16>,
+    ) -> Result<Self, Error> {
+        let mut context = keyed_context(
+            cryptocell,
+            EncryptDecrypt::Encrypt,
+            AesOperationMode::Ctr,
+            key,
+        )?;
+        context.set_iv(nonce)?;
+        Ok(Self {
+            context,
+            keystream: [0u8; BLOCK_SIZE],
+            position: BLOCK_SIZE,
+        })
+    }
+}
+
+/// # Panics
+///
+/// Panics if the CryptoCell is busy, see the module documentation.
+impl<'a> StreamCipher for Aes128Ctr<'a> {
+    fn try_apply_keystream(&mut self, data: &mut [u8]) -> Result<(), LoopError> {
+        // Use what is left of the key stream from the last call
+        let used = data.len().min(BLOCK_SIZE - self.position);
+        let (head, data) = data.split_at_mut(used);
+        for (d, k) in head.iter_mut().zip(self.keystream[self.position..].iter()) {
+            *d ^= *k;
+        }
+        self.position += used;
+
+        let full_length = (data.len() / BLOCK_SIZE) * BLOCK_SIZE;
+        let (full, tail) = data.split_at_mut(full_length);
+        for chunk in full.chunks_exact_mut(BLOCK_SIZE) {
+            let mut block = [0u8; BLOCK_SIZE];
+            block.copy_from_slice(chunk);
+            self.context
+                .process_block(&block, chunk)
+                .expect("AES encryption failed");
+        }
+
+        if !tail.is_empty() {
+            let block = [0u8; BLOCK_SIZE];
+            self.context
+                .process_block(&block, &mut self.keystream)
+                .expect("AES encryption failed");
+            for (d, k) in tail.iter_mut().zip(self.keystream.iter()) {
+                *d ^= *k;
+            }
+            self.position = tail.len();
+        }
+        Ok(())
+    }
+}
+
+impl<'a> core::fmt::Debug for Aes128Ctr<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Aes128Ctr")
+            .field("context", &self.context)
+            .field("keystream", &Redacted)
+            .field("position", &self.position)
+            .finish()
+    }
+}
+
+impl<'a> core::ops::Drop for Aes128Ctr<'a> {
+    fn drop(&mut self) {
+        clear(&mut self.keystream);
+    }
+}
+
+/// AES-128-CCM with a `M` byte tag and a `N` byte nonce
+///
+/// The length field size is derived from the nonce size, L = 15 - N. Zigbee
+/// and IEEE 802.15.4 uses `Aes128Ccm<'_, U4..U16, U13>`, Bluetooth LE uses
+/// `Aes128Ccm<'_, U4, U13>`.
+pub struct Aes128Ccm<'a, M, N> {
+    cipher: RefCell<AesContext<'a>>,
+    _sizes: PhantomData<(M, N)>,
+}
+
+impl<'a, M, N> Aes128Ccm<'a, M, N>
+where
+    M: ArrayLength<u8>,
+    N: ArrayLength<u8>,
+{
+    pub fn new(cryptocell: &'a CryptoCell, key: &GenericArray<u8, U16>) -> Result<Self, Error> {
+        assert!(M::USIZE >= 4 && M::USIZE <= BLOCK_SIZE && M::USIZE % 2 == 0);
+        assert!(N::USIZE >= ccmstar::MIN_NONCE_SIZE && N::USIZE <= ccmstar::MAX_NONCE_SIZE);
+        Ok(Self {
+            cipher: RefCell::new(keyed_context(
+                cryptocell,
+                EncryptDecrypt::Encrypt,
+                AesOperationMode::Ecb,
+                key,
+            )?),
+            _sizes: PhantomData,
+        })
+    }
+}
+
+impl<'a, M, N> core::fmt::Debug for Aes128Ccm<'a, M, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Aes128Ccm")
+            .field("cipher", &self.cipher)
+            .finish()
+    }
+}
+
+impl<'a, M, N> AeadCore for Aes128Ccm<'a, M, N>
+where
+    M: ArrayLength<u8>,
+    N: ArrayLength<u8>,
+{
+    type NonceSize = N;
+    type TagSize = M;
+    type CiphertextOverhead = U0;
+}
+
+impl<'a, M, N> AeadInPlace for Aes128Ccm<'a, M, N>
+where
+    M: ArrayLength<u8>,
+    N: ArrayLength<u8>,
+{
+    fn encrypt_in_place_detached(
+        &self,
+        nonce: &GenericArray<u8, N>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<GenericArray<u8, M>, aead::Error> {
+        if !ccmstar::lengths_valid(N::USIZE, buffer.len(), associated_data.len()) {
+            return Err(aead::Error);
+        }
+        let mut cipher = self.cipher.borrow_mut();
+        let mut encrypt = |block: &mut [u8; BLOCK_SIZE]| {
+            let input = *block;
+            cipher.process_block(&input, block)
+        };
+        let mut tag = GenericArray::default();
+        ccmstar::encrypt_in_place(&mut encrypt, nonce, buffer, &mut tag, associated_data)
+            .map_err(|_| aead::Error)?;
+        Ok(tag)
+    }
+
+    fn decrypt_in_place_detached(
+        &self,
+        nonce: &GenericArray<u8, N>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+        tag: &GenericArray<u8, M>,
+    ) -> Result<(), aead::Error> {
+        if !ccmstar::lengths_valid(N::USIZE, buffer.len(), associated_data.len()) {
+            return Err(aead::Error);
+        }
+        let mut cipher = self.cipher.borrow_mut();
+        let mut encrypt = |block: &mut [u8; BLOCK_SIZE]| {
+            let input = *block;
+            cipher.process_block(&input, block)
+        };
+        let valid = ccmstar::decrypt_in_place(&mut encrypt, nonce, buffer, tag, associated_data)
+            .map_err(|_| aead::Error)?;
+        if valid {
+            Ok(())
+        } else {
+            Err(aead::Error)
+        }
+    }
+}
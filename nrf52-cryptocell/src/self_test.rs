@@ -0,0 +1,231 @@
+//! Power-on self-test
+//!
+//! Known-answer tests for the AES operations used by the backend. A faulty
+//! CryptoCell produces wrong ciphertexts and MICs without reporting an error,
+//! frames secured by it are silently dropped by the other nodes. Running the
+//! self-test before joining a network lets the node refuse to operate instead.
+//!
+//! The vectors are from FIPS-197 appendix C.1, SP800-38A appendix F.2.1 and
+//! F.5.1 and RFC 3610 packet vector #1. A second CCM* vector has additional
+//! data and payload longer than two blocks, like most 802.15.4 and Zigbee
+//! frames. The CCM* vectors run twice, so the second run goes through the
+//! cached key contexts.
+
+use crate::{
+    AesContext, AesOperationMode, BlockCipher as _, CcmStarInPlace, CryptoCell, CryptoCellBackend,
+    EncryptDecrypt, Error, PaddingType, BLOCK_SIZE, KEY_SIZE,
+};
+
+/// Operation that failed the self-test
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SelfTestError {
+    /// AES-ECB produced the wrong ciphertext
+    Ecb,
+    /// AES-CTR produced the wrong ciphertext
+    Ctr,
+    /// AES-CBC-MAC produced the wrong MAC
+    CbcMac,
+    /// CCM* produced the wrong ciphertext or MIC, or rejected a valid MIC
+    CcmStar,
+    /// The CryptoCell reported an error
+    Backend(Error),
+}
+
+impl From<Error> for SelfTestError {
+    fn from(error: Error) -> Self {
+        SelfTestError::Backend(error)
+    }
+}
+
+/// FIPS-197 appendix C.1
+const ECB_KEY: [u8; KEY_SIZE] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+const ECB_PLAINTEXT: [u8; BLOCK_SIZE] = [
+    0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+];
+const ECB_CIPHERTEXT: [u8; BLOCK_SIZE] = [
+    0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4, 0xc5, 0x5a,
+];
+
+/// SP800-38A key and plaintext, shared by the CTR and CBC-MAC tests
+const SP800_38A_KEY: [u8; KEY_SIZE] = [
+    0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+];
+const SP800_38A_PLAINTEXT: [u8; 2 * BLOCK_SIZE] = [
+    0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+    0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+];
+
+/// SP800-38A appendix F.5.1
+const CTR_COUNTER: [u8; BLOCK_SIZE] = [
+    0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa, 0xfb, 0xfc, 0xfd, 0xfe, 0xff,
+];
+const CTR_CIPHERTEXT: [u8; 2 * BLOCK_SIZE] = [
+    0x87, 0x4d, 0x61, 0x91, 0xb6, 0x20, 0xe3, 0x26, 0x1b, 0xef, 0x68, 0x64, 0x99, 0x0d, 0xb6, 0xce,
+    0x98, 0x06, 0xf6, 0x6b, 0x79, 0x70, 0xfd, 0xff, 0x86, 0x17, 0x18, 0x7b, 0xb9, 0xff, 0xfd, 0xff,
+];
+
+/// SP800-38A appendix F.2.1, the CBC-MAC is the last CBC ciphertext block
+const CBC_IV: [u8; BLOCK_SIZE] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+const CBC_MAC: [u8; BLOCK_SIZE] = [
+    0x50, 0x86, 0xcb, 0x9b, 0x50, 0x72, 0x19, 0xee, 0x95, 0xdb, 0x11, 0x3a, 0x91, 0x76, 0x78, 0xb2,
+];
+
+/// RFC 3610 packet vector #1
+const CCM_KEY: [u8; KEY_SIZE] = [
+    0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcc, 0xcd, 0xce, 0xcf,
+];
+const CCM_NONCE: [u8; 13] = [
+    0x00, 0x00, 0x00, 0x03, 0x02, 0x01, 0x00, 0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5,
+];
+const CCM_AAD: [u8; 8] = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07];
+const CCM_PLAINTEXT: [u8; 23] = [
+    0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17,
+    0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e,
+];
+const CCM_CIPHERTEXT: [u8; 23] = [
+    0x58, 0x8c, 0x97, 0x9a, 0x61, 0xc6, 0x63, 0xd2, 0xf0, 0x66, 0xd0, 0xc2, 0xc0, 0xf9, 0x89, 0x80,
+    0x6d, 0x5f, 0x6b, 0x61, 0xda, 0xc3, 0x84,
+];
+const CCM_MIC: [u8; 8] = [0x17, 0xe8, 0xd1, 0x2c, 0xfd, 0xf9, 0x26, 0xe0];
+
+/// Additional data and payload longer than two blocks, exercising the
+/// multi-block CBC-MAC, computed with the Python cryptography package. Uses the
+/// RFC 3610 key
+const CCM_LONG_NONCE: [u8; 13] = [
+    0xac, 0xde, 0x48, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x05,
+];
+const CCM_LONG_AAD: [u8; 40] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+    0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f,
+    0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27,
+];
+const CCM_LONG_PLAINTEXT: [u8; 45] = [
+    0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d, 0x4e, 0x4f,
+    0x50, 0x51, 0x52, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x5b, 0x5c, 0x5d, 0x5e, 0x5f,
+    0x60, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x6b, 0x6c,
+];
+const CCM_LONG_CIPHERTEXT: [u8; 45] = [
+    0x14, 0x45, 0x9c, 0x55, 0x90, 0x2e, 0xe2, 0x96, 0xde, 0x3d, 0x38, 0x31, 0xf4, 0x95, 0xd5, 0x6e,
+    0x8f, 0xfb, 0x0e, 0xd4, 0xf7, 0x96, 0x6c, 0xd5, 0x8c, 0x92, 0xb4, 0xa9, 0xaf, 0x1b, 0xa1, 0xcc,
+    0xb1, 0xec, 0x9e, 0x9f, 0xd3, 0xff, 0x7d, 0xa5, 0x43, 0xab, 0x80, 0xa8, 0xc2,
+];
+const CCM_LONG_MIC: [u8; 8] = [0xbc, 0x97, 0x47, 0x76, 0x22, 0xc0, 0x07, 0x36];
+
+fn context<'a>(
+    cryptocell: &'a CryptoCell,
+    mode: AesOperationMode,
+    key: &[u8],
+) -> Result<AesContext<'a>, Error> {
+    let mut context =
+        AesContext::new(cryptocell, EncryptDecrypt::Encrypt, mode, PaddingType::None)?;
+    context.set_key(key)?;
+    Ok(context)
+}
+
+fn check(result: &[u8], expected: &[u8], error: SelfTestError) -> Result<(), SelfTestError> {
+    if result == expected {
+        Ok(())
+    } else {
+        Err(error)
+    }
+}
+
+impl<'a> CryptoCellBackend<'a> {
+    /// Create a backend after running the self-test
+    ///
+    /// Fails if the CryptoCell doesn't produce the known answers.
+    pub fn with_self_test(cryptocell: &'a CryptoCell) -> Result<Self, SelfTestError> {
+        let mut backend = Self::new(cryptocell)?;
+        backend.self_test()?;
+        Ok(backend)
+    }
+
+    /// Run known-answer tests for AES-ECB, AES-CTR, AES-CBC-MAC and CCM*
+    ///
+    /// Clears the key cache.
+    pub fn self_test(&mut self) -> Result<(), SelfTestError> {
+        let cryptocell = self.cryptocell;
+
+        let mut block = ECB_PLAINTEXT;
+        context(cryptocell, AesOperationMode::Ecb, &ECB_KEY)?.process_block_in_place(&mut block)?;
+        check(&block, &ECB_CIPHERTEXT, SelfTestError::Ecb)?;
+
+        let mut data = SP800_38A_PLAINTEXT;
+        let mut cipher = context(cryptocell, AesOperationMode::Ctr, &SP800_38A_KEY)?;
+        cipher.set_iv(&CTR_COUNTER)?;
+        cipher.process_block_in_place(&mut data[..BLOCK_SIZE])?;
+        cipher.finish_in_place(&mut data[BLOCK_SIZE..])?;
+        check(&data, &CTR_CIPHERTEXT, SelfTestError::Ctr)?;
+
+        let mut mac = [0u8; BLOCK_SIZE];
+        let mut cipher = context(cryptocell, AesOperationMode::CbcMac, &SP800_38A_KEY)?;
+        cipher.set_iv(&CBC_IV)?;
+        cipher.process_block(&SP800_38A_PLAINTEXT[..BLOCK_SIZE], &mut mac)?;
+        cipher.finish(&SP800_38A_PLAINTEXT[BLOCK_SIZE..], &mut mac)?;
+        check(&mac, &CBC_MAC, SelfTestError::CbcMac)?;
+
+        let result = self.self_test_ccmstar();
+        // Don't keep the test key in the cache
+        self.clear_key_cache();
+        result
+    }
+
+    fn self_test_ccmstar(&mut self) -> Result<(), SelfTestError> {
+        self.clear_key_cache();
+        // The first pass sets up the cached contexts, the second pass reuses
+        // them after they were finished, as for every frame after the first
+        for _ in 0..2 {
+            self.self_test_ccmstar_vector(
+                &CCM_NONCE,
+                &CCM_AAD,
+                &CCM_PLAINTEXT,
+                &CCM_CIPHERTEXT,
+                &CCM_MIC,
+            )?;
+            self.self_test_ccmstar_vector(
+                &CCM_LONG_NONCE,
+                &CCM_LONG_AAD,
+                &CCM_LONG_PLAINTEXT,
+                &CCM_LONG_CIPHERTEXT,
+                &CCM_LONG_MIC,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn self_test_ccmstar_vector(
+        &mut self,
+        nonce: &[u8],
+        aad: &[u8],
+        plaintext: &[u8],
+        ciphertext: &[u8],
+        expected_mic: &[u8],
+    ) -> Result<(), SelfTestError> {
+        let mut buffer = [0u8; CCM_LONG_PLAINTEXT.len()];
+        let data = &mut buffer[..plaintext.len()];
+        let mut mic = [0u8; 8];
+
+        data.copy_from_slice(plaintext);
+        self.ccmstar_encrypt_in_place(&CCM_KEY, nonce, data, &mut mic, aad)?;
+        check(data, ciphertext, SelfTestError::CcmStar)?;
+        check(&mic, expected_mic, SelfTestError::CcmStar)?;
+
+        if !self.ccmstar_decrypt_in_place(&CCM_KEY, nonce, data, &mic, aad)? {
+            return Err(SelfTestError::CcmStar);
+        }
+        check(data, plaintext, SelfTestError::CcmStar)?;
+
+        // A modified MIC must be rejected
+        data.copy_from_slice(ciphertext);
+        mic[0] ^= 0x01;
+        if self.ccmstar_decrypt_in_place(&CCM_KEY, nonce, data, &mic, aad)? {
+            return Err(SelfTestError::CcmStar);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,892 @@
+//! Zigbee NWK and APS security
+//!
+//! Parse and build the Zigbee auxiliary security header, create the CCM*
+//! nonce and secure or unsecure NWK and APS frames with any [`CcmStarInPlace`]
+//! backend.
+//!
+//! Zigbee transmits the security level as zero, the receiver substitutes the
+//! security level of the network. The substituted security control byte, with
+//! the key identifier and extended nonce bits, is the last byte of the nonce.
+//! That is why the CCM* implementation of the nrf_cc310 library can not be
+//! used.
+//!
+//! Parsing and nonce creation do not use the hardware, so these can be used
+//! by host tools that decode captured traffic.
+
+use crate::{CcmStarInPlace, Error, BLOCK_SIZE};
+
+/// Security level used by Zigbee networks, encryption with a 4 byte MIC
+pub const DEFAULT_SECURITY_LEVEL: u8 = 5;
+/// Size of the Zigbee CCM* nonce
+pub const NONCE_SIZE: usize = 13;
+/// Maximum size of a frame handled here
+pub const MAX_FRAME_SIZE: usize = 127;
+
+const NWK_SECURITY: u8 = 0x02;
+const APS_SECURITY: u8 = 0x20;
+
+/// Length of the MIC for the security `level`
+pub fn mic_length(level: u8) -> usize {
+    match level & 0x03 {
+        1 => 4,
+        2 => 8,
+        3 => 16,
+        _ => 0,
+    }
+}
+
+/// Key used to secure the frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum KeyIdentifier {
+    /// Link key
+    Data = 0,
+    /// Network key
+    Network = 1,
+    /// Key-transport key, derived from the link key
+    KeyTransport = 2,
+    /// Key-load key, derived from the link key
+    KeyLoad = 3,
+}
+
+impl KeyIdentifier {
+    fn from_bits(bits: u8) -> Self {
+        match bits & 0x03 {
+            1 => KeyIdentifier::Network,
+            2 => KeyIdentifier::KeyTransport,
+            3 => KeyIdentifier::KeyLoad,
+            _ => KeyIdentifier::Data,
+        }
+    }
+}
+
+/// Zigbee auxiliary security header
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SecurityHeader {
+    /// Security level as transmitted, normally zero
+    pub level: u8,
+    pub key_identifier: KeyIdentifier,
+    pub frame_counter: u32,
+    /// Extended source address, present if the extended nonce bit is set
+    pub source: Option<u64>,
+    /// Network key sequence number, present for the network key
+    pub key_sequence: Option<u8>,
+}
+
+impl SecurityHeader {
+    /// Length of the encoded header
+    pub fn length(&self) -> usize {
+        let mut length = 5;
+        if self.source.is_some() {
+            length += 8;
+        }
+        if self.key_sequence.is_some() {
+            length += 1;
+        }
+        length
+    }
+
+    /// Security control byte with the security `level`
+    pub fn control(&self, level: u8) -> u8 {
+        let mut control = (level & 0x07) | ((self.key_identifier as u8) << 3);
+        if self.source.is_some() {
+            control |= 0x20;
+        }
+        control
+    }
+
+    /// Decode the header from `data`
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        if data.len() < 5 {
+            return Err(Error::InvalidFrame);
+        }
+        let control = data[0];
+        let key_identifier = KeyIdentifier::from_bits(control >> 3);
+        let mut frame_counter = [0u8; 4];
+        frame_counter.copy_from_slice(&data[1..5]);
+        let mut offset = 5;
+        let source = if control & 0x20 == 0x20 {
+            if data.len() < offset + 8 {
+                return Err(Error::InvalidFrame);
+            }
+            let mut source = [0u8; 8];
+            source.copy_from_slice(&data[offset..offset + 8]);
+            offset += 8;
+            Some(u64::from_le_bytes(source))
+        } else {
+            None
+        };
+        let key_sequence = if key_identifier == KeyIdentifier::Network {
+            if data.len() <= offset {
+                return Err(Error::InvalidFrame);
+            }
+            Some(data[offset])
+        } else {
+            None
+        };
+        Ok(Self {
+            level: control & 0x07,
+            key_identifier,
+            frame_counter: u32::from_le_bytes(frame_counter),
+            source,
+            key_sequence,
+        })
+    }
+
+    /// Encode the header into `data`, returns the length
+    pub fn encode(&self, data: &mut [u8]) -> usize {
+        assert!(data.len() >= self.length());
+        data[0] = self.control(self.level);
+        data[1..5].copy_from_slice(&self.frame_counter.to_le_bytes());
+        let mut offset = 5;
+        if let Some(source) = self.source {
+            data[offset..offset + 8].copy_from_slice(&source.to_le_bytes());
+            offset += 8;
+        }
+        if let Some(sequence) = self.key_sequence {
+            data[offset] = sequence;
+            offset += 1;
+        }
+        offset
+    }
+
+    /// Create the nonce for the security `level`
+    ///
+    /// `source` is used if the header does not contain the source address.
+    pub fn nonce(&self, source: Option<u64>, level: u8) -> Result<[u8; NONCE_SIZE], Error> {
+        match self.source.or(source) {
+            Some(source) => Ok(make_nonce(source, self.frame_counter, self.control(level))),
+            None => Err(Error::NoSourceAddress),
+        }
+    }
+}
+
+/// Create the CCM* nonce
+///
+/// The nonce is the extended source address, the frame counter and the
+/// security control byte, with the security level substituted.
+pub fn make_nonce(source: u64, frame_counter: u32, control: u8) -> [u8; NONCE_SIZE] {
+    let mut nonce = [0u8; NONCE_SIZE];
+    nonce[..8].copy_from_slice(&source.to_le_bytes());
+    nonce[8..12].copy_from_slice(&frame_counter.to_le_bytes());
+    nonce[12] = control;
+    nonce
+}
+
+/// Length of the NWK header, up to the auxiliary security header
+pub fn nwk_header_length(frame: &[u8]) -> Result<usize, Error> {
+    if frame.len() < 8 {
+        return Err(Error::InvalidFrame);
+    }
+    let control = u16::from(frame[0]) | (u16::from(frame[1]) << 8);
+    // Frame control, destination, source, radius and sequence number
+    let mut length = 8;
+    if control & 0x0800 == 0x0800 {
+        // Destination IEEE address
+        length += 8;
+    }
+    if control & 0x1000 == 0x1000 {
+        // Source IEEE address
+        length += 8;
+    }
+    if control & 0x0100 == 0x0100 {
+        // Multicast control
+        length += 1;
+    }
+    if control & 0x0400 == 0x0400 {
+        // Source route, relay count, relay index and relay list
+        if frame.len() <= length {
+            return Err(Error::InvalidFrame);
+        }
+        length += 2 + 2 * frame[length] as usize;
+    }
+    if frame.len() < length {
+        return Err(Error::InvalidFrame);
+    }
+    Ok(length)
+}
+
+/// Length of the APS header, up to the auxiliary security header
+pub fn aps_header_length(frame: &[u8]) -> Result<usize, Error> {
+    if frame.is_empty() {
+        return Err(Error::InvalidFrame);
+    }
+    let control = frame[0];
+    let frame_type = control & 0x03;
+    let delivery_mode = (control >> 2) & 0x03;
+    let command_ack = control & 0x10 == 0x10;
+    let mut length = 1;
+    let addressed = match frame_type {
+        // Data
+        0 => true,
+        // Command
+        1 => false,
+        // Acknowledgement, of a data frame or a command
+        2 => !command_ack,
+        _ => return Err(Error::InvalidFrame),
+    };
+    if addressed {
+        length += match delivery_mode {
+            // Destination endpoint
+            0 | 2 => 1,
+            // Group address
+            3 => 2,
+            _ => 0,
+        };
+        // Cluster identifier, profile identifier and source endpoint
+        length += 5;
+    }
+    // APS counter
+    length += 1;
+    if control & 0x80 == 0x80 {
+        if frame.len() <= length {
+            return Err(Error::InvalidFrame);
+        }
+        let fragmentation = frame[length] & 0x03;
+        length += 1;
+        if fragmentation != 0 {
+            // Block number, and the acknowledgement bit field
+            length += if frame_type == 2 { 2 } else { 1 };
+        }
+    }
+    if frame.len() < length {
+        return Err(Error::InvalidFrame);
+    }
+    Ok(length)
+}
+
+/// Generate the MIC for `aad` and compare it with `mic`
+fn validate_mic<B: CcmStarInPlace>(
+    backend: &mut B,
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    mic: &[u8],
+) -> Result<bool, Error> {
+    let mut tag = [0u8; BLOCK_SIZE];
+    backend.ccmstar_encrypt_in_place(key, nonce, &mut [], &mut tag[..mic.len()], aad)?;
+    let mut difference = 0u8;
+    for (a, b) in tag[..mic.len()].iter().zip(mic.iter()) {
+        difference |= a ^ b;
+    }
+    Ok(difference == 0)
+}
+
+/// Validate the MIC and decrypt the payload of the frame in `buffer`
+fn validate_payload<B: CcmStarInPlace>(
+    backend: &mut B,
+    key: &[u8],
+    nonce: &[u8],
+    buffer: &mut [u8],
+    open_length: usize,
+    payload_length: usize,
+    level: u8,
+) -> Result<bool, Error> {
+    let mic_length = mic_length(level);
+    if level & 0x04 == 0x04 && payload_length > 0 {
+        let (open, private) = buffer.split_at_mut(open_length);
+        let (payload, mic) = private.split_at_mut(payload_length);
+        backend.ccmstar_decrypt_in_place(key, nonce, payload, mic, open)
+    } else if mic_length > 0 {
+        let aad_length = if level & 0x04 == 0x04 {
+            open_length
+        } else {
+            open_length + payload_length
+        };
+        let (aad, mic) = buffer.split_at(aad_length);
+        validate_mic(backend, key, nonce, aad, mic)
+    } else {
+        Ok(true)
+    }
+}
+
+/// Secure the frame in `buffer`
+///
+/// `buffer` holds a header of `header_length` bytes followed by a payload of
+/// `payload_length` bytes. The auxiliary security header is inserted after
+/// the header, the payload is secured with the security `level` and the MIC
+/// is appended. The header must already have its security bit set.
+///
+/// Returns the length of the secured frame.
+#[allow(clippy::too_many_arguments)]
+pub fn secure<B: CcmStarInPlace>(
+    backend: &mut B,
+    key: &[u8],
+    source: u64,
+    buffer: &mut [u8],
+    header_length: usize,
+    payload_length: usize,
+    security: &SecurityHeader,
+    level: u8,
+) -> Result<usize, Error> {
+    let mic_length = mic_length(level);
+    let open_length = header_length + security.length();
+    let secured_length = open_length + payload_length + mic_length;
+    if secured_length > MAX_FRAME_SIZE || buffer.len() < secured_length {
+        return Err(Error::InvalidFrame);
+    }
+    let nonce = security.nonce(Some(source), level)?;
+
+    buffer.copy_within(header_length..header_length + payload_length, open_length);
+    // The security level is substituted in the additional data
+    let mut substituted = *security;
+    substituted.level = level;
+    substituted.encode(&mut buffer[header_length..]);
+
+    let (open, private) = buffer.split_at_mut(open_length);
+    let (payload, mic) = private.split_at_mut(payload_length);
+    let mic = &mut mic[..mic_length];
+    if level & 0x04 == 0x04 {
+        backend.ccmstar_encrypt_in_place(key, &nonce, payload, mic, open)?;
+    } else {
+        let (aad, mic) = buffer.split_at_mut(open_length + payload_length);
+        backend.ccmstar_encrypt_in_place(key, &nonce, &mut [], &mut mic[..mic_length], aad)?;
+    }
+    // Transmit the security level as given in the header
+    security.encode(&mut buffer[header_length..]);
+    Ok(secured_length)
+}
+
+/// Unsecure the frame in `buffer`
+///
+/// `buffer` holds the secured frame with a header of `header_length` bytes.
+/// The MIC is validated and the payload decrypted in place, using the
+/// security `level` of the network. `source` is used for the nonce if the
+/// auxiliary security header does not contain the source address.
+///
+/// Returns the auxiliary security header and the location of the payload in
+/// `buffer`.
+pub fn unsecure<B: CcmStarInPlace>(
+    backend: &mut B,
+    key: &[u8],
+    source: Option<u64>,
+    buffer: &mut [u8],
+    header_length: usize,
+    level: u8,
+) -> Result<(SecurityHeader, core::ops::Range<usize>), Error> {
+    if buffer.len() < header_length {
+        return Err(Error::InvalidFrame);
+    }
+    let security = SecurityHeader::decode(&buffer[header_length..])?;
+    let mic_length = mic_length(level);
+    let open_length = header_length + security.length();
+    if buffer.len() < open_length + mic_length {
+        return Err(Error::InvalidFrame);
+    }
+    let payload_length = buffer.len() - open_length - mic_length;
+    let nonce = security.nonce(source, level)?;
+
+    // The security level is substituted in the additional data
+    buffer[header_length] = security.control(level);
+    let valid = validate_payload(
+        backend,
+        key,
+        &nonce,
+        buffer,
+        open_length,
+        payload_length,
+        level,
+    );
+    // Put back the transmitted security level, also when unsecuring failed
+    buffer[header_length] = security.control(security.level);
+    if !valid? {
+        return Err(Error::InvalidMic);
+    }
+    Ok((security, open_length..open_length + payload_length))
+}
+
+/// Secure the NWK frame in `buffer`
+///
+/// Sets the security bit of the NWK header, see [`secure`]. The header is
+/// restored if securing fails, the payload may then be moved or partly
+/// encrypted.
+pub fn secure_nwk<B: CcmStarInPlace>(
+    backend: &mut B,
+    key: &[u8],
+    source: u64,
+    buffer: &mut [u8],
+    length: usize,
+    security: &SecurityHeader,
+    level: u8,
+) -> Result<usize, Error> {
+    if length > buffer.len() {
+        return Err(Error::InvalidFrame);
+    }
+    let header_length = nwk_header_length(&buffer[..length])?;
+    // The security bit is part of the additional data
+    let frame_control = buffer[1];
+    buffer[1] |= NWK_SECURITY;
+    let result = secure(
+        backend,
+        key,
+        source,
+        buffer,
+        header_length,
+        length - header_length,
+        security,
+        level,
+    );
+    if result.is_err() {
+        buffer[1] = frame_control;
+    }
+    result
+}
+
+/// Unsecure the NWK frame in `buffer`, see [`unsecure`]
+pub fn unsecure_nwk<B: CcmStarInPlace>(
+    backend: &mut B,
+    key: &[u8],
+    source: Option<u64>,
+    buffer: &mut [u8],
+    level: u8,
+) -> Result<(SecurityHeader, core::ops::Range<usize>), Error> {
+    let header_length = nwk_header_length(buffer)?;
+    if buffer[1] & NWK_SECURITY == 0 {
+        return Err(Error::InvalidFrame);
+    }
+    unsecure(backend, key, source, buffer, header_length, level)
+}
+
+/// Secure the APS frame in `buffer`
+///
+/// Sets the security bit of the APS header, see [`secure`]. The header is
+/// restored if securing fails, the payload may then be moved or partly
+/// encrypted.
+pub fn secure_aps<B: CcmStarInPlace>(
+    backend: &mut B,
+    key: &[u8],
+    source: u64,
+    buffer: &mut [u8],
+    length: usize,
+    security: &SecurityHeader,
+    level: u8,
+) -> Result<usize, Error> {
+    if length > buffer.len() {
+        return Err(Error::InvalidFrame);
+    }
+    let header_length = aps_header_length(&buffer[..length])?;
+    // The security bit is part of the additional data
+    let frame_control = buffer[0];
+    buffer[0] |= APS_SECURITY;
+    let result = secure(
+        backend,
+        key,
+        source,
+        buffer,
+        header_length,
+        length - header_length,
+        security,
+        level,
+    );
+    if result.is_err() {
+        buffer[0] = frame_control;
+    }
+    result
+}
+
+/// Unsecure the APS frame in `buffer`, see [`unsecure`]
+pub fn unsecure_aps<B: CcmStarInPlace>(
+    backend: &mut B,
+    key: &[u8],
+    source: Option<u64>,
+    buffer: &mut [u8],
+    level: u8,
+) -> Result<(SecurityHeader, core::ops::Range<usize>), Error> {
+    let header_length = aps_header_length(buffer)?;
+    if buffer[0] & APS_SECURITY == 0 {
+        return Err(Error::InvalidFrame);
+    }
+    unsecure(backend, key, source, buffer, header_length, level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ccmstar::tests::SoftwareCcmStar;
+
+    // No captured traffic is available for these tests, the secured frames
+    // are computed with the AES-CCM implementation of the Python
+    // cryptography package, using the Zigbee nonce and substituted security
+    // level.
+
+    /// Default trust center link key, "ZigBeeAlliance09"
+    const KEY: [u8; 16] = *b"ZigBeeAlliance09";
+    const NWK_SOURCE: u64 = 0x0017_8801_0011_2233;
+    const APS_SOURCE: u64 = 0x0017_8801_0044_5566;
+    /// NWK data frame, with its header
+    const NWK_HEADER: [u8; 8] = [0x48, 0x00, 0x00, 0x00, 0x34, 0x12, 0x1e, 0x42];
+    const NWK_PAYLOAD: [u8; 10] = [0x40, 0x0a, 0x06, 0x00, 0x04, 0x01, 0x01, 0x12, 0x18, 0x12];
+    /// The NWK frame secured with the network key, extended nonce
+    const NWK_SECURED: [u8; 36] = [
+        0x48, 0x02, 0x00, 0x00, 0x34, 0x12, 0x1e, 0x42, 0x28, 0x01, 0x01, 0x00, 0x00, 0x33, 0x22,
+        0x11, 0x00, 0x01, 0x88, 0x17, 0x00, 0x00, 0x25, 0x9d, 0xfe, 0xf8, 0x85, 0x5d, 0x7a, 0x47,
+        0x3f, 0xe1, 0x8c, 0x68, 0x44, 0x63,
+    ];
+    /// The NWK frame secured with the network key, without the source address
+    const NWK_SECURED_SHORT: [u8; 28] = [
+        0x48, 0x02, 0x00, 0x00, 0x34, 0x12, 0x1e, 0x42, 0x08, 0x02, 0x01, 0x00, 0x00, 0x00, 0xdc,
+        0xb4, 0xe8, 0x0e, 0xa6, 0xb7, 0x9a, 0x7a, 0xc5, 0x41, 0xdd, 0x14, 0x5c, 0x09,
+    ];
+    /// APS transport key command, with its header
+    const APS_HEADER: [u8; 2] = [0x01, 0x10];
+    const APS_PAYLOAD: [u8; 18] = [
+        0x05, 0x01, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc,
+        0xdd, 0xee, 0xff,
+    ];
+    /// The APS command secured with the link key, extended nonce
+    const APS_SECURED: [u8; 37] = [
+        0x21, 0x10, 0x20, 0x02, 0x00, 0x00, 0x00, 0x66, 0x55, 0x44, 0x00, 0x01, 0x88, 0x17, 0x00,
+        0x6b, 0x30, 0xd7, 0xae, 0xf6, 0xe5, 0x8e, 0xea, 0xab, 0x22, 0xf9, 0x37, 0x77, 0x7c, 0x2d,
+        0x81, 0x16, 0x61, 0xc6, 0x9a, 0xe7, 0x11,
+    ];
+
+    /// Backend failing every operation
+    struct FailingCcmStar;
+
+    impl CcmStarInPlace for FailingCcmStar {
+        fn ccmstar_encrypt_in_place(
+            &mut self,
+            _key: &[u8],
+            _nonce: &[u8],
+            _data: &mut [u8],
+            _mic: &mut [u8],
+            _aad: &[u8],
+        ) -> Result<(), Error> {
+            Err(Error::Busy)
+        }
+
+        fn ccmstar_decrypt_in_place(
+            &mut self,
+            _key: &[u8],
+            _nonce: &[u8],
+            _data: &mut [u8],
+            _mic: &[u8],
+            _aad: &[u8],
+        ) -> Result<bool, Error> {
+            Err(Error::Busy)
+        }
+    }
+
+    fn nwk_security() -> SecurityHeader {
+        SecurityHeader {
+            level: 0,
+            key_identifier: KeyIdentifier::Network,
+            frame_counter: 0x101,
+            source: Some(NWK_SOURCE),
+            key_sequence: Some(0),
+        }
+    }
+
+    fn aps_security() -> SecurityHeader {
+        SecurityHeader {
+            level: 0,
+            key_identifier: KeyIdentifier::Data,
+            frame_counter: 2,
+            source: Some(APS_SOURCE),
+            key_sequence: None,
+        }
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let headers = [
+            (nwk_security(), &NWK_SECURED[8..22]),
+            (
+                SecurityHeader {
+                    level: 0,
+                    key_identifier: KeyIdentifier::Network,
+                    frame_counter: 0x102,
+                    source: None,
+                    key_sequence: Some(0),
+                },
+                &NWK_SECURED_SHORT[8..14],
+            ),
+            (aps_security(), &APS_SECURED[2..15]),
+        ];
+        for (security, encoded) in headers.iter() {
+            assert_eq!(security.length(), encoded.len());
+            assert_eq!(SecurityHeader::decode(encoded), Ok(*security));
+            let mut buffer = [0u8; 14];
+            let length = security.encode(&mut buffer);
+            assert_eq!(&buffer[..length], *encoded);
+        }
+    }
+
+    #[test]
+    fn header_truncated() {
+        assert_eq!(
+            SecurityHeader::decode(&NWK_SECURED[8..12]),
+            Err(Error::InvalidFrame)
+        );
+        // Extended nonce with a short source address
+        assert_eq!(
+            SecurityHeader::decode(&APS_SECURED[2..14]),
+            Err(Error::InvalidFrame)
+        );
+        // Network key without the key sequence number
+        assert_eq!(
+            SecurityHeader::decode(&NWK_SECURED[8..21]),
+            Err(Error::InvalidFrame)
+        );
+    }
+
+    #[test]
+    fn nonce() {
+        assert_eq!(
+            nwk_security().nonce(None, DEFAULT_SECURITY_LEVEL),
+            Ok([0x33, 0x22, 0x11, 0x00, 0x01, 0x88, 0x17, 0x00, 0x01, 0x01, 0x00, 0x00, 0x2d])
+        );
+        assert_eq!(
+            aps_security().nonce(None, DEFAULT_SECURITY_LEVEL),
+            Ok([0x66, 0x55, 0x44, 0x00, 0x01, 0x88, 0x17, 0x00, 0x02, 0x00, 0x00, 0x00, 0x25])
+        );
+        let mut security = nwk_security();
+        security.source = None;
+        // The source address of the header takes precedence
+        assert_eq!(
+            nwk_security().nonce(Some(APS_SOURCE), DEFAULT_SECURITY_LEVEL),
+            nwk_security().nonce(None, DEFAULT_SECURITY_LEVEL)
+        );
+        assert_eq!(
+            security.nonce(Some(NWK_SOURCE), DEFAULT_SECURITY_LEVEL),
+            Ok(make_nonce(NWK_SOURCE, 0x101, 0x0d))
+        );
+        assert_eq!(
+            security.nonce(None, DEFAULT_SECURITY_LEVEL),
+            Err(Error::NoSourceAddress)
+        );
+    }
+
+    #[test]
+    fn header_lengths() {
+        assert_eq!(nwk_header_length(&NWK_SECURED), Ok(8));
+        assert_eq!(aps_header_length(&APS_SECURED), Ok(2));
+        // NWK header with both IEEE addresses
+        let mut frame = [0u8; 24];
+        frame[1] = 0x18;
+        assert_eq!(nwk_header_length(&frame), Ok(24));
+        assert_eq!(nwk_header_length(&frame[..23]), Err(Error::InvalidFrame));
+        // APS data frame, unicast
+        assert_eq!(aps_header_length(&[0x40, 1, 6, 0, 4, 1, 1, 0x12]), Ok(8));
+        // APS data frame, group addressed
+        assert_eq!(aps_header_length(&[0x0c, 1, 0, 6, 0, 4, 1, 1, 0x12]), Ok(9));
+        // APS acknowledgement of a command
+        assert_eq!(aps_header_length(&[0x12, 0x10]), Ok(2));
+        assert_eq!(aps_header_length(&[0x03]), Err(Error::InvalidFrame));
+    }
+
+    #[test]
+    fn secure_nwk_frame() {
+        let mut buffer = [0u8; MAX_FRAME_SIZE];
+        buffer[..8].copy_from_slice(&NWK_HEADER);
+        buffer[8..18].copy_from_slice(&NWK_PAYLOAD);
+        let length = secure_nwk(
+            &mut SoftwareCcmStar,
+            &KEY,
+            NWK_SOURCE,
+            &mut buffer,
+            18,
+            &nwk_security(),
+            DEFAULT_SECURITY_LEVEL,
+        )
+        .unwrap();
+        assert_eq!(&buffer[..length], &NWK_SECURED[..]);
+    }
+
+    #[test]
+    fn unsecure_nwk_frame() {
+        let mut buffer = NWK_SECURED;
+        let result = unsecure_nwk(
+            &mut SoftwareCcmStar,
+            &KEY,
+            None,
+            &mut buffer,
+            DEFAULT_SECURITY_LEVEL,
+        );
+        assert_eq!(result, Ok((nwk_security(), 22..32)));
+        assert_eq!(&buffer[..22], &NWK_SECURED[..22]);
+        assert_eq!(&buffer[22..32], &NWK_PAYLOAD[..]);
+
+        let mut buffer = NWK_SECURED_SHORT;
+        let result = unsecure_nwk(
+            &mut SoftwareCcmStar,
+            &KEY,
+            Some(NWK_SOURCE),
+            &mut buffer,
+            DEFAULT_SECURITY_LEVEL,
+        );
+        assert_eq!(result.map(|(_, payload)| payload), Ok(14..24));
+        assert_eq!(&buffer[14..24], &NWK_PAYLOAD[..]);
+
+        let mut buffer = NWK_SECURED_SHORT;
+        let result = unsecure_nwk(
+            &mut SoftwareCcmStar,
+            &KEY,
+            None,
+            &mut buffer,
+            DEFAULT_SECURITY_LEVEL,
+        );
+        assert_eq!(result, Err(Error::NoSourceAddress));
+    }
+
+    #[test]
+    fn secure_aps_frame() {
+        let mut buffer = [0u8; MAX_FRAME_SIZE];
+        buffer[..2].copy_from_slice(&APS_HEADER);
+        buffer[2..20].copy_from_slice(&APS_PAYLOAD);
+        let length = secure_aps(
+            &mut SoftwareCcmStar,
+            &KEY,
+            APS_SOURCE,
+            &mut buffer,
+            20,
+            &aps_security(),
+            DEFAULT_SECURITY_LEVEL,
+        )
+        .unwrap();
+        assert_eq!(&buffer[..length], &APS_SECURED[..]);
+    }
+
+    #[test]
+    fn unsecure_aps_frame() {
+        let mut buffer = APS_SECURED;
+        let result = unsecure_aps(
+            &mut SoftwareCcmStar,
+            &KEY,
+            None,
+            &mut buffer,
+            DEFAULT_SECURITY_LEVEL,
+        );
+        assert_eq!(result, Ok((aps_security(), 15..33)));
+        assert_eq!(&buffer[15..33], &APS_PAYLOAD[..]);
+    }
+
+    #[test]
+    fn unsecure_tampered() {
+        // Header, auxiliary security header, payload and MIC
+        for &position in [0, 5, 16, 24, 35].iter() {
+            let mut buffer = NWK_SECURED;
+            buffer[position] ^= 0x80;
+            let result = unsecure_nwk(
+                &mut SoftwareCcmStar,
+                &KEY,
+                None,
+                &mut buffer,
+                DEFAULT_SECURITY_LEVEL,
+            );
+            assert_eq!(result, Err(Error::InvalidMic));
+        }
+        let mut key = KEY;
+        key[15] ^= 0x01;
+        let mut buffer = APS_SECURED;
+        let result = unsecure_aps(
+            &mut SoftwareCcmStar,
+            &key,
+            None,
+            &mut buffer,
+            DEFAULT_SECURITY_LEVEL,
+        );
+        assert_eq!(result, Err(Error::InvalidMic));
+    }
+
+    #[test]
+    fn unsecure_not_secured() {
+        let mut buffer = [0u8; 18];
+        buffer[..8].copy_from_slice(&NWK_HEADER);
+        buffer[8..].copy_from_slice(&NWK_PAYLOAD);
+        let result = unsecure_nwk(
+            &mut SoftwareCcmStar,
+            &KEY,
+            Some(NWK_SOURCE),
+            &mut buffer,
+            DEFAULT_SECURITY_LEVEL,
+        );
+        assert_eq!(result, Err(Error::InvalidFrame));
+    }
+
+    #[test]
+    fn secure_length_too_long() {
+        let mut buffer = [0u8; 18];
+        buffer[..8].copy_from_slice(&NWK_HEADER);
+        buffer[8..].copy_from_slice(&NWK_PAYLOAD);
+        let result = secure_nwk(
+            &mut SoftwareCcmStar,
+            &KEY,
+            NWK_SOURCE,
+            &mut buffer,
+            19,
+            &nwk_security(),
+            DEFAULT_SECURITY_LEVEL,
+        );
+        assert_eq!(result, Err(Error::InvalidFrame));
+        let result = secure_aps(
+            &mut SoftwareCcmStar,
+            &KEY,
+            APS_SOURCE,
+            &mut buffer,
+            19,
+            &aps_security(),
+            DEFAULT_SECURITY_LEVEL,
+        );
+        assert_eq!(result, Err(Error::InvalidFrame));
+    }
+
+    #[test]
+    fn secure_failure_keeps_header() {
+        let mut buffer = [0u8; MAX_FRAME_SIZE];
+        buffer[..8].copy_from_slice(&NWK_HEADER);
+        buffer[8..18].copy_from_slice(&NWK_PAYLOAD);
+        let result = secure_nwk(
+            &mut FailingCcmStar,
+            &KEY,
+            NWK_SOURCE,
+            &mut buffer,
+            18,
+            &nwk_security(),
+            DEFAULT_SECURITY_LEVEL,
+        );
+        assert_eq!(result, Err(Error::Busy));
+        assert_eq!(&buffer[..8], &NWK_HEADER[..]);
+
+        let mut buffer = [0u8; MAX_FRAME_SIZE];
+        buffer[..2].copy_from_slice(&APS_HEADER);
+        buffer[2..20].copy_from_slice(&APS_PAYLOAD);
+        let result = secure_aps(
+            &mut FailingCcmStar,
+            &KEY,
+            APS_SOURCE,
+            &mut buffer,
+            20,
+            &aps_security(),
+            DEFAULT_SECURITY_LEVEL,
+        );
+        assert_eq!(result, Err(Error::Busy));
+        assert_eq!(&buffer[..2], &APS_HEADER[..]);
+    }
+
+    #[test]
+    fn unsecure_failure_keeps_header() {
+        let mut buffer = NWK_SECURED;
+        let result = unsecure_nwk(
+            &mut FailingCcmStar,
+            &KEY,
+            None,
+            &mut buffer,
+            DEFAULT_SECURITY_LEVEL,
+        );
+        assert_eq!(result, Err(Error::Busy));
+        assert_eq!(&buffer[..22], &NWK_SECURED[..22]);
+
+        let mut buffer = APS_SECURED;
+        let result = unsecure_aps(
+            &mut FailingCcmStar,
+            &KEY,
+            None,
+            &mut buffer,
+            DEFAULT_SECURITY_LEVEL,
+        );
+        assert_eq!(result, Err(Error::Busy));
+        assert_eq!(&buffer[..15], &APS_SECURED[..15]);
+    }
+}
@@ -1,86 +1,86 @@
 
 #define CRYPTOCELL_ARCH_BASE_ADDRESS 0x5002A000
 
-// #include "nrf_cc310/include/ccsw_crys_rsa_shared_types.h"
-#include "nrf_cc310/include/crys_aesccm_error.h"
-#include "nrf_cc310/include/crys_aesccm.h"
-// #include "nrf_cc310/include/crys_chacha_error.h"
-// #include "nrf_cc310/include/crys_chacha.h"
-// #include "nrf_cc310/include/crys_chacha_poly_error.h"
-// #include "nrf_cc310/include/crys_chacha_poly.h"
-// #include "nrf_cc310/include/crys_common_error.h"
-// #include "nrf_cc310/include/crys_common.h"
-// #include "nrf_cc310/include/crys_dh_error.h"
-// #include "nrf_cc310/include/crys_dh.h"
-// #include "nrf_cc310/include/crys_dh_kg.h"
-// #include "nrf_cc310/include/crys_ec_edw_api.h"
-// #include "nrf_cc310/include/crys_ec_mont_api.h"
-// #include "nrf_cc310/include/crys_ec_mont_edw_error.h"
-// #include "nrf_cc310/include/crys_ecpki_build.h"
-// #include "nrf_cc310/include/crys_ecpki_dh.h"
-// #include "nrf_cc310/include/crys_ecpki_domain.h"
-// #include "nrf_cc310/include/crys_ecpki_ecdsa.h"
-// #include "nrf_cc310/include/crys_ecpki_error.h"
-// #include "nrf_cc310/include/crys_ecpki_kg.h"
-// #include "nrf_cc310/include/crys_ecpki_types.h"
-// #include "nrf_cc310/include/crys_error.h"
-// #include "nrf_cc310/include/crys_hash_defs.h"
-// #include "nrf_cc310/include/crys_hash_error.h"
-// #include "nrf_cc310/include/crys_hash.h"
-// #include "nrf_cc310/include/crys_hkdf_error.h"
-// #include "nrf_cc310/include/crys_hkdf.h"
-// #include "nrf_cc310/include/crys_hmac_defs.h"
-// #include "nrf_cc310/include/crys_hmac_error.h"
-// #include "nrf_cc310/include/crys_hmac.h"
-// #include "nrf_cc310/include/crys_kdf_error.h"
-// #include "nrf_cc310/include/crys_kdf.h"
-// #include "nrf_cc310/include/crys_pka_defs_hw.h"
-// #include "nrf_cc310/include/crys_poly_error.h"
-// #include "nrf_cc310/include/crys_poly.h"
-// #include "nrf_cc310/include/crys_rnd_error.h"
-#include "nrf_cc310/include/crys_rnd.h"
-// #include "nrf_cc310/include/crys_rsa_build.h"
-// #include "nrf_cc310/include/crys_rsa_error.h"
-// #include "nrf_cc310/include/crys_rsa_kg.h"
-// #include "nrf_cc310/include/crys_rsa_prim.h"
-// #include "nrf_cc310/include/crys_rsa_schemes.h"
-// #include "nrf_cc310/include/crys_rsa_types.h"
-// #include "nrf_cc310/include/crys_srp_error.h"
-// #include "nrf_cc310/include/crys_srp.h"
-// #include "nrf_cc310/include/dx_reg_base_host.h"
-#include "nrf_cc310/include/sns_silib.h"
-// #include "nrf_cc310/include/ssi_aes_defs.h"
-// #include "nrf_cc310/include/ssi_aes_error.h"
-#include "nrf_cc310/include/ssi_aes.h"
-// #include "nrf_cc310/include/ssi_bitops.h"
-// #include "nrf_cc310/include/ssi_pal_abort.h"
-// #include "nrf_cc310/include/ssi_pal_barrier.h"
-// #include "nrf_cc310/include/ssi_pal_compiler.h"
-// #include "nrf_cc310/include/ssi_pal_dma_defs.h"
-// #include "nrf_cc310/include/ssi_pal_dma.h"
-// #include "nrf_cc310/include/ssi_pal_dma_plat.h"
-// #include "nrf_cc310/include/ssi_pal_error.h"
-// #include "nrf_cc310/include/ssi_pal_file.h"
-// #include "nrf_cc310/include/ssi_pal_file_plat.h"
-// #include "nrf_cc310/include/ssi_pal_fips.h"
-// #include "nrf_cc310/include/ssi_pal_init.h"
-// #include "nrf_cc310/include/ssi_pal_list.h"
-// #include "nrf_cc310/include/ssi_pal_log.h"
-// #include "nrf_cc310/include/ssi_pal_mem.h"
-// #include "nrf_cc310/include/ssi_pal_memmap.h"
-// #include "nrf_cc310/include/ssi_pal_mutex.h"
-// #include "nrf_cc310/include/ssi_pal_mutex_plat.h"
-// #include "nrf_cc310/include/ssi_pal_perf.h"
-// #include "nrf_cc310/include/ssi_pal_perf_plat.h"
-// #include "nrf_cc310/include/ssi_pal_sem.h"
-// #include "nrf_cc310/include/ssi_pal_sem_plat.h"
-// #include "nrf_cc310/include/ssi_pal_trng.h"
-// #include "nrf_cc310/include/ssi_pal_types.h"
-// #include "nrf_cc310/include/ssi_pal_types_plat.h"
-// #include "nrf_cc310/include/ssi_pka_hw_plat_defs.h"
-// #include "nrf_cc310/include/ssi_regs.h"
-// #include "nrf_cc310/include/ssi_sram_map.h"
-// #include "nrf_cc310/include/ssi_util_defs.h"
-// #include "nrf_cc310/include/ssi_util_error.h"
-// #include "nrf_cc310/include/ssi_util_key_derivation_defs.h"
-// #include "nrf_cc310/include/ssi_util_key_derivation.h"
+// #include "ccsw_crys_rsa_shared_types.h"
+#include "crys_aesccm_error.h"
+#include "crys_aesccm.h"
+// #include "crys_chacha_error.h"
+// #include "crys_chacha.h"
+// #include "crys_chacha_poly_error.h"
+// #include "crys_chacha_poly.h"
+// #include "crys_common_error.h"
+// #include "crys_common.h"
+// #include "crys_dh_error.h"
+// #include "crys_dh.h"
+// #include "crys_dh_kg.h"
+#include "crys_ec_edw_api.h"
+// #include "crys_ec_mont_api.h"
+#include "crys_ec_mont_edw_error.h"
+#include "crys_ecpki_build.h"
+// #include "crys_ecpki_dh.h"
+#include "crys_ecpki_domain.h"
+#include "crys_ecpki_ecdsa.h"
+#include "crys_ecpki_error.h"
+// #include "crys_ecpki_kg.h"
+// #include "crys_ecpki_types.h"
+#include "crys_error.h"
+// #include "crys_hash_defs.h"
+// #include "crys_hash_error.h"
+#include "crys_hash.h"
+// #include "crys_hkdf_error.h"
+// #include "crys_hkdf.h"
+// #include "crys_hmac_defs.h"
+// #include "crys_hmac_error.h"
+// #include "crys_hmac.h"
+// #include "crys_kdf_error.h"
+// #include "crys_kdf.h"
+// #include "crys_pka_defs_hw.h"
+// #include "crys_poly_error.h"
+// #include "crys_poly.h"
+#include "crys_rnd_error.h"
+#include "crys_rnd.h"
+// #include "crys_rsa_build.h"
+// #include "crys_rsa_error.h"
+// #include "crys_rsa_kg.h"
+// #include "crys_rsa_prim.h"
+// #include "crys_rsa_schemes.h"
+// #include "crys_rsa_types.h"
+// #include "crys_srp_error.h"
+// #include "crys_srp.h"
+// #include "dx_reg_base_host.h"
+#include "sns_silib.h"
+// #include "ssi_aes_defs.h"
+#include "ssi_aes_error.h"
+#include "ssi_aes.h"
+// #include "ssi_bitops.h"
+// #include "ssi_pal_abort.h"
+// #include "ssi_pal_barrier.h"
+// #include "ssi_pal_compiler.h"
+// #include "ssi_pal_dma_defs.h"
+// #include "ssi_pal_dma.h"
+// #include "ssi_pal_dma_plat.h"
+// #include "ssi_pal_error.h"
+// #include "ssi_pal_file.h"
+// #include "ssi_pal_file_plat.h"
+// #include "ssi_pal_fips.h"
+// #include "ssi_pal_init.h"
+// #include "ssi_pal_list.h"
+// #include "ssi_pal_log.h"
+// #include "ssi_pal_mem.h"
+// #include "ssi_pal_memmap.h"
+// #include "ssi_pal_mutex.h"
+// #include "ssi_pal_mutex_plat.h"
+// #include "ssi_pal_perf.h"
+// #include "ssi_pal_perf_plat.h"
+// #include "ssi_pal_sem.h"
+// #include "ssi_pal_sem_plat.h"
+// #include "ssi_pal_trng.h"
+// #include "ssi_pal_types.h"
+// #include "ssi_pal_types_plat.h"
+// #include "ssi_pka_hw_plat_defs.h"
+// #include "ssi_regs.h"
+// #include "ssi_sram_map.h"
+#include "ssi_util_defs.h"
+// #include "ssi_util_error.h"
+#include "ssi_util_key_derivation_defs.h"
+#include "ssi_util_key_derivation.h"
@@ -0,0 +1,49 @@
+//! Channel hopping
+
+use nrf52840_pac as pac;
+
+use crate::protocol::next_channel;
+
+/// Channel hopping, using TIMER2 for the dwell time
+pub(crate) struct Hopper {
+    timer: pac::TIMER2,
+    channels: u32,
+}
+
+impl Hopper {
+    pub fn new(timer: pac::TIMER2) -> Self {
+        timer.mode.write(|w| w.mode().timer());
+        timer.bitmode.write(|w| w.bitmode()._32bit());
+        timer.prescaler.write(|w| unsafe { w.prescaler().bits(4) });
+        timer.shorts.write(|w| w.compare0_clear().enabled());
+        timer.intenset.write(|w| w.compare0().set());
+        Self { timer, channels: 0 }
+    }
+
+    /// Hop across `channels`, bit n set for channel n, staying `dwell`
+    /// milliseconds on each channel
+    pub fn start(&mut self, channels: u32, dwell: u16) {
+        self.stop();
+        self.channels = channels;
+        self.timer.cc[0].write(|w| unsafe { w.bits(u32::from(dwell) * 1_000) });
+        self.timer.tasks_start.write(|w| unsafe { w.bits(1) });
+    }
+
+    pub fn stop(&mut self) {
+        self.timer.tasks_stop.write(|w| unsafe { w.bits(1) });
+        self.timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+        self.timer.events_compare[0].reset();
+        self.channels = 0;
+    }
+
+    pub fn is_hopping(&self) -> bool {
+        self.channels != 0
+    }
+
+    /// Acknowledge the dwell time event and get the channel to hop to from
+    /// `channel`
+    pub fn next_channel(&mut self, channel: u8) -> u8 {
+        self.timer.events_compare[0].reset();
+        next_channel(self.channels, channel)
+    }
+}
@@ -0,0 +1,119 @@
+//! Link to the host over the UART
+
+use nrf52840_hal::{uarte, Timer};
+use nrf52840_pac as pac;
+
+use psila_nrf52::radio::MAX_PACKET_LENGHT;
+
+use crate::protocol::{Command, FrameReader, Response, CAPTURE_HEADER_SIZE, HOST_BUFFER_SIZE};
+
+/// Time to wait for data from the host, in microseconds
+const HOST_READ_TIMEOUT: u32 = 1_000;
+/// Size of the largest encoded message, a captured frame
+const PACKET_SIZE: usize = (CAPTURE_HEADER_SIZE + MAX_PACKET_LENGHT) * 2;
+
+/// Error sending a message to the host
+#[derive(Debug)]
+pub enum Error {
+    /// The message couldn't be encoded
+    Encode,
+    /// Writing to the UART failed
+    Uart(uarte::Error),
+}
+
+/// Esercom messages to and from the host
+pub struct Host {
+    uart: uarte::Uarte<pac::UARTE0>,
+    timer: Timer<pac::TIMER0>,
+    reader: FrameReader,
+    frame: [u8; HOST_BUFFER_SIZE],
+    message: [u8; HOST_BUFFER_SIZE],
+    packet: [u8; PACKET_SIZE],
+}
+
+impl Host {
+    pub fn new(uart: uarte::Uarte<pac::UARTE0>, timer: pac::TIMER0) -> Self {
+        Self {
+            uart,
+            timer: Timer::new(timer),
+            reader: FrameReader::new(),
+            frame: [0u8; HOST_BUFFER_SIZE],
+            message: [0u8; HOST_BUFFER_SIZE],
+            packet: [0u8; PACKET_SIZE],
+        }
+    }
+
+    fn send(&mut self, message_type: esercom::MessageType, data: &[u8]) -> Result<(), Error> {
+        let written =
+            esercom::com_encode(message_type, data, &mut self.packet).map_err(|_| Error::Encode)?;
+        self.uart
+            .write(&self.packet[..written])
+            .map_err(Error::Uart)
+    }
+
+    /// Send a captured frame as a `RadioReceive` message
+    ///
+    /// `record` is the capture header followed by the packet, starting with
+    /// the length byte, as returned by [`Listener::receive`]. The length byte
+    /// is not sent.
+    ///
+    /// [`Listener::receive`]: crate::Listener::receive
+    pub fn send_capture(&mut self, record: &[u8]) -> Result<(), Error> {
+        let mut capture = [0u8; CAPTURE_HEADER_SIZE + MAX_PACKET_LENGHT];
+        let packet_length = record[CAPTURE_HEADER_SIZE] as usize;
+        // The metadata followed by the frame, without the length
+        let capture_length = CAPTURE_HEADER_SIZE + packet_length - 1;
+        capture[..CAPTURE_HEADER_SIZE].copy_from_slice(&record[..CAPTURE_HEADER_SIZE]);
+        capture[CAPTURE_HEADER_SIZE..capture_length]
+            .copy_from_slice(&record[CAPTURE_HEADER_SIZE + 1..CAPTURE_HEADER_SIZE + packet_length]);
+        self.send(
+            esercom::MessageType::RadioReceive,
+            &capture[..capture_length],
+        )
+    }
+
+    /// Send an `Acknowledge` message
+    pub fn send_response(&mut self, response: &Response) -> Result<(), Error> {
+        self.send(esercom::MessageType::Acknowledge, response.as_bytes())
+    }
+
+    /// Read from the host, waiting at most a millisecond for data
+    ///
+    /// Returns the sequence number and the next command, or the response to a
+    /// message that isn't a valid command. Messages that can't be decoded are
+    /// dropped.
+    ///
+    /// The UART only receives while this is called. Without flow control,
+    /// data sent by the host in between is lost, so the host must retry a
+    /// command that isn't acknowledged.
+    pub fn receive(&mut self) -> Option<Result<(u8, Command), Response>> {
+        let mut read = false;
+        let written = loop {
+            if let Some(length) = self.reader.next_frame(&mut self.frame) {
+                match esercom::com_decode(&self.frame[..length], &mut self.message) {
+                    Ok((esercom::MessageType::Command, _, written)) if written > 0 => {
+                        break written;
+                    }
+                    // Other messages are ignored, malformed messages dropped
+                    _ => continue,
+                }
+            }
+            if read {
+                return None;
+            }
+            read = true;
+            let space = self.reader.space();
+            let size = space.len();
+            let received = match self
+                .uart
+                .read_timeout(space, &mut self.timer, HOST_READ_TIMEOUT)
+            {
+                Ok(()) => size,
+                Err(uarte::Error::Timeout(count)) => count,
+                Err(_) => 0,
+            };
+            self.reader.received(received);
+        };
+        Some(Command::parse_message(&self.message[..written]))
+    }
+}
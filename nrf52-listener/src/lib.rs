@@ -0,0 +1,35 @@
+//! IEEE 802.15.4 listener
+//!
+//! The listener captures frames with the nRF52840 radio and sends them to a
+//! host over the UART, and takes commands from the host to change channel,
+//! hop across channels and transmit frames. The board examples only set up
+//! the clocks, the UART pins and the tasks, the rest is shared here.
+//!
+//! The [`protocol`] module has no device dependencies, disable the default
+//! `device` feature to use it on the host.
+//!
+//! The listener uses the following peripherals,
+//!
+//!  * RADIO, owned by the psila-nrf52 `Radio`
+//!  * TIMER1 for the receive timestamps, captured at the start of each frame
+//!    through PPI channel 0
+//!  * TIMER2 for the dwell time when hopping, see [`Listener::on_hop_timer`]
+//!  * TIMER0 and UARTE0 for the host link
+
+#![no_std]
+
+pub mod protocol;
+
+#[cfg(feature = "device")]
+mod hopper;
+#[cfg(feature = "device")]
+mod host;
+#[cfg(feature = "device")]
+mod listener;
+#[cfg(feature = "device")]
+mod transmitter;
+
+#[cfg(feature = "device")]
+pub use host::{Error, Host};
+#[cfg(feature = "device")]
+pub use listener::{Listener, DEFAULT_CHANNEL, DEFAULT_TX_POWER};
@@ -0,0 +1,220 @@
+//! Radio, timestamps and command execution
+
+use nrf52840_pac as pac;
+
+use psila_nrf52::radio::{Radio, MAX_PACKET_LENGHT};
+
+use crate::hopper::Hopper;
+use crate::protocol::{
+    lqi, CaptureHeader, Command, Response, STATUS_BUSY, STATUS_FAILED, STATUS_OK,
+};
+use crate::transmitter::Transmitter;
+
+/// Channel used at start-up
+pub const DEFAULT_CHANNEL: u8 = 15;
+/// Transmission power in dBm used at start-up
+pub const DEFAULT_TX_POWER: i8 = 8;
+
+/// Start receiving, sampling the RSSI when the SFD of a frame is received
+pub(crate) fn start_receive(radio: &mut Radio) {
+    radio.receive_prepare();
+    // Safety: Only enables a short, the other shorts set by `Radio` are kept
+    unsafe {
+        (*pac::RADIO::ptr())
+            .shorts
+            .modify(|_, w| w.address_rssistart().enabled())
+    };
+}
+
+/// The listener state shared by the radio and timer interrupts and the host
+/// loop
+pub struct Listener {
+    radio: Radio,
+    timer: pac::TIMER1,
+    hopper: Hopper,
+    transmitter: Transmitter,
+    /// Sequence number of the transmit command being executed
+    transmit_sequence: u8,
+    capturing: bool,
+    tx_power: i8,
+}
+
+impl Listener {
+    /// Set up the radio and the timers and start capturing on
+    /// [`DEFAULT_CHANNEL`]
+    pub fn new(
+        radio: pac::RADIO,
+        timer: pac::TIMER1,
+        hop_timer: pac::TIMER2,
+        ppi: &pac::PPI,
+    ) -> Self {
+        // Capture the start of each frame with a 1 MHz timer
+        timer.mode.write(|w| w.mode().timer());
+        timer.bitmode.write(|w| w.bitmode()._32bit());
+        timer.prescaler.write(|w| unsafe { w.prescaler().bits(4) });
+        timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+        timer.tasks_start.write(|w| unsafe { w.bits(1) });
+        ppi.ch[0]
+            .eep
+            .write(|w| unsafe { w.bits(&radio.events_framestart as *const _ as u32) });
+        ppi.ch[0]
+            .tep
+            .write(|w| unsafe { w.bits(&timer.tasks_capture[0] as *const _ as u32) });
+        ppi.chenset.write(|w| unsafe { w.bits(1 << 0) });
+
+        let mut radio = Radio::new(radio);
+        radio.set_channel(DEFAULT_CHANNEL);
+        radio.set_transmission_power(DEFAULT_TX_POWER);
+        start_receive(&mut radio);
+
+        Self {
+            radio,
+            timer,
+            hopper: Hopper::new(hop_timer),
+            transmitter: Transmitter::new(),
+            transmit_sequence: 0,
+            capturing: true,
+            tx_power: DEFAULT_TX_POWER,
+        }
+    }
+
+    /// Current time of the 1 MHz timestamp timer
+    fn now(&self) -> u32 {
+        self.timer.tasks_capture[1].write(|w| unsafe { w.bits(1) });
+        self.timer.cc[1].read().bits()
+    }
+
+    /// Capture metadata of the frame that was just received
+    ///
+    /// The timestamp is the TIMER1 value captured at the start of the frame.
+    /// The RSSI is sampled when the SFD is received.
+    fn capture_header(&self) -> CaptureHeader {
+        // Safety: Only reading status registers of the radio owned by `Radio`
+        let registers = unsafe { &*pac::RADIO::ptr() };
+        let rssi = -(registers.rssisample.read().rssisample().bits() as i8);
+        CaptureHeader {
+            timestamp: self.timer.cc[0].read().bits(),
+            channel: self.radio.get_channel(),
+            rssi,
+            lqi: lqi(rssi),
+            crc_ok: registers.crcstatus.read().crcstatus().is_crcok(),
+        }
+    }
+
+    /// Handle the RADIO interrupt
+    ///
+    /// Receives into `packet`, the length byte followed by the frame. Returns
+    /// the capture header and the length of the packet when capturing.
+    pub fn receive(
+        &mut self,
+        packet: &mut [u8; MAX_PACKET_LENGHT],
+    ) -> Option<(CaptureHeader, usize)> {
+        let result = self.radio.receive(packet);
+        let (captured, frame) = match result {
+            Ok(packet_len) if packet_len > 0 => {
+                let captured = if self.capturing {
+                    Some((self.capture_header(), packet_len))
+                } else {
+                    None
+                };
+                (captured, Some(&packet[1..packet_len]))
+            }
+            _ => (None, None),
+        };
+        let now = self.now();
+        let cca_busy = matches!(result, Err(psila_nrf52::radio::Error::CcaBusy));
+        self.transmitter
+            .on_radio_event(&mut self.radio, now, frame, cca_busy);
+        captured
+    }
+
+    /// Handle the TIMER2 interrupt, hop to the next channel
+    pub fn on_hop_timer(&mut self) {
+        let channel = self.hopper.next_channel(self.radio.get_channel());
+        // Stay on the channel while transmitting
+        if self.transmitter.is_idle() {
+            self.set_channel(channel);
+        }
+    }
+
+    fn set_channel(&mut self, channel: u8) {
+        self.radio.set_channel(channel);
+        start_receive(&mut self.radio);
+    }
+
+    /// Execute command `sequence` from the host
+    ///
+    /// Returns the response, or `None` when a transmission was started. The
+    /// transmission is acknowledged by [`poll`](Self::poll) when done.
+    pub fn execute(&mut self, sequence: u8, command: &Command) -> Option<Response> {
+        // The receive channel is restored when the transmission is done
+        if command.changes_channel() && !self.transmitter.is_idle() {
+            return Some(Response::new(sequence, command.id(), STATUS_BUSY));
+        }
+        let status = match *command {
+            Command::SetChannel(channel) => {
+                self.hopper.stop();
+                self.set_channel(channel);
+                STATUS_OK
+            }
+            Command::StartCapture => {
+                self.capturing = true;
+                STATUS_OK
+            }
+            Command::StopCapture => {
+                self.capturing = false;
+                STATUS_OK
+            }
+            Command::SetTxPower(power) => {
+                self.tx_power = power;
+                self.radio.set_transmission_power(power);
+                STATUS_OK
+            }
+            Command::Status => {
+                return Some(Response::status(
+                    sequence,
+                    self.capturing,
+                    self.radio.get_channel(),
+                    self.tx_power,
+                    self.hopper.is_hopping(),
+                ));
+            }
+            Command::StartHopping { channels, dwell } => {
+                self.set_channel(channels.trailing_zeros() as u8);
+                self.hopper.start(channels, dwell);
+                STATUS_OK
+            }
+            Command::StopHopping => {
+                self.hopper.stop();
+                STATUS_OK
+            }
+            Command::Transmit {
+                channel,
+                cca,
+                frame,
+            } => {
+                let now = self.now();
+                match self
+                    .transmitter
+                    .start(&mut self.radio, now, channel, cca, frame)
+                {
+                    Ok(()) => {
+                        self.transmit_sequence = sequence;
+                        return None;
+                    }
+                    Err(_) => STATUS_FAILED,
+                }
+            }
+        };
+        Some(Response::new(sequence, command.id(), status))
+    }
+
+    /// Acknowledge of a finished transmission, if any
+    pub fn poll(&mut self) -> Option<Response> {
+        let now = self.now();
+        let sequence = self.transmit_sequence;
+        self.transmitter
+            .poll(&mut self.radio, now)
+            .map(|outcome| Response::transmitted(sequence, outcome))
+    }
+}
@@ -0,0 +1,660 @@
+//! Host protocol
+//!
+//! Captured frames are sent to the host as `RadioReceive` messages, a
+//! [`CaptureHeader`] followed by the frame. The host controls the listener
+//! with `Command` messages, see [`Command::parse_message`], each command is
+//! answered with an `Acknowledge` message holding a [`Response`]. The host
+//! numbers the commands, the acknowledge holds the sequence number of the
+//! command it answers. Use [`FrameReader`] to
+//! split the data received from the host into messages.
+//!
+//! Nothing in this module uses the device.
+
+/// Size of the capture header in front of each frame
+pub const CAPTURE_HEADER_SIZE: usize = 8;
+/// Capture flag set when the CRC of the frame is valid
+pub const CAPTURE_FLAG_CRC_OK: u8 = 0x01;
+
+/// Channels that can be hopped across, bit n set for channel n
+pub const ALL_CHANNELS: u32 = 0x07ff_f800;
+/// Transmission power levels supported by the radio, in dBm
+pub const TX_POWER_LEVELS: [i8; 14] = [8, 7, 6, 5, 4, 3, 2, 0, -4, -8, -12, -16, -20, -40];
+/// Largest frame that can be transmitted
+pub const MAX_FRAME_SIZE: usize = 127;
+
+/// Host commands, the second byte of a `Command` message
+pub const COMMAND_SET_CHANNEL: u8 = 0x01;
+pub const COMMAND_START_CAPTURE: u8 = 0x02;
+pub const COMMAND_STOP_CAPTURE: u8 = 0x03;
+pub const COMMAND_SET_TX_POWER: u8 = 0x04;
+pub const COMMAND_STATUS: u8 = 0x05;
+pub const COMMAND_START_HOPPING: u8 = 0x06;
+pub const COMMAND_STOP_HOPPING: u8 = 0x07;
+pub const COMMAND_TRANSMIT: u8 = 0x08;
+
+/// Command status, the third byte of an `Acknowledge` message
+pub const STATUS_OK: u8 = 0x00;
+pub const STATUS_UNKNOWN_COMMAND: u8 = 0x01;
+pub const STATUS_INVALID_ARGUMENT: u8 = 0x02;
+pub const STATUS_BUSY: u8 = 0x03;
+pub const STATUS_FAILED: u8 = 0x04;
+
+/// Transmit flag to check that the channel is clear before transmitting
+pub const TRANSMIT_FLAG_CCA: u8 = 0x01;
+/// Transmission outcome, the fourth byte of the `Acknowledge` of a transmit
+pub const TRANSMIT_SENT: u8 = 0x00;
+pub const TRANSMIT_CCA_BUSY: u8 = 0x01;
+pub const TRANSMIT_NO_ACK: u8 = 0x02;
+pub const TRANSMIT_TIMEOUT: u8 = 0x03;
+
+/// Delimiter ending each message
+pub const FRAME_DELIMITER: u8 = 0x00;
+/// Size of the buffer for messages from the host, fits a transmit command
+pub const HOST_BUFFER_SIZE: usize = 256;
+
+/// Size of the largest response
+const RESPONSE_SIZE: usize = 7;
+
+/// Check that `channel` is a 2.4 GHz channel, 11 to 26
+pub fn is_valid_channel(channel: u8) -> bool {
+    (11..=26).contains(&channel)
+}
+
+/// The channel following `channel` in `channels`, bit n set for channel n
+///
+/// Wraps around from channel 26 to channel 11. Returns `channel` if no
+/// channel is set.
+pub fn next_channel(channels: u32, channel: u8) -> u8 {
+    (1..=16)
+        .map(|n| 11 + (channel - 11 + n) % 16)
+        .find(|c| channels & (1 << c) != 0)
+        .unwrap_or(channel)
+}
+
+/// LQI derived from the RSSI, with the energy detect scaling of the radio
+pub fn lqi(rssi: i8) -> u8 {
+    (4 * (i16::from(rssi) + 94)).clamp(0, 255) as u8
+}
+
+/// Metadata sent in front of each captured frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CaptureHeader {
+    /// Time at the start of the frame in microseconds, wraps around after
+    /// about 71 minutes
+    pub timestamp: u32,
+    /// Channel the frame was received on
+    pub channel: u8,
+    /// RSSI in dBm, sampled when the SFD was received
+    pub rssi: i8,
+    /// Link quality, see [`lqi`]
+    pub lqi: u8,
+    /// The CRC of the frame is valid
+    pub crc_ok: bool,
+}
+
+impl CaptureHeader {
+    /// Write the header to the first [`CAPTURE_HEADER_SIZE`] bytes of
+    /// `buffer`
+    pub fn encode(&self, buffer: &mut [u8]) {
+        buffer[..4].copy_from_slice(&self.timestamp.to_le_bytes());
+        buffer[4] = self.channel;
+        buffer[5] = self.rssi as u8;
+        buffer[6] = self.lqi;
+        buffer[7] = if self.crc_ok { CAPTURE_FLAG_CRC_OK } else { 0 };
+    }
+
+    /// Read the header from the start of `buffer`
+    pub fn decode(buffer: &[u8]) -> Option<Self> {
+        if buffer.len() < CAPTURE_HEADER_SIZE {
+            return None;
+        }
+        Some(Self {
+            timestamp: u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]),
+            channel: buffer[4],
+            rssi: buffer[5] as i8,
+            lqi: buffer[6],
+            crc_ok: buffer[7] & CAPTURE_FLAG_CRC_OK != 0,
+        })
+    }
+}
+
+/// Command from the host
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Command<'a> {
+    /// Receive on a channel, stops hopping
+    SetChannel(u8),
+    StartCapture,
+    StopCapture,
+    /// Transmission power in dBm, one of [`TX_POWER_LEVELS`]
+    SetTxPower(i8),
+    /// Report capturing, channel, transmission power and hopping
+    Status,
+    /// Hop across `channels`, bit n set for channel n, staying `dwell`
+    /// milliseconds on each channel
+    StartHopping {
+        channels: u32,
+        dwell: u16,
+    },
+    StopHopping,
+    /// Transmit `frame` on `channel`, optionally with clear channel
+    /// assessment
+    Transmit {
+        channel: u8,
+        cca: bool,
+        frame: &'a [u8],
+    },
+}
+
+impl<'a> Command<'a> {
+    /// Parse a `Command` message, the sequence number followed by the command
+    ///
+    /// Returns the sequence number and the command, or the response to
+    /// acknowledge the message with if the command is unknown or the
+    /// arguments are invalid.
+    pub fn parse_message(message: &'a [u8]) -> Result<(u8, Self), Response> {
+        let (&sequence, command) = message
+            .split_first()
+            .ok_or_else(|| Response::new(0, 0, STATUS_UNKNOWN_COMMAND))?;
+        Self::parse(command)
+            .map(|command| (sequence, command))
+            .map_err(|status| Response::rejected(sequence, command, status))
+    }
+
+    /// Parse a command, the command byte followed by the arguments
+    ///
+    /// Returns the status to acknowledge the command with if it is unknown or
+    /// the arguments are invalid.
+    pub fn parse(message: &'a [u8]) -> Result<Self, u8> {
+        let (&command, arguments) = message.split_first().ok_or(STATUS_UNKNOWN_COMMAND)?;
+        match (command, arguments) {
+            (COMMAND_SET_CHANNEL, &[channel]) if is_valid_channel(channel) => {
+                Ok(Command::SetChannel(channel))
+            }
+            (COMMAND_START_CAPTURE, &[]) => Ok(Command::StartCapture),
+            (COMMAND_STOP_CAPTURE, &[]) => Ok(Command::StopCapture),
+            (COMMAND_SET_TX_POWER, &[power]) if TX_POWER_LEVELS.contains(&(power as i8)) => {
+                Ok(Command::SetTxPower(power as i8))
+            }
+            (COMMAND_STATUS, &[]) => Ok(Command::Status),
+            (COMMAND_START_HOPPING, &[c0, c1, c2, c3, d0, d1]) => {
+                let channels = u32::from_le_bytes([c0, c1, c2, c3]);
+                let dwell = u16::from_le_bytes([d0, d1]);
+                if channels != 0 && channels & !ALL_CHANNELS == 0 && dwell != 0 {
+                    Ok(Command::StartHopping { channels, dwell })
+                } else {
+                    Err(STATUS_INVALID_ARGUMENT)
+                }
+            }
+            (COMMAND_STOP_HOPPING, &[]) => Ok(Command::StopHopping),
+            (COMMAND_TRANSMIT, &[channel, flags, ref frame @ ..])
+                if is_valid_channel(channel)
+                    && !frame.is_empty()
+                    && frame.len() <= MAX_FRAME_SIZE =>
+            {
+                Ok(Command::Transmit {
+                    channel,
+                    cca: flags & TRANSMIT_FLAG_CCA != 0,
+                    frame,
+                })
+            }
+            (COMMAND_SET_CHANNEL, _)
+            | (COMMAND_START_CAPTURE, _)
+            | (COMMAND_STOP_CAPTURE, _)
+            | (COMMAND_SET_TX_POWER, _)
+            | (COMMAND_STATUS, _)
+            | (COMMAND_START_HOPPING, _)
+            | (COMMAND_STOP_HOPPING, _)
+            | (COMMAND_TRANSMIT, _) => Err(STATUS_INVALID_ARGUMENT),
+            _ => Err(STATUS_UNKNOWN_COMMAND),
+        }
+    }
+
+    /// The command byte
+    pub fn id(&self) -> u8 {
+        match self {
+            Command::SetChannel(_) => COMMAND_SET_CHANNEL,
+            Command::StartCapture => COMMAND_START_CAPTURE,
+            Command::StopCapture => COMMAND_STOP_CAPTURE,
+            Command::SetTxPower(_) => COMMAND_SET_TX_POWER,
+            Command::Status => COMMAND_STATUS,
+            Command::StartHopping { .. } => COMMAND_START_HOPPING,
+            Command::StopHopping => COMMAND_STOP_HOPPING,
+            Command::Transmit { .. } => COMMAND_TRANSMIT,
+        }
+    }
+
+    /// Returns true if the command changes the radio channel
+    ///
+    /// The channel is restored when a transmission is done, so these commands
+    /// are rejected with [`STATUS_BUSY`] while a transmission is in progress.
+    pub fn changes_channel(&self) -> bool {
+        matches!(
+            self,
+            Command::SetChannel(_) | Command::StartHopping { .. } | Command::Transmit { .. }
+        )
+    }
+}
+
+/// Content of an `Acknowledge` message, the sequence number and the command
+/// that is acknowledged, a status and any response data
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Response {
+    data: [u8; RESPONSE_SIZE],
+    length: usize,
+}
+
+impl Response {
+    /// Acknowledge command `sequence`, `command`, with `status`
+    pub fn new(sequence: u8, command: u8, status: u8) -> Self {
+        let mut data = [0u8; RESPONSE_SIZE];
+        data[0] = sequence;
+        data[1] = command;
+        data[2] = status;
+        Self { data, length: 3 }
+    }
+
+    /// Acknowledge a command that couldn't be parsed, see [`Command::parse`]
+    pub fn rejected(sequence: u8, message: &[u8], status: u8) -> Self {
+        Self::new(sequence, message.first().copied().unwrap_or(0), status)
+    }
+
+    /// Acknowledge a status command with the state of the listener
+    pub fn status(sequence: u8, capturing: bool, channel: u8, tx_power: i8, hopping: bool) -> Self {
+        let mut response = Self::new(sequence, COMMAND_STATUS, STATUS_OK);
+        response.data[3] = capturing as u8;
+        response.data[4] = channel;
+        response.data[5] = tx_power as u8;
+        response.data[6] = hopping as u8;
+        response.length = 7;
+        response
+    }
+
+    /// Acknowledge the transmit command `sequence` with the outcome of the
+    /// transmission
+    pub fn transmitted(sequence: u8, outcome: u8) -> Self {
+        let mut response = Self::new(sequence, COMMAND_TRANSMIT, STATUS_OK);
+        response.data[3] = outcome;
+        response.length = 4;
+        response
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data[..self.length]
+    }
+}
+
+/// Splits the data received from the host into messages
+///
+/// Each message ends with [`FRAME_DELIMITER`]. A message that doesn't fit the
+/// buffer is dropped up to the next delimiter, so the reader resynchronises
+/// with the host after lost or corrupted bytes.
+pub struct FrameReader {
+    buffer: [u8; HOST_BUFFER_SIZE],
+    length: usize,
+    /// Dropping the rest of a message that didn't fit the buffer
+    discarding: bool,
+}
+
+impl FrameReader {
+    pub fn new() -> Self {
+        Self {
+            buffer: [0u8; HOST_BUFFER_SIZE],
+            length: 0,
+            discarding: false,
+        }
+    }
+
+    /// The free part of the buffer to receive into, see
+    /// [`received`](Self::received)
+    pub fn space(&mut self) -> &mut [u8] {
+        &mut self.buffer[self.length..]
+    }
+
+    /// Add `count` bytes received into [`space`](Self::space)
+    pub fn received(&mut self, count: usize) {
+        self.length = (self.length + count).min(HOST_BUFFER_SIZE);
+    }
+
+    /// Copy the next complete message, including the delimiter, to `frame`
+    ///
+    /// Returns the length of the message, or `None` if no complete message
+    /// has been received. Empty messages and messages that don't fit `frame`
+    /// are dropped.
+    pub fn next_frame(&mut self, frame: &mut [u8]) -> Option<usize> {
+        loop {
+            let end = match self.buffer[..self.length]
+                .iter()
+                .position(|&b| b == FRAME_DELIMITER)
+            {
+                Some(end) => end,
+                None => {
+                    if self.length == HOST_BUFFER_SIZE {
+                        // Too long for a message, drop it up to the next
+                        // delimiter
+                        self.length = 0;
+                        self.discarding = true;
+                    }
+                    return None;
+                }
+            };
+            let length = end + 1;
+            let complete = !self.discarding && end > 0 && length <= frame.len();
+            if complete {
+                frame[..length].copy_from_slice(&self.buffer[..length]);
+            }
+            self.buffer.copy_within(length..self.length, 0);
+            self.length -= length;
+            self.discarding = false;
+            if complete {
+                return Some(length);
+            }
+        }
+    }
+}
+
+impl Default for FrameReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(Command::parse(&[0x01, 11]), Ok(Command::SetChannel(11)));
+        assert_eq!(Command::parse(&[0x01, 26]), Ok(Command::SetChannel(26)));
+        assert_eq!(Command::parse(&[0x02]), Ok(Command::StartCapture));
+        assert_eq!(Command::parse(&[0x03]), Ok(Command::StopCapture));
+        assert_eq!(Command::parse(&[0x04, 0xfc]), Ok(Command::SetTxPower(-4)));
+        assert_eq!(Command::parse(&[0x04, 8]), Ok(Command::SetTxPower(8)));
+        assert_eq!(Command::parse(&[0x05]), Ok(Command::Status));
+        assert_eq!(
+            Command::parse(&[0x06, 0x00, 0xf8, 0xff, 0x07, 0xe8, 0x03]),
+            Ok(Command::StartHopping {
+                channels: ALL_CHANNELS,
+                dwell: 1000
+            })
+        );
+        assert_eq!(Command::parse(&[0x07]), Ok(Command::StopHopping));
+        assert_eq!(
+            Command::parse(&[0x08, 15, 0x01, 0x41, 0x88, 0x01]),
+            Ok(Command::Transmit {
+                channel: 15,
+                cca: true,
+                frame: &[0x41, 0x88, 0x01]
+            })
+        );
+        assert_eq!(
+            Command::parse(&[0x08, 20, 0x00, 0x02]),
+            Ok(Command::Transmit {
+                channel: 20,
+                cca: false,
+                frame: &[0x02]
+            })
+        );
+    }
+
+    #[test]
+    fn parse_id_round_trip() {
+        let messages: [&[u8]; 8] = [
+            &[0x01, 15],
+            &[0x02],
+            &[0x03],
+            &[0x04, 0],
+            &[0x05],
+            &[0x06, 0x00, 0x08, 0x00, 0x00, 0x01, 0x00],
+            &[0x07],
+            &[0x08, 11, 0x00, 0x02],
+        ];
+        for message in messages.iter() {
+            assert_eq!(Command::parse(message).unwrap().id(), message[0]);
+        }
+    }
+
+    #[test]
+    fn commands_changing_channel() {
+        assert!(Command::SetChannel(11).changes_channel());
+        assert!(Command::StartHopping {
+            channels: 0x07ff_f800,
+            dwell: 100
+        }
+        .changes_channel());
+        assert!(Command::parse(&[0x08, 11, 0x00, 0x02])
+            .unwrap()
+            .changes_channel());
+        assert!(!Command::StopHopping.changes_channel());
+        assert!(!Command::SetTxPower(0).changes_channel());
+        assert!(!Command::Status.changes_channel());
+    }
+
+    #[test]
+    fn parse_unknown_command() {
+        assert_eq!(Command::parse(&[]), Err(STATUS_UNKNOWN_COMMAND));
+        assert_eq!(Command::parse(&[0x00]), Err(STATUS_UNKNOWN_COMMAND));
+        assert_eq!(Command::parse(&[0x09, 0x01]), Err(STATUS_UNKNOWN_COMMAND));
+        assert_eq!(Command::parse(&[0xff]), Err(STATUS_UNKNOWN_COMMAND));
+    }
+
+    #[test]
+    fn parse_invalid_arguments() {
+        let messages: [&[u8]; 20] = [
+            // Channel outside the 2.4 GHz band, missing or extra arguments
+            &[0x01, 10],
+            &[0x01, 27],
+            &[0x01],
+            &[0x01, 15, 0],
+            &[0x02, 0],
+            &[0x03, 0],
+            // Unsupported power level
+            &[0x04, 9],
+            &[0x04, 1],
+            &[0x04],
+            &[0x05, 0],
+            // No channels, channels outside 11 to 26, no dwell time
+            &[0x06, 0x00, 0x00, 0x00, 0x00, 0xe8, 0x03],
+            &[0x06, 0x00, 0x04, 0x00, 0x00, 0xe8, 0x03],
+            &[0x06, 0x00, 0x08, 0x00, 0x08, 0xe8, 0x03],
+            &[0x06, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00],
+            &[0x06, 0x00, 0x08, 0x00, 0x00, 0xe8],
+            &[0x07, 0],
+            // Invalid channel, no frame, no flags
+            &[0x08, 10, 0x00, 0x02],
+            &[0x08, 15, 0x00],
+            &[0x08, 15],
+            &[0x08],
+        ];
+        for message in messages.iter() {
+            assert_eq!(
+                Command::parse(message),
+                Err(STATUS_INVALID_ARGUMENT),
+                "{:02x?}",
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn parse_frame_size() {
+        let mut message = [0u8; 3 + MAX_FRAME_SIZE];
+        message[0] = COMMAND_TRANSMIT;
+        message[1] = 11;
+        match Command::parse(&message[..3 + MAX_FRAME_SIZE]) {
+            Ok(Command::Transmit { frame, .. }) => assert_eq!(frame.len(), MAX_FRAME_SIZE),
+            other => panic!("Unexpected {:?}", other),
+        }
+        let mut message = [0u8; 4 + MAX_FRAME_SIZE];
+        message[0] = COMMAND_TRANSMIT;
+        message[1] = 11;
+        assert_eq!(Command::parse(&message), Err(STATUS_INVALID_ARGUMENT));
+    }
+
+    #[test]
+    fn parse_messages() {
+        assert_eq!(
+            Command::parse_message(&[0x2a, 0x01, 11]),
+            Ok((0x2a, Command::SetChannel(11)))
+        );
+        assert_eq!(
+            Command::parse_message(&[0xff, 0x08, 20, 0x00, 0x02]),
+            Ok((
+                0xff,
+                Command::Transmit {
+                    channel: 20,
+                    cca: false,
+                    frame: &[0x02],
+                }
+            ))
+        );
+        // The sequence number is acknowledged with the rejection
+        assert_eq!(
+            Command::parse_message(&[0x2b, 0x01, 10]),
+            Err(Response::new(0x2b, 0x01, STATUS_INVALID_ARGUMENT))
+        );
+        assert_eq!(
+            Command::parse_message(&[0x2c, 0x09]),
+            Err(Response::new(0x2c, 0x09, STATUS_UNKNOWN_COMMAND))
+        );
+        assert_eq!(
+            Command::parse_message(&[0x2d]),
+            Err(Response::new(0x2d, 0x00, STATUS_UNKNOWN_COMMAND))
+        );
+        assert_eq!(
+            Command::parse_message(&[]),
+            Err(Response::new(0x00, 0x00, STATUS_UNKNOWN_COMMAND))
+        );
+    }
+
+    #[test]
+    fn responses() {
+        assert_eq!(
+            Response::new(0x10, 0x01, STATUS_OK).as_bytes(),
+            &[0x10, 0x01, 0x00]
+        );
+        assert_eq!(
+            Response::rejected(0x11, &[0x09, 0x01], STATUS_UNKNOWN_COMMAND).as_bytes(),
+            &[0x11, 0x09, 0x01]
+        );
+        assert_eq!(
+            Response::rejected(0x12, &[], STATUS_UNKNOWN_COMMAND).as_bytes(),
+            &[0x12, 0x00, 0x01]
+        );
+        assert_eq!(
+            Response::status(0x13, true, 15, -4, false).as_bytes(),
+            &[0x13, 0x05, 0x00, 0x01, 15, 0xfc, 0x00]
+        );
+        assert_eq!(
+            Response::transmitted(0x14, TRANSMIT_NO_ACK).as_bytes(),
+            &[0x14, 0x08, 0x00, 0x02]
+        );
+    }
+
+    #[test]
+    fn capture_header() {
+        let header = CaptureHeader {
+            timestamp: 0x1234_5678,
+            channel: 25,
+            rssi: -60,
+            lqi: lqi(-60),
+            crc_ok: true,
+        };
+        let mut buffer = [0u8; CAPTURE_HEADER_SIZE];
+        header.encode(&mut buffer);
+        assert_eq!(buffer, [0x78, 0x56, 0x34, 0x12, 25, 0xc4, 136, 0x01]);
+        assert_eq!(CaptureHeader::decode(&buffer), Some(header));
+
+        let header = CaptureHeader {
+            crc_ok: false,
+            ..header
+        };
+        header.encode(&mut buffer);
+        assert_eq!(buffer[7], 0);
+        assert_eq!(CaptureHeader::decode(&buffer), Some(header));
+        assert_eq!(CaptureHeader::decode(&buffer[..7]), None);
+    }
+
+    #[test]
+    fn lqi_scaling() {
+        assert_eq!(lqi(-100), 0);
+        assert_eq!(lqi(-94), 0);
+        assert_eq!(lqi(-93), 4);
+        assert_eq!(lqi(-60), 136);
+        assert_eq!(lqi(-31), 252);
+        assert_eq!(lqi(-30), 255);
+        assert_eq!(lqi(0), 255);
+    }
+
+    #[test]
+    fn hop_channels() {
+        assert_eq!(next_channel(ALL_CHANNELS, 11), 12);
+        assert_eq!(next_channel(ALL_CHANNELS, 26), 11);
+        let channels = (1 << 15) | (1 << 20) | (1 << 25);
+        assert_eq!(next_channel(channels, 15), 20);
+        assert_eq!(next_channel(channels, 20), 25);
+        assert_eq!(next_channel(channels, 25), 15);
+        // From a channel outside the set
+        assert_eq!(next_channel(channels, 11), 15);
+        assert_eq!(next_channel(channels, 26), 15);
+        assert_eq!(next_channel(1 << 18, 18), 18);
+        assert_eq!(next_channel(0, 13), 13);
+    }
+
+    fn push(reader: &mut FrameReader, data: &[u8]) {
+        let space = reader.space();
+        space[..data.len()].copy_from_slice(data);
+        reader.received(data.len());
+    }
+
+    #[test]
+    fn frame_reader_split() {
+        let mut reader = FrameReader::new();
+        let mut frame = [0u8; HOST_BUFFER_SIZE];
+        assert_eq!(reader.next_frame(&mut frame), None);
+        push(&mut reader, &[0x03, 0x05, 0x01, 0x00, 0x02, 0x06]);
+        assert_eq!(reader.next_frame(&mut frame), Some(4));
+        assert_eq!(frame[..4], [0x03, 0x05, 0x01, 0x00]);
+        assert_eq!(reader.next_frame(&mut frame), None);
+        // The rest of the second message
+        push(&mut reader, &[0x00, 0x00, 0x01, 0x00]);
+        assert_eq!(reader.next_frame(&mut frame), Some(3));
+        assert_eq!(frame[..3], [0x02, 0x06, 0x00]);
+        // Empty messages are skipped
+        assert_eq!(reader.next_frame(&mut frame), Some(2));
+        assert_eq!(frame[..2], [0x01, 0x00]);
+        assert_eq!(reader.next_frame(&mut frame), None);
+    }
+
+    #[test]
+    fn frame_reader_resynchronise() {
+        let mut reader = FrameReader::new();
+        let mut frame = [0u8; HOST_BUFFER_SIZE];
+        // Garbage without a delimiter filling the buffer
+        push(&mut reader, &[0xaa; HOST_BUFFER_SIZE]);
+        assert_eq!(reader.space().len(), 0);
+        assert_eq!(reader.next_frame(&mut frame), None);
+        assert_eq!(reader.space().len(), HOST_BUFFER_SIZE);
+        // The rest of the garbage is dropped up to the delimiter
+        push(&mut reader, &[0xaa, 0xbb, 0x00, 0x02, 0x03, 0x00]);
+        assert_eq!(reader.next_frame(&mut frame), Some(3));
+        assert_eq!(frame[..3], [0x02, 0x03, 0x00]);
+        assert_eq!(reader.next_frame(&mut frame), None);
+    }
+
+    #[test]
+    fn frame_reader_too_long() {
+        let mut reader = FrameReader::new();
+        let mut frame = [0u8; 4];
+        push(
+            &mut reader,
+            &[0x05, 0x01, 0x02, 0x03, 0x04, 0x00, 0x02, 0x07, 0x00],
+        );
+        assert_eq!(reader.next_frame(&mut frame), Some(3));
+        assert_eq!(frame[..3], [0x02, 0x07, 0x00]);
+        assert_eq!(reader.next_frame(&mut frame), None);
+        assert_eq!(reader.space().len(), HOST_BUFFER_SIZE);
+    }
+}
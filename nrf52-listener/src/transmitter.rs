@@ -0,0 +1,171 @@
+//! Transmission of frames supplied by the host
+
+use nrf52840_pac as pac;
+
+use psila_nrf52::radio::Radio;
+
+use crate::listener::start_receive;
+use crate::protocol::{TRANSMIT_CCA_BUSY, TRANSMIT_NO_ACK, TRANSMIT_SENT, TRANSMIT_TIMEOUT};
+
+/// Time to wait for a transmission to finish, in microseconds. The longest
+/// frame takes about 4.3 milliseconds to send, including the CCA
+const SEND_TIMEOUT: u32 = 10_000;
+
+/// Time to wait for an acknowledge, in microseconds. The acknowledge wait
+/// duration is 864 microseconds, with some margin for the interrupt latency
+const ACK_WAIT_DURATION: u32 = 2_000;
+/// CCA control with an energy detect threshold that is never exceeded
+const CCA_ALWAYS_IDLE: u32 = 0xff << 8;
+
+/// Frame control fields of the first byte of a frame
+const FRAME_TYPE_MASK: u8 = 0x07;
+const FRAME_TYPE_ACKNOWLEDGE: u8 = 0x02;
+const FRAME_ACKNOWLEDGE_REQUEST: u8 = 0x20;
+
+enum TransmitState {
+    Idle,
+    Sending { sequence: Option<u8>, started: u32 },
+    AwaitAcknowledge { sequence: u8, sent: u32 },
+    Done(u8),
+}
+
+/// Transmission of a frame supplied by the host
+pub(crate) struct Transmitter {
+    state: TransmitState,
+    channel: u8,
+    cca_control: u32,
+}
+
+impl Transmitter {
+    pub fn new() -> Self {
+        Self {
+            state: TransmitState::Idle,
+            channel: 0,
+            cca_control: 0,
+        }
+    }
+
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, TransmitState::Idle)
+    }
+
+    /// Transmit `frame` on `channel`, the receive channel is restored when
+    /// the transmission is done
+    ///
+    /// Fails if the radio doesn't accept the frame, the receive channel is
+    /// restored before returning.
+    pub fn start(
+        &mut self,
+        radio: &mut Radio,
+        now: u32,
+        channel: u8,
+        cca: bool,
+        frame: &[u8],
+    ) -> Result<(), psila_nrf52::radio::Error> {
+        // Safety: Only changes the CCA control of the radio owned by `Radio`
+        let registers = unsafe { &*pac::RADIO::ptr() };
+        let sequence = if frame.len() >= 3 && frame[0] & FRAME_ACKNOWLEDGE_REQUEST != 0 {
+            Some(frame[2])
+        } else {
+            None
+        };
+        self.channel = radio.get_channel();
+        self.cca_control = registers.ccactrl.read().bits();
+        if !cca {
+            registers
+                .ccactrl
+                .write(|w| unsafe { w.bits(CCA_ALWAYS_IDLE) });
+        }
+        radio.set_channel(channel);
+        if let Err(error) = radio.queue_transmission(frame) {
+            self.restore(radio);
+            return Err(error);
+        }
+        self.state = TransmitState::Sending {
+            sequence,
+            started: now,
+        };
+        Ok(())
+    }
+
+    /// Track the transmission after a radio event, `frame` is the received
+    /// frame, if any, `cca_busy` is set if the radio reported a busy channel
+    ///
+    /// A busy channel only ends the transmission while sending.
+    pub fn on_radio_event(
+        &mut self,
+        radio: &mut Radio,
+        now: u32,
+        frame: Option<&[u8]>,
+        cca_busy: bool,
+    ) {
+        match self.state {
+            TransmitState::Sending { sequence, .. } => {
+                if cca_busy {
+                    self.finish(radio, TRANSMIT_CCA_BUSY);
+                } else if !radio.is_tx_busy() {
+                    match sequence {
+                        Some(sequence) => {
+                            self.state = TransmitState::AwaitAcknowledge {
+                                sequence,
+                                sent: now,
+                            }
+                        }
+                        None => self.finish(radio, TRANSMIT_SENT),
+                    }
+                }
+            }
+            TransmitState::AwaitAcknowledge { sequence, .. } => {
+                if let Some(frame) = frame {
+                    if frame.len() >= 3
+                        && frame[0] & FRAME_TYPE_MASK == FRAME_TYPE_ACKNOWLEDGE
+                        && frame[2] == sequence
+                    {
+                        self.finish(radio, TRANSMIT_SENT);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Take the outcome of a finished transmission
+    pub fn poll(&mut self, radio: &mut Radio, now: u32) -> Option<u8> {
+        match self.state {
+            TransmitState::Sending { started, .. } => {
+                if now.wrapping_sub(started) > SEND_TIMEOUT {
+                    self.finish(radio, TRANSMIT_TIMEOUT);
+                }
+            }
+            TransmitState::AwaitAcknowledge { sent, .. } => {
+                if now.wrapping_sub(sent) > ACK_WAIT_DURATION {
+                    self.finish(radio, TRANSMIT_NO_ACK);
+                }
+            }
+            _ => {}
+        }
+        if let TransmitState::Done(outcome) = self.state {
+            self.state = TransmitState::Idle;
+            Some(outcome)
+        } else {
+            None
+        }
+    }
+
+    fn finish(&mut self, radio: &mut Radio, outcome: u8) {
+        self.restore(radio);
+        self.state = TransmitState::Done(outcome);
+    }
+
+    /// Restore the CCA control and the receive channel
+    fn restore(&self, radio: &mut Radio) {
+        // Safety: Only changes the CCA control of the radio owned by `Radio`
+        unsafe {
+            (*pac::RADIO::ptr())
+                .ccactrl
+                .write(|w| w.bits(self.cca_control))
+        };
+        radio.set_channel(self.channel);
+        start_receive(radio);
+    }
+}
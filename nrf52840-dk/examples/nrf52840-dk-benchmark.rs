@@ -0,0 +1,237 @@
+#![no_main]
+#![no_std]
+
+use nrf52840_dk as _;
+
+use core::fmt::Write;
+
+use cortex_m::peripheral::DWT;
+use cortex_m_rt::entry;
+
+use nrf52840_hal::{clocks, ecb::Ecb, gpio, uarte};
+
+use nrf52840_pac as pac;
+
+use aes::{
+    cipher::{generic_array::GenericArray, BlockEncrypt, NewBlockCipher},
+    Aes128,
+};
+
+use nrf52_benchmark::{LineBuffer, Measurement};
+use nrf52_cryptocell::{
+    AesContext, AesOperationMode, BlockCipher, CryptoBackend, CryptoCell, CryptoCellBackend,
+    EncryptDecrypt, PaddingType,
+};
+
+/// Number of iterations for each measurement
+const ITERATIONS: u32 = 100;
+/// Sizes used for the block cipher measurements
+const BLOCK_SIZES: [usize; 4] = [16, 32, 64, 96];
+/// Sizes used for the CCM* measurements, typical Zigbee frame payloads
+const FRAME_SIZES: [usize; 5] = [8, 16, 32, 64, 96];
+
+const KEY: [u8; 16] = [
+    0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcc, 0xcd, 0xce, 0xcf,
+];
+const NONCE: [u8; 13] = [
+    0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7, 0x03, 0x02, 0x01, 0x00, 0x06,
+];
+const AAD: [u8; 8] = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07];
+const MIC_LENGTH: usize = 4;
+
+/// Run `f` for all iterations and return the number of cycles spent
+fn measure<F: FnMut()>(mut f: F) -> u32 {
+    let start = DWT::get_cycle_count();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    DWT::get_cycle_count().wrapping_sub(start)
+}
+
+fn report(uart: &mut uarte::Uarte<pac::UARTE0>, name: &str, bytes: usize, cycles: u32) {
+    let measurement = Measurement {
+        name,
+        bytes,
+        iterations: ITERATIONS,
+        cycles,
+    };
+    defmt::info!(
+        "{=str},{=str},{=usize},{=u32},{=u32}",
+        nrf52_benchmark::TAG,
+        name,
+        bytes,
+        ITERATIONS,
+        cycles
+    );
+    let mut buffer = [0u8; 64];
+    let mut line = LineBuffer::new(&mut buffer);
+    if writeln!(line, "{}", measurement).is_ok() {
+        let _ = uart.write(line.as_bytes());
+    }
+}
+
+fn aes_context(cryptocell: &CryptoCell, mode: AesOperationMode) -> AesContext {
+    let mut cipher =
+        AesContext::new(cryptocell, EncryptDecrypt::Encrypt, mode, PaddingType::None).unwrap();
+    cipher.set_key(&KEY).unwrap();
+    cipher
+}
+
+#[entry]
+fn main() -> ! {
+    let mut core = pac::CorePeripherals::take().unwrap();
+    let device = pac::Peripherals::take().unwrap();
+
+    core.DCB.enable_trace();
+    core.DWT.enable_cycle_counter();
+
+    let port0 = gpio::p0::Parts::new(device.P0);
+    // Configure to use external clocks, and start them
+    let _clocks = clocks::Clocks::new(device.CLOCK)
+        .enable_ext_hfosc()
+        .set_lfclk_src_external(clocks::LfOscConfiguration::NoExternalNoBypass)
+        .start_lfclk();
+    let mut uart = uarte::Uarte::new(
+        device.UARTE0,
+        uarte::Pins {
+            txd: port0
+                .p0_06
+                .into_push_pull_output(gpio::Level::High)
+                .degrade(),
+            rxd: port0.p0_08.into_floating_input().degrade(),
+            cts: Some(port0.p0_07.into_floating_input().degrade()),
+            rts: Some(
+                port0
+                    .p0_05
+                    .into_push_pull_output(gpio::Level::High)
+                    .degrade(),
+            ),
+        },
+        uarte::Parity::EXCLUDED,
+        uarte::Baudrate::BAUD115200,
+    );
+
+    let cryptocell = CryptoCell::new(device.CRYPTOCELL).unwrap();
+    let mut backend = CryptoCellBackend::new(&cryptocell).unwrap();
+    let mut uncached = CryptoCellBackend::without_key_cache(&cryptocell).unwrap();
+    let mut ecb = Ecb::init(device.ECB);
+    let software = Aes128::new(GenericArray::from_slice(&KEY));
+
+    let input = [0x5au8; 96];
+    let mut output = [0u8; 96];
+
+    defmt::info!("~ benchmark, {=u32} iterations ~", ITERATIONS);
+
+    for &size in BLOCK_SIZES.iter() {
+        let mut cipher = aes_context(&cryptocell, AesOperationMode::Ecb);
+        let cycles = measure(|| {
+            cipher
+                .process_block(&input[..size], &mut output[..size])
+                .unwrap();
+        });
+        report(&mut uart, "cc310-ecb", size, cycles);
+
+        let mut cipher = aes_context(&cryptocell, AesOperationMode::Ctr);
+        cipher.set_iv(&[0u8; 16]).unwrap();
+        let cycles = measure(|| {
+            cipher
+                .process_block(&input[..size], &mut output[..size])
+                .unwrap();
+        });
+        report(&mut uart, "cc310-ctr", size, cycles);
+
+        let mut cipher = aes_context(&cryptocell, AesOperationMode::CbcMac);
+        let mut mac = [0u8; 16];
+        let cycles = measure(|| {
+            // The MAC is a single block, so feed the input a block at a time
+            for block in input[..size].chunks_exact(16) {
+                cipher.process_block(block, &mut mac).unwrap();
+            }
+        });
+        report(&mut uart, "cc310-cbc-mac", size, cycles);
+
+        let cycles = measure(|| {
+            for (i, o) in input[..size]
+                .chunks_exact(16)
+                .zip(output[..size].chunks_exact_mut(16))
+            {
+                let mut block = [0u8; 16];
+                block.copy_from_slice(i);
+                o.copy_from_slice(&ecb.encrypt_block(block, KEY).unwrap());
+            }
+        });
+        report(&mut uart, "nrf-ecb", size, cycles);
+
+        let cycles = measure(|| {
+            for (i, o) in input[..size]
+                .chunks_exact(16)
+                .zip(output[..size].chunks_exact_mut(16))
+            {
+                let mut block = GenericArray::clone_from_slice(i);
+                software.encrypt_block(&mut block);
+                o.copy_from_slice(&block);
+            }
+        });
+        report(&mut uart, "soft-ecb", size, cycles);
+    }
+
+    // The key setup of the two contexts used for CCM*, saved by the key cache
+    let cycles = measure(|| {
+        let _ctr = aes_context(&cryptocell, AesOperationMode::Ctr);
+        let _cbc_mac = aes_context(&cryptocell, AesOperationMode::CbcMac);
+    });
+    report(&mut uart, "cc310-ccm*-key-setup", 0, cycles);
+
+    for &size in FRAME_SIZES.iter() {
+        let mut encrypted = [0u8; 96];
+        let mut mic = [0u8; MIC_LENGTH];
+        let cycles = measure(|| {
+            backend
+                .ccmstar_encrypt(&KEY, &NONCE, &input[..size], &mut mic, &AAD, &mut encrypted)
+                .unwrap();
+        });
+        report(&mut uart, "cc310-ccm*-encrypt", size, cycles);
+
+        let cycles = measure(|| {
+            let length = backend
+                .ccmstar_decrypt(
+                    &KEY,
+                    &NONCE,
+                    &encrypted[..size],
+                    &mic,
+                    &AAD,
+                    &mut output[..size],
+                )
+                .unwrap();
+            assert_eq!(length, size);
+        });
+        report(&mut uart, "cc310-ccm*-decrypt", size, cycles);
+
+        // Without the cached contexts, every frame includes the key setup
+        let cycles = measure(|| {
+            uncached
+                .ccmstar_encrypt(&KEY, &NONCE, &input[..size], &mut mic, &AAD, &mut encrypted)
+                .unwrap();
+        });
+        report(&mut uart, "cc310-ccm*-encrypt-cold", size, cycles);
+
+        let cycles = measure(|| {
+            let length = uncached
+                .ccmstar_decrypt(
+                    &KEY,
+                    &NONCE,
+                    &encrypted[..size],
+                    &mic,
+                    &AAD,
+                    &mut output[..size],
+                )
+                .unwrap();
+            assert_eq!(length, size);
+        });
+        report(&mut uart, "cc310-ccm*-decrypt-cold", size, cycles);
+    }
+
+    defmt::info!("~ benchmark done ~");
+
+    nrf52840_dk::exit()
+}
@@ -0,0 +1,493 @@
+#![no_main]
+#![no_std]
+
+use nrf52840_dk as _;
+
+use cortex_m_rt::entry;
+
+use nrf52840_pac as pac;
+
+use nrf52_cryptocell::{
+    ccm::AesCcm,
+    key_derivation::DerivationKey,
+    key_wrap::KeyWrap,
+    modes::{Cbc, CbcCts, Ofb, Xts},
+    CcmStarInPlace, CryptoCell, CryptoCellBackend, Error, PaddingType,
+};
+
+/// NIST SP800-38A, F.2 and F.4, AES-128
+const SP800_38A_KEY: [u8; 16] = [
+    0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+];
+const SP800_38A_IV: [u8; 16] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+const SP800_38A_PLAINTEXT: [u8; 64] = [
+    0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+    0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+    0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11, 0xe5, 0xfb, 0xc1, 0x19, 0x1a, 0x0a, 0x52, 0xef,
+    0xf6, 0x9f, 0x24, 0x45, 0xdf, 0x4f, 0x9b, 0x17, 0xad, 0x2b, 0x41, 0x7b, 0xe6, 0x6c, 0x37, 0x10,
+];
+const SP800_38A_CBC: [u8; 64] = [
+    0x76, 0x49, 0xab, 0xac, 0x81, 0x19, 0xb2, 0x46, 0xce, 0xe9, 0x8e, 0x9b, 0x12, 0xe9, 0x19, 0x7d,
+    0x50, 0x86, 0xcb, 0x9b, 0x50, 0x72, 0x19, 0xee, 0x95, 0xdb, 0x11, 0x3a, 0x91, 0x76, 0x78, 0xb2,
+    0x73, 0xbe, 0xd6, 0xb8, 0xe3, 0xc1, 0x74, 0x3b, 0x71, 0x16, 0xe6, 0x9e, 0x22, 0x22, 0x95, 0x16,
+    0x3f, 0xf1, 0xca, 0xa1, 0x68, 0x1f, 0xac, 0x09, 0x12, 0x0e, 0xca, 0x30, 0x75, 0x86, 0xe1, 0xa7,
+];
+const SP800_38A_OFB: [u8; 64] = [
+    0x3b, 0x3f, 0xd9, 0x2e, 0xb7, 0x2d, 0xad, 0x20, 0x33, 0x34, 0x49, 0xf8, 0xe8, 0x3c, 0xfb, 0x4a,
+    0x77, 0x89, 0x50, 0x8d, 0x16, 0x91, 0x8f, 0x03, 0xf5, 0x3c, 0x52, 0xda, 0xc5, 0x4e, 0xd8, 0x25,
+    0x97, 0x40, 0x05, 0x1e, 0x9c, 0x5f, 0xec, 0xf6, 0x43, 0x44, 0xf7, 0xa8, 0x22, 0x60, 0xed, 0xcc,
+    0x30, 0x4c, 0x65, 0x28, 0xf6, 0x59, 0xc7, 0x78, 0x66, 0xa5, 0x10, 0xd9, 0xc1, 0xd6, 0xae, 0x5e,
+];
+
+/// IEEE 1619 vector 2, XTS-AES-128, as referenced by NIST SP800-38E
+const XTS_KEY: [u8; 32] = [
+    0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+    0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+];
+const XTS_TWEAK: [u8; 16] = [
+    0x33, 0x33, 0x33, 0x33, 0x33, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+const XTS_PLAINTEXT: [u8; 32] = [0x44; 32];
+const XTS_CIPHERTEXT: [u8; 32] = [
+    0xc4, 0x54, 0x18, 0x5e, 0x6a, 0x16, 0x93, 0x6e, 0x39, 0x33, 0x40, 0x38, 0xac, 0xef, 0x83, 0x8b,
+    0xfb, 0x18, 0x6f, 0xff, 0x74, 0x80, 0xad, 0xc4, 0x28, 0x93, 0x82, 0xec, 0xd6, 0xd3, 0x94, 0xf0,
+];
+
+/// RFC 3394, 4.1 wrap 128 bits of key data with a 128-bit KEK
+const KW_KEK: [u8; 16] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+const KW_KEY_DATA: [u8; 16] = [
+    0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+];
+const KW_WRAPPED: [u8; 24] = [
+    0x1f, 0xa6, 0x8b, 0x0a, 0x81, 0x12, 0xb4, 0x47, 0xae, 0xf3, 0x4b, 0xd8, 0xfb, 0x5a, 0x7b, 0x82,
+    0x9d, 0x3e, 0x86, 0x23, 0x71, 0xd2, 0xcf, 0xe5,
+];
+
+/// RFC 5649, section 6, 192-bit KEK
+const KWP_KEK: [u8; 24] = [
+    0x58, 0x40, 0xdf, 0x6e, 0x29, 0xb0, 0x2a, 0xf1, 0xab, 0x49, 0x3b, 0x70, 0x5b, 0xf1, 0x6e, 0xa1,
+    0xae, 0x83, 0x38, 0xf4, 0xdc, 0xc1, 0x76, 0xa8,
+];
+const KWP_KEY_DATA: [u8; 20] = [
+    0xc3, 0x7b, 0x7e, 0x64, 0x92, 0x58, 0x43, 0x40, 0xbe, 0xd1, 0x22, 0x07, 0x80, 0x89, 0x41, 0x15,
+    0x50, 0x68, 0xf7, 0x38,
+];
+const KWP_WRAPPED: [u8; 32] = [
+    0x13, 0x8b, 0xde, 0xaa, 0x9b, 0x8f, 0xa7, 0xfc, 0x61, 0xf9, 0x77, 0x42, 0xe7, 0x22, 0x48, 0xee,
+    0x5a, 0xe6, 0xae, 0x53, 0x60, 0xd1, 0xae, 0x6a, 0x5f, 0x54, 0xf3, 0x73, 0xfa, 0x54, 0x3b, 0x6a,
+];
+const KWP_SHORT_KEY_DATA: [u8; 7] = [0x46, 0x6f, 0x72, 0x50, 0x61, 0x73, 0x69];
+const KWP_SHORT_WRAPPED: [u8; 16] = [
+    0xaf, 0xbe, 0xb0, 0xf0, 0x7d, 0xfb, 0xf5, 0x41, 0x92, 0x00, 0xf2, 0xcc, 0xb5, 0x0b, 0xb2, 0x4f,
+];
+
+/// AES-CCM vector, the key, nonce, additional data, plaintext, ciphertext and
+/// tag
+struct CcmVector {
+    key: [u8; 16],
+    nonce: &'static [u8],
+    aad: &'static [u8],
+    plaintext: &'static [u8],
+    ciphertext: &'static [u8],
+    tag: &'static [u8],
+}
+
+/// RFC 3610 packet vector #1, NIST SP800-38C appendix C examples 1 to 3, with
+/// 7, 8 and 12 byte nonces, and a 16 byte tag vector computed with the Python
+/// cryptography package
+const CCM_VECTORS: [CcmVector; 5] = [
+    CcmVector {
+        key: [
+            0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcc, 0xcd,
+            0xce, 0xcf,
+        ],
+        nonce: &[
+            0x00, 0x00, 0x00, 0x03, 0x02, 0x01, 0x00, 0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5,
+        ],
+        aad: &[0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07],
+        plaintext: &[
+            0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15,
+            0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e,
+        ],
+        ciphertext: &[
+            0x58, 0x8c, 0x97, 0x9a, 0x61, 0xc6, 0x63, 0xd2, 0xf0, 0x66, 0xd0, 0xc2, 0xc0, 0xf9,
+            0x89, 0x80, 0x6d, 0x5f, 0x6b, 0x61, 0xda, 0xc3, 0x84,
+        ],
+        tag: &[0x17, 0xe8, 0xd1, 0x2c, 0xfd, 0xf9, 0x26, 0xe0],
+    },
+    CcmVector {
+        key: [
+            0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d,
+            0x4e, 0x4f,
+        ],
+        nonce: &[0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16],
+        aad: &[0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07],
+        plaintext: &[0x20, 0x21, 0x22, 0x23],
+        ciphertext: &[0x71, 0x62, 0x01, 0x5b],
+        tag: &[0x4d, 0xac, 0x25, 0x5d],
+    },
+    CcmVector {
+        key: [
+            0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d,
+            0x4e, 0x4f,
+        ],
+        nonce: &[0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17],
+        aad: &[
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f,
+        ],
+        plaintext: &[
+            0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2a, 0x2b, 0x2c, 0x2d,
+            0x2e, 0x2f,
+        ],
+        ciphertext: &[
+            0xd2, 0xa1, 0xf0, 0xe0, 0x51, 0xea, 0x5f, 0x62, 0x08, 0x1a, 0x77, 0x92, 0x07, 0x3d,
+            0x59, 0x3d,
+        ],
+        tag: &[0x1f, 0xc6, 0x4f, 0xbf, 0xac, 0xcd],
+    },
+    CcmVector {
+        key: [
+            0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d,
+            0x4e, 0x4f,
+        ],
+        nonce: &[
+            0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+        ],
+        aad: &[
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13,
+        ],
+        plaintext: &[
+            0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2a, 0x2b, 0x2c, 0x2d,
+            0x2e, 0x2f, 0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37,
+        ],
+        ciphertext: &[
+            0xe3, 0xb2, 0x01, 0xa9, 0xf5, 0xb7, 0x1a, 0x7a, 0x9b, 0x1c, 0xea, 0xec, 0xcd, 0x97,
+            0xe7, 0x0b, 0x61, 0x76, 0xaa, 0xd9, 0xa4, 0x42, 0x8a, 0xa5,
+        ],
+        tag: &[0x48, 0x43, 0x92, 0xfb, 0xc1, 0xb0, 0x99, 0x51],
+    },
+    CcmVector {
+        key: [
+            0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcc, 0xcd,
+            0xce, 0xcf,
+        ],
+        nonce: &[
+            0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7, 0xa8, 0xa9, 0xaa,
+        ],
+        aad: b"header",
+        plaintext: b"CryptoCell AES-CCM, 16 byte tag",
+        ciphertext: &[
+            0x96, 0x46, 0x2f, 0x51, 0x08, 0x1e, 0x82, 0x13, 0x62, 0x82, 0xf7, 0x90, 0xc8, 0x58,
+            0xd7, 0x04, 0xee, 0x43, 0x60, 0x6e, 0x2b, 0xb5, 0xd0, 0x78, 0x3c, 0xe0, 0xd1, 0x48,
+            0x8f, 0x0c, 0x21,
+        ],
+        tag: &[
+            0x54, 0x8d, 0x47, 0x57, 0xf0, 0x75, 0x46, 0x00, 0x84, 0x2a, 0x8d, 0xdb, 0x62, 0x14,
+            0xec, 0x00,
+        ],
+    },
+];
+
+/// CCM* with additional data and payload longer than two blocks, the same
+/// vector as the host tests, computed with the Python cryptography package
+const CCMSTAR_KEY: [u8; 16] = [
+    0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcc, 0xcd, 0xce, 0xcf,
+];
+const CCMSTAR_NONCE: [u8; 13] = [
+    0xac, 0xde, 0x48, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x05,
+];
+const CCMSTAR_AAD: [u8; 40] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+    0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f,
+    0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27,
+];
+const CCMSTAR_PLAINTEXT: [u8; 45] = [
+    0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d, 0x4e, 0x4f,
+    0x50, 0x51, 0x52, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x5b, 0x5c, 0x5d, 0x5e, 0x5f,
+    0x60, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x6b, 0x6c,
+];
+const CCMSTAR_CIPHERTEXT: [u8; 45] = [
+    0x14, 0x45, 0x9c, 0x55, 0x90, 0x2e, 0xe2, 0x96, 0xde, 0x3d, 0x38, 0x31, 0xf4, 0x95, 0xd5, 0x6e,
+    0x8f, 0xfb, 0x0e, 0xd4, 0xf7, 0x96, 0x6c, 0xd5, 0x8c, 0x92, 0xb4, 0xa9, 0xaf, 0x1b, 0xa1, 0xcc,
+    0xb1, 0xec, 0x9e, 0x9f, 0xd3, 0xff, 0x7d, 0xa5, 0x43, 0xab, 0x80, 0xa8, 0xc2,
+];
+const CCMSTAR_MIC: [u8; 8] = [0xbc, 0x97, 0x47, 0x76, 0x22, 0xc0, 0x07, 0x36];
+
+fn check(name: &str, result: &[u8], expected: &[u8]) -> bool {
+    let passed = result == expected;
+    if passed {
+        defmt::info!("{=str} passed", name);
+    } else {
+        defmt::error!("{=str} failed", name);
+        defmt::info!("result   {=[u8]:x}", result);
+        defmt::info!("expected {=[u8]:x}", expected);
+    }
+    passed
+}
+
+/// Root key for the key derivation checks, only for this example. The KDR
+/// keeps it until the next reset.
+const ROOT_KEY: [u8; 16] = [
+    0x52, 0x6f, 0x6f, 0x74, 0x20, 0x6b, 0x65, 0x79, 0x20, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65,
+];
+
+fn differs(name: &str, a: &[u8], b: &[u8]) -> bool {
+    let passed = a != b;
+    if passed {
+        defmt::info!("{=str} passed", name);
+    } else {
+        defmt::error!("{=str} failed, {=[u8]:x}", name, a);
+    }
+    passed
+}
+
+/// Derivation is deterministic and depends on the key, label and context
+fn check_key_derivation(cryptocell: &mut CryptoCell) -> bool {
+    let mut passed = true;
+    let mut derived = [0u8; 16];
+    {
+        let mut backend = CryptoCellBackend::new(cryptocell).unwrap();
+        let result = backend.derive_key(DerivationKey::Root, b"label", &[], &mut derived);
+        passed &= result == Err(Error::NoRootKey);
+    }
+    cryptocell.load_root_key(&ROOT_KEY).unwrap();
+    passed &= cryptocell.load_root_key(&ROOT_KEY) == Err(Error::RootKeyLoaded);
+
+    let mut backend = CryptoCellBackend::new(cryptocell).unwrap();
+    let mut derive = |key: DerivationKey, label: &[u8], context: &[u8]| {
+        let mut derived = [0u8; 16];
+        backend
+            .derive_key(key, label, context, &mut derived)
+            .unwrap();
+        derived
+    };
+    let user = DerivationKey::User(&SP800_38A_KEY);
+    for key in [DerivationKey::Root, user.clone()].iter() {
+        let derived = derive(key.clone(), b"label", b"context");
+        passed &= check(
+            "kdf repeat",
+            &derive(key.clone(), b"label", b"context"),
+            &derived,
+        );
+        passed &= differs(
+            "kdf label",
+            &derive(key.clone(), b"Label", b"context"),
+            &derived,
+        );
+        passed &= differs(
+            "kdf context",
+            &derive(key.clone(), b"label", b"Context"),
+            &derived,
+        );
+        passed &= differs(
+            "kdf no context",
+            &derive(key.clone(), b"label", &[]),
+            &derived,
+        );
+    }
+    passed &= differs(
+        "kdf root and user key",
+        &derive(DerivationKey::Root, b"label", b"context"),
+        &derive(user, b"label", b"context"),
+    );
+    passed
+}
+
+#[entry]
+fn main() -> ! {
+    let device = pac::Peripherals::take().unwrap();
+    let mut cryptocell = CryptoCell::new(device.CRYPTOCELL).unwrap();
+
+    let mut buffer = [0u8; 80];
+    let mut decrypted = [0u8; 80];
+    let mut passed = true;
+
+    defmt::info!("~ cipher modes ~");
+
+    passed &= check_key_derivation(&mut cryptocell);
+
+    let cbc = Cbc::new(&cryptocell, &SP800_38A_KEY, PaddingType::None);
+    let length = cbc
+        .encrypt(&SP800_38A_IV, &SP800_38A_PLAINTEXT, &mut buffer)
+        .unwrap();
+    passed &= check("cbc encrypt", &buffer[..length], &SP800_38A_CBC);
+    let length = cbc
+        .decrypt(&SP800_38A_IV, &SP800_38A_CBC, &mut buffer)
+        .unwrap();
+    passed &= check("cbc decrypt", &buffer[..length], &SP800_38A_PLAINTEXT);
+
+    // With PKCS7 padding the ciphertext is the SP800-38A ciphertext followed
+    // by a block of padding
+    let cbc = Cbc::new(&cryptocell, &SP800_38A_KEY, PaddingType::Pkcs7);
+    let length = cbc
+        .encrypt(&SP800_38A_IV, &SP800_38A_PLAINTEXT, &mut buffer)
+        .unwrap();
+    passed &= length == 80 && check("cbc-pkcs7 encrypt", &buffer[..64], &SP800_38A_CBC);
+    let decrypted_length = cbc
+        .decrypt(&SP800_38A_IV, &buffer[..length], &mut decrypted)
+        .unwrap();
+    passed &= check(
+        "cbc-pkcs7 decrypt",
+        &decrypted[..decrypted_length],
+        &SP800_38A_PLAINTEXT,
+    );
+    for &size in [1usize, 15, 16, 17, 47].iter() {
+        let length = cbc
+            .encrypt(&SP800_38A_IV, &SP800_38A_PLAINTEXT[..size], &mut buffer)
+            .unwrap();
+        let decrypted_length = cbc
+            .decrypt(&SP800_38A_IV, &buffer[..length], &mut decrypted)
+            .unwrap();
+        passed &= check(
+            "cbc-pkcs7 round trip",
+            &decrypted[..decrypted_length],
+            &SP800_38A_PLAINTEXT[..size],
+        );
+    }
+
+    let ofb = Ofb::new(&cryptocell, &SP800_38A_KEY);
+    let length = ofb
+        .encrypt(&SP800_38A_IV, &SP800_38A_PLAINTEXT, &mut buffer)
+        .unwrap();
+    passed &= check("ofb encrypt", &buffer[..length], &SP800_38A_OFB);
+    // Output feedback is a stream, a partial block is a prefix
+    let length = ofb
+        .decrypt(&SP800_38A_IV, &SP800_38A_OFB[..37], &mut buffer)
+        .unwrap();
+    passed &= check("ofb decrypt", &buffer[..length], &SP800_38A_PLAINTEXT[..37]);
+
+    // The first blocks of CBC-CTS are plain CBC, the stealing only affects the
+    // last two blocks
+    let cts = CbcCts::new(&cryptocell, &SP800_38A_KEY);
+    for &size in [16usize, 17, 31, 32, 47, 64].iter() {
+        let length = cts
+            .encrypt(&SP800_38A_IV, &SP800_38A_PLAINTEXT[..size], &mut buffer)
+            .unwrap();
+        let prefix = ((size - 1) / 16).saturating_sub(1) * 16;
+        passed &= check(
+            "cbc-cts prefix",
+            &buffer[..prefix],
+            &SP800_38A_CBC[..prefix],
+        );
+        let length = cts
+            .decrypt(&SP800_38A_IV, &buffer[..length], &mut decrypted)
+            .unwrap();
+        passed &= check(
+            "cbc-cts round trip",
+            &decrypted[..length],
+            &SP800_38A_PLAINTEXT[..size],
+        );
+    }
+
+    let xts = Xts::new(&cryptocell, &XTS_KEY);
+    let length = xts
+        .encrypt(&XTS_TWEAK, &XTS_PLAINTEXT, &mut buffer)
+        .unwrap();
+    passed &= check("xts encrypt", &buffer[..length], &XTS_CIPHERTEXT);
+    let length = xts
+        .decrypt(&XTS_TWEAK, &XTS_CIPHERTEXT, &mut buffer)
+        .unwrap();
+    passed &= check("xts decrypt", &buffer[..length], &XTS_PLAINTEXT);
+
+    let kw = KeyWrap::new(&cryptocell, &KW_KEK);
+    let length = kw.wrap(&KW_KEY_DATA, &mut buffer).unwrap();
+    passed &= check("aes-kw wrap", &buffer[..length], &KW_WRAPPED);
+    let length = kw.unwrap(&KW_WRAPPED, &mut buffer).unwrap();
+    passed &= check("aes-kw unwrap", &buffer[..length], &KW_KEY_DATA);
+    let mut corrupted = KW_WRAPPED;
+    corrupted[0] ^= 0x01;
+    passed &= kw.unwrap(&corrupted, &mut buffer).is_err();
+
+    let kwp = KeyWrap::new(&cryptocell, &KWP_KEK);
+    let length = kwp.wrap_with_padding(&KWP_KEY_DATA, &mut buffer).unwrap();
+    passed &= check("aes-kwp wrap", &buffer[..length], &KWP_WRAPPED);
+    let length = kwp.unwrap_with_padding(&KWP_WRAPPED, &mut buffer).unwrap();
+    passed &= check("aes-kwp unwrap", &buffer[..length], &KWP_KEY_DATA);
+    let length = kwp
+        .wrap_with_padding(&KWP_SHORT_KEY_DATA, &mut buffer)
+        .unwrap();
+    passed &= check("aes-kwp wrap short", &buffer[..length], &KWP_SHORT_WRAPPED);
+    let length = kwp
+        .unwrap_with_padding(&KWP_SHORT_WRAPPED, &mut buffer)
+        .unwrap();
+    passed &= check(
+        "aes-kwp unwrap short",
+        &buffer[..length],
+        &KWP_SHORT_KEY_DATA,
+    );
+
+    let mut tag = [0u8; 16];
+    for vector in CCM_VECTORS.iter() {
+        let size = vector.plaintext.len();
+        let tag = &mut tag[..vector.tag.len()];
+        let ccm = AesCcm::new(&cryptocell, &vector.key);
+        ccm.encrypt(
+            vector.nonce,
+            vector.aad,
+            vector.plaintext,
+            &mut buffer[..size],
+            tag,
+        )
+        .unwrap();
+        passed &= check("ccm encrypt", &buffer[..size], vector.ciphertext);
+        passed &= check("ccm tag", tag, vector.tag);
+        let valid = ccm
+            .decrypt(
+                vector.nonce,
+                vector.aad,
+                vector.ciphertext,
+                &mut decrypted[..size],
+                vector.tag,
+            )
+            .unwrap();
+        passed &= valid && check("ccm decrypt", &decrypted[..size], vector.plaintext);
+        tag[0] ^= 0x01;
+        let valid = ccm
+            .decrypt(
+                vector.nonce,
+                vector.aad,
+                vector.ciphertext,
+                &mut decrypted[..size],
+                tag,
+            )
+            .unwrap();
+        passed &= !valid;
+    }
+
+    // In-place CCM*, the CBC-MAC is fed one block at a time. The second pass
+    // reuses the contexts cached by the first, after they were finished.
+    let mut backend = CryptoCellBackend::new(&cryptocell).unwrap();
+    for _ in 0..2 {
+        let mut data = CCMSTAR_PLAINTEXT;
+        let mut mic = [0u8; 8];
+        backend
+            .ccmstar_encrypt_in_place(
+                &CCMSTAR_KEY,
+                &CCMSTAR_NONCE,
+                &mut data,
+                &mut mic,
+                &CCMSTAR_AAD,
+            )
+            .unwrap();
+        passed &= check("ccm* encrypt", &data, &CCMSTAR_CIPHERTEXT);
+        passed &= check("ccm* mic", &mic, &CCMSTAR_MIC);
+        let valid = backend
+            .ccmstar_decrypt_in_place(&CCMSTAR_KEY, &CCMSTAR_NONCE, &mut data, &mic, &CCMSTAR_AAD)
+            .unwrap();
+        passed &= valid && check("ccm* decrypt", &data, &CCMSTAR_PLAINTEXT);
+    }
+
+    if passed {
+        defmt::info!("All passed");
+    } else {
+        defmt::error!("Failures");
+    }
+
+    nrf52840_dk::exit()
+}
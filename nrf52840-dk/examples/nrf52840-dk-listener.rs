@@ -3,7 +3,7 @@
 
 use nrf52840_dk as _;
 
-use rtic::app;
+use rtic::{app, Mutex};
 
 use bbqueue::{self, BBBuffer, ConstBBBuffer};
 
@@ -11,9 +11,11 @@ use nrf52840_hal::{clocks, gpio, uarte};
 
 use nrf52840_pac as pac;
 
-use psila_nrf52::radio::{Radio, MAX_PACKET_LENGHT};
+use psila_nrf52::radio::MAX_PACKET_LENGHT;
 
-// Use a packet buffer that can hold 16 packages
+use nrf52_listener::{protocol::CAPTURE_HEADER_SIZE, Host, Listener};
+
+// Use a packet buffer that can hold 15 captured packages
 pub(crate) use bbqueue::consts::U2048 as PacketBufferSize;
 
 static PKT_BUFFER: BBBuffer<PacketBufferSize> = BBBuffer(ConstBBBuffer::new());
@@ -21,8 +23,8 @@ static PKT_BUFFER: BBBuffer<PacketBufferSize> = BBBuffer(ConstBBBuffer::new());
 #[app(device = nrf52840_pac, peripherals = true)]
 const APP: () = {
     struct Resources {
-        radio: Radio,
-        uart: uarte::Uarte<pac::UARTE0>,
+        listener: Listener,
+        host: Host,
         rx_producer: bbqueue::Producer<'static, PacketBufferSize>,
         rx_consumer: bbqueue::Consumer<'static, PacketBufferSize>,
     }
@@ -57,69 +59,76 @@ const APP: () = {
 
         let (q_producer, q_consumer) = PKT_BUFFER.try_split().unwrap();
 
-        let mut radio = Radio::new(cx.device.RADIO);
-        radio.set_channel(15);
-        radio.set_transmission_power(8);
-        radio.receive_prepare();
-
         init::LateResources {
-            radio,
-            uart: uarte0,
+            listener: Listener::new(
+                cx.device.RADIO,
+                cx.device.TIMER1,
+                cx.device.TIMER2,
+                &cx.device.PPI,
+            ),
+            host: Host::new(uarte0, cx.device.TIMER0),
             rx_producer: q_producer,
             rx_consumer: q_consumer,
         }
     }
 
-    #[task(binds = RADIO, resources = [radio, rx_producer],)]
+    #[task(binds = RADIO, resources = [listener, rx_producer])]
     fn radio(cx: radio::Context) {
-        let radio = cx.resources.radio;
+        let mut packet = [0u8; MAX_PACKET_LENGHT];
         let queue = cx.resources.rx_producer;
 
-        match queue.grant_exact(MAX_PACKET_LENGHT) {
-            Ok(mut grant) => {
-                if grant.buf().len() < MAX_PACKET_LENGHT {
-                    grant.commit(0);
-                } else {
-                    match radio.receive_slice(grant.buf()) {
-                        Ok(packet_len) => {
-                            grant.commit(packet_len);
-                        }
-                        Err(_) => {}
-                    }
-                }
-            }
-            Err(_) => {
-                // Drop package
-                let mut buffer = [0u8; MAX_PACKET_LENGHT];
-                let _ = radio.receive(&mut buffer);
+        if let Some((header, packet_len)) = cx.resources.listener.receive(&mut packet) {
+            // Drop the package if the queue is full
+            if let Ok(mut grant) = queue.grant_exact(CAPTURE_HEADER_SIZE + packet_len) {
+                let (metadata, data) = grant.buf().split_at_mut(CAPTURE_HEADER_SIZE);
+                header.encode(metadata);
+                data.copy_from_slice(&packet[..packet_len]);
+                grant.commit(CAPTURE_HEADER_SIZE + packet_len);
             }
         }
     }
 
-    #[idle(resources = [rx_consumer, uart])]
-    fn idle(cx: idle::Context) -> ! {
-        let mut host_packet = [0u8; MAX_PACKET_LENGHT * 2];
+    #[task(binds = TIMER2, resources = [listener])]
+    fn hop(cx: hop::Context) {
+        cx.resources.listener.on_hop_timer();
+    }
+
+    #[idle(resources = [listener, host, rx_consumer])]
+    fn idle(mut cx: idle::Context) -> ! {
         let queue = cx.resources.rx_consumer;
-        let uarte = cx.resources.uart;
+        let host = cx.resources.host;
 
         defmt::info!("~ listening ~");
 
         loop {
             if let Ok(grant) = queue.read() {
-                let packet_length = grant[0] as usize;
-                match esercom::com_encode(
-                    esercom::MessageType::RadioReceive,
-                    &grant[1..packet_length],
-                    &mut host_packet,
-                ) {
-                    Ok(written) => {
-                        uarte.write(&host_packet[..written]).unwrap();
-                    }
-                    Err(_) => {
-                        defmt::info!("Failed to encode packet");
-                    }
+                let length = CAPTURE_HEADER_SIZE + grant[CAPTURE_HEADER_SIZE] as usize;
+                if host.send_capture(&grant[..length]).is_err() {
+                    defmt::info!("Failed to send packet");
+                }
+                grant.release(length);
+            }
+
+            if let Some(response) = cx.resources.listener.lock(|listener| listener.poll()) {
+                if host.send_response(&response).is_err() {
+                    defmt::info!("Failed to send acknowledge");
+                }
+            }
+
+            let response = match host.receive() {
+                Some(Ok((sequence, command))) => {
+                    defmt::info!("Command {=u8} {:?}", sequence, command);
+                    cx.resources
+                        .listener
+                        .lock(|listener| listener.execute(sequence, &command))
+                }
+                Some(Err(response)) => Some(response),
+                None => None,
+            };
+            if let Some(response) = response {
+                if host.send_response(&response).is_err() {
+                    defmt::info!("Failed to send acknowledge");
                 }
-                grant.release(packet_length);
             }
         }
     }
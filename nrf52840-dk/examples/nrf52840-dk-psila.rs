@@ -3,7 +3,7 @@
 
 use nrf52840_dk as _;
 
-use rtic::app;
+use rtic::{app, Mutex};
 
 use nrf52840_hal::{clocks, gpio};
 
@@ -13,7 +13,10 @@ use bbqueue::{self, BBBuffer, ConstBBBuffer};
 
 use embedded_hal::digital::v2::OutputPin;
 
-use nrf52_cryptocell::CryptoCellBackend;
+#[cfg(feature = "ecb-backend")]
+use nrf52_cryptocell::EcbBackend;
+#[cfg(not(feature = "ecb-backend"))]
+use nrf52_cryptocell::{CryptoCell, CryptoCellBackend};
 use psila_data::{
     cluster_library::{AttributeDataType, ClusterLibraryStatus},
     device_profile::SimpleDescriptor,
@@ -34,6 +37,13 @@ static TX_BUFFER: BBBuffer<TxBufferSize> = BBBuffer(ConstBBBuffer::new());
 
 const TIMER_SECOND: u32 = 1_000_000;
 
+/// Crypto backend used by the Psila service
+#[cfg(not(feature = "ecb-backend"))]
+type Backend = CryptoCellBackend<'static>;
+/// Crypto backend used by the Psila service
+#[cfg(feature = "ecb-backend")]
+type Backend = EcbBackend;
+
 const MANUFACTURER_NAME: &'static str = "ERIK of Sweden";
 const MODEL_IDENTIFIER: &'static str = "Lampan";
 
@@ -169,7 +179,7 @@ const APP: () = {
     struct Resources {
         timer: pac::TIMER1,
         radio: Radio,
-        service: PsilaService<'static, TxBufferSize, CryptoCellBackend, ClusterHandler>,
+        service: PsilaService<'static, TxBufferSize, Backend, ClusterHandler>,
         rx_producer: bbqueue::Producer<'static, RxBufferSize>,
         rx_consumer: bbqueue::Consumer<'static, RxBufferSize>,
         tx_consumer: bbqueue::Consumer<'static, TxBufferSize>,
@@ -220,14 +230,23 @@ const APP: () = {
         let (rx_producer, rx_consumer) = RX_BUFFER.try_split().unwrap();
         let (tx_producer, tx_consumer) = TX_BUFFER.try_split().unwrap();
 
-        let cryptocell = CryptoCellBackend::new(cx.device.CRYPTOCELL);
+        #[cfg(not(feature = "ecb-backend"))]
+        let backend = {
+            let cryptocell =
+                CryptoCell::new(cx.device.CRYPTOCELL).expect("CryptoCell initialisation failed");
+            let cryptocell = cortex_m::singleton!(: CryptoCell = cryptocell).unwrap();
+            // Don't join a network with a faulty CryptoCell
+            CryptoCellBackend::with_self_test(cryptocell).expect("CryptoCell self-test failed")
+        };
+        #[cfg(feature = "ecb-backend")]
+        let backend = EcbBackend::new(cx.device.ECB);
         let default_link_key = Key::from(DEFAULT_LINK_KEY);
 
         init::LateResources {
             timer: timer1,
             radio,
             service: PsilaService::new(
-                cryptocell,
+                backend,
                 tx_producer,
                 extended_address,
                 default_link_key,
@@ -239,51 +258,35 @@ const APP: () = {
         }
     }
 
-    #[task(binds = TIMER1, resources = [service, timer], spawn = [radio_tx])]
+    // The radio and timer interrupts only queue work for the service. The
+    // service secures and unsecures frames with CCM*, it runs in the lower
+    // priority tasks below, so the radio is never blocked by the CryptoCell.
+
+    #[task(binds = TIMER1, priority = 3, resources = [timer], spawn = [service_update, radio_tx])]
     fn timer(cx: timer::Context) {
         let timer = cx.resources.timer;
-        let service = cx.resources.service;
 
         if timer.is_compare_event(1) {
             timer.ack_compare_event(1);
-            let _ = service.update(timer.now());
+            let _ = cx.spawn.service_update(timer.now());
             timer.fire_in(1, TIMER_SECOND);
         }
         let _ = cx.spawn.radio_tx();
     }
 
-    #[task(binds = RADIO, resources = [radio, service, rx_producer], spawn = [radio_tx])]
+    #[task(binds = RADIO, priority = 3, resources = [radio, rx_producer], spawn = [radio_tx])]
     fn radio(cx: radio::Context) {
         let mut packet = [0u8; MAX_PACKET_LENGHT as usize];
         let radio = cx.resources.radio;
-        let service = cx.resources.service;
         let queue = cx.resources.rx_producer;
 
         match radio.receive(&mut packet) {
             Ok(packet_len) => {
                 if packet_len > 0 {
-                    match service.handle_acknowledge(&packet[1..packet_len - 1]) {
-                        Ok(to_me) => {
-                            if to_me {
-                                if let Ok(mut grant) = queue.grant_exact(packet_len) {
-                                    grant.copy_from_slice(&packet[..packet_len]);
-                                    grant.commit(packet_len);
-                                }
-                            }
-                        }
-                        Err(e) => match e {
-                            psila_service::Error::MalformedPacket => {
-                                defmt::warn!(
-                                    "service handle acknowledge failed, malformed package"
-                                );
-                            }
-                            psila_service::Error::NotEnoughSpace => {
-                                defmt::warn!("service handle acknowledge failed, queue full");
-                            }
-                            _ => {
-                                defmt::warn!("service handle acknowledge failed");
-                            }
-                        },
+                    // Drop the frame if the queue is full
+                    if let Ok(mut grant) = queue.grant_exact(packet_len) {
+                        grant.copy_from_slice(&packet[..packet_len]);
+                        grant.commit(packet_len);
                     }
                 }
             }
@@ -294,24 +297,44 @@ const APP: () = {
         let _ = cx.spawn.radio_tx();
     }
 
+    #[task(resources = [service], spawn = [radio_tx])]
+    fn service_update(cx: service_update::Context, timestamp: u32) {
+        let _ = cx.resources.service.update(timestamp);
+        let _ = cx.spawn.radio_tx();
+    }
+
     #[task(resources = [rx_consumer, service, timer], spawn = [radio_tx])]
-    fn radio_rx(cx: radio_rx::Context) {
+    fn radio_rx(mut cx: radio_rx::Context) {
         let queue = cx.resources.rx_consumer;
         let service = cx.resources.service;
-        let timer = cx.resources.timer;
 
         if let Ok(grant) = queue.read() {
-            let timestamp = timer.now();
+            let timestamp = cx.resources.timer.lock(|timer| timer.now());
             let packet_length = grant[0] as usize;
-            if let Err(_) = service.receive(timestamp, &grant[1..packet_length - 1]) {
-                defmt::warn!("service receive failed");
+            let frame = &grant[1..packet_length - 1];
+            match service.handle_acknowledge(frame) {
+                Ok(true) => {
+                    if service.receive(timestamp, frame).is_err() {
+                        defmt::warn!("service receive failed");
+                    }
+                }
+                Ok(false) => {}
+                Err(psila_service::Error::MalformedPacket) => {
+                    defmt::warn!("service handle acknowledge failed, malformed package");
+                }
+                Err(psila_service::Error::NotEnoughSpace) => {
+                    defmt::warn!("service handle acknowledge failed, queue full");
+                }
+                Err(_) => {
+                    defmt::warn!("service handle acknowledge failed");
+                }
             }
             grant.release(packet_length);
             let _ = cx.spawn.radio_tx();
         }
     }
 
-    #[task(resources = [radio, tx_consumer], spawn = [radio_rx])]
+    #[task(priority = 3, resources = [radio, tx_consumer], spawn = [radio_rx])]
     fn radio_tx(cx: radio_tx::Context) {
         let queue = cx.resources.tx_consumer;
         let radio = cx.resources.radio;
@@ -327,7 +350,18 @@ const APP: () = {
         }
     }
 
+    // Only bound with the interrupt enabled library. The service tasks sleep
+    // while the CryptoCell runs and are woken by this interrupt when the
+    // operation completes, the radio and timer interrupts keep running.
+    #[cfg(feature = "cryptocell-interrupts")]
+    #[task(binds = CRYPTOCELL, priority = 2)]
+    fn cryptocell(_cx: cryptocell::Context) {
+        nrf52_cryptocell::interrupt::on_interrupt();
+    }
+
+    // Dispatchers for the service tasks and the transmit task
     extern "C" {
         fn QDEC();
+        fn SWI0_EGU0();
     }
 };